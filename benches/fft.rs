@@ -0,0 +1,29 @@
+#![feature(test)]
+
+extern crate ndimage;
+extern crate rand;
+extern crate test;
+
+use ndimage::core::padding::Padding;
+use ndimage::core::{ImageBuffer2D, Luma};
+use ndimage::processing::fft::convolve_fft;
+use ndimage::processing::kernel::Kernel;
+use rand::thread_rng;
+use test::Bencher;
+
+const SIZE: u32 = 1024;
+const RADIUS: u32 = 31;
+
+#[bench]
+fn gaussian_blur_direct(b: &mut Bencher) {
+    let img = ImageBuffer2D::<Luma<u8>>::rand(SIZE, SIZE, &mut thread_rng());
+    let kernel = Kernel::<f64>::gaussian(10., RADIUS);
+    b.iter(|| kernel.convolve(&img));
+}
+
+#[bench]
+fn gaussian_blur_fft(b: &mut Bencher) {
+    let img = ImageBuffer2D::<Luma<u8>>::rand(SIZE, SIZE, &mut thread_rng());
+    let kernel = Kernel::<f64>::gaussian(10., RADIUS);
+    b.iter(|| convolve_fft(&img, &kernel, Padding::Zeros));
+}
@@ -0,0 +1,82 @@
+#![feature(test)]
+
+extern crate ndimage;
+extern crate rand;
+extern crate test;
+
+use ndimage::core::{Image2D, Image2DMut, ImageBuffer2D, Luma, Rect};
+use rand::thread_rng;
+use test::Bencher;
+
+const W: u32 = 1920;
+const H: u32 = 1080;
+const RADIUS: u32 = 2;
+
+// Compute the box blurred value of a pixel, ignoring the parts of the window falling outside of the image.
+fn box_blur_pixel(img: &ImageBuffer2D<Luma<u8>>, x: u32, y: u32) -> Luma<u8> {
+    let left = x.saturating_sub(RADIUS);
+    let top = y.saturating_sub(RADIUS);
+    let r = Rect::new(left, top, 2 * RADIUS + 1, 2 * RADIUS + 1)
+        .crop_to_image(img)
+        .unwrap();
+    let n = r.width() * r.height();
+    let sum = img
+        .rect_iter(r)
+        .fold(0u32, |acc, p| acc + u32::from(p.data[0]));
+    Luma::new([(sum / n) as u8])
+}
+
+#[bench]
+fn box_blur_sequential(b: &mut Bencher) {
+    let img = ImageBuffer2D::<Luma<u8>>::rand(W, H, &mut thread_rng());
+    let mut out = ImageBuffer2D::<Luma<u8>>::new(W, H);
+    b.iter(|| {
+        for ((x, y), pix) in out.enumerate_pixels_mut() {
+            *pix = box_blur_pixel(&img, x, y);
+        }
+    });
+}
+
+#[bench]
+fn box_blur_parallel(b: &mut Bencher) {
+    let img = ImageBuffer2D::<Luma<u8>>::rand(W, H, &mut thread_rng());
+    let mut out = ImageBuffer2D::<Luma<u8>>::new(W, H);
+    b.iter(|| {
+        out.par_enumerate_pixels_mut(|(x, y), pix| *pix = box_blur_pixel(&img, x, y));
+    });
+}
+
+// Number of iterations of the Mandelbrot sequence before the point at the given pixel escapes.
+fn mandelbrot(x: u32, y: u32, size: u32) -> Luma<u8> {
+    let c_re = f64::from(x) / f64::from(size) * 3. - 2.;
+    let c_im = f64::from(y) / f64::from(size) * 3. - 1.5;
+    let (mut re, mut im) = (0., 0.);
+    let mut i = 0;
+    while i < 255 && re * re + im * im <= 4. {
+        let tmp = re * re - im * im + c_re;
+        im = 2. * re * im + c_im;
+        re = tmp;
+        i += 1;
+    }
+    Luma::new([i as u8])
+}
+
+const MANDELBROT_SIZE: u32 = 2048;
+
+#[bench]
+fn mandelbrot_sequential(b: &mut Bencher) {
+    b.iter(|| {
+        ImageBuffer2D::generate(MANDELBROT_SIZE, MANDELBROT_SIZE, |(x, y)| {
+            mandelbrot(x, y, MANDELBROT_SIZE)
+        })
+    });
+}
+
+#[bench]
+fn mandelbrot_parallel(b: &mut Bencher) {
+    b.iter(|| {
+        ImageBuffer2D::generate_parallel(MANDELBROT_SIZE, MANDELBROT_SIZE, |(x, y)| {
+            mandelbrot(x, y, MANDELBROT_SIZE)
+        })
+    });
+}
@@ -1,9 +1,85 @@
 //! Definition of the dynamic image type.
 
-use core::{BitDepth, ImageBuffer2D, ImageType, Luma, LumaA, PixelType, Rgb, RgbA};
+use core::{
+    BitDepth, Image2D, ImageBuffer2D, ImageType, Luma, LumaA, Pixel, PixelType, Rect, Rgb, RgbA,
+};
 
 use failure::Error;
 
+use std::fmt;
+
+// Rec. 709 luma coefficients.
+const LUMA_R: f64 = 0.2126;
+const LUMA_G: f64 = 0.7152;
+const LUMA_B: f64 = 0.0722;
+
+// Convert a 16 bit subpixel value to 8 bit by discarding the low byte.
+fn u16_to_u8(v: u16) -> u8 {
+    (v >> 8) as u8
+}
+
+// Compute the luma of an 8 bit RGB triplet.
+fn rgb_to_luma_u8(r: u8, g: u8, b: u8) -> u8 {
+    let l = LUMA_R * f64::from(r) + LUMA_G * f64::from(g) + LUMA_B * f64::from(b);
+    l.round().min(255.) as u8
+}
+
+// Implement the borrowing accessors of a DynamicImage variant.
+macro_rules! impl_accessors {
+    ( $( $variant:ident, $pixel:ty, $desc:expr, $as_ref:ident, $as_mut:ident; )+ ) => {
+        $(
+        #[doc = "Borrow the image as "]
+        #[doc = $desc]
+        #[doc = " image, if it is one."]
+        pub fn $as_ref(&self) -> Option<&ImageBuffer2D<$pixel>> {
+            match self {
+                DynamicImage::$variant(img) => Some(img),
+                _ => None,
+            }
+        }
+
+        #[doc = "Mutably borrow the image as "]
+        #[doc = $desc]
+        #[doc = " image, if it is one."]
+        pub fn $as_mut(&mut self) -> Option<&mut ImageBuffer2D<$pixel>> {
+            match self {
+                DynamicImage::$variant(img) => Some(img),
+                _ => None,
+            }
+        }
+        )+
+    };
+}
+
+// Implement the conversion of a concrete image buffer type into its DynamicImage variant.
+macro_rules! impl_from_buffer {
+    ( $( $variant:ident, $pixel:ty; )+ ) => {
+        $(
+        impl From<ImageBuffer2D<$pixel>> for DynamicImage {
+            fn from(img: ImageBuffer2D<$pixel>) -> DynamicImage {
+                DynamicImage::$variant(Box::new(img))
+            }
+        }
+        )+
+    };
+}
+
+// Apply an expression to the image contained in any variant of a DynamicImage.
+macro_rules! match_variants {
+    ($img:expr, $inner:ident => $e:expr) => {
+        match $img {
+            DynamicImage::LumaU8($inner) => $e,
+            DynamicImage::LumaU16($inner) => $e,
+            DynamicImage::LumaAU8($inner) => $e,
+            DynamicImage::LumaAU16($inner) => $e,
+            DynamicImage::RgbU8($inner) => $e,
+            DynamicImage::RgbU16($inner) => $e,
+            DynamicImage::RgbAU8($inner) => $e,
+            DynamicImage::RgbAU16($inner) => $e,
+        }
+    };
+}
+
 /// Image of dynamic pixel type.
 pub enum DynamicImage {
     /// 8 bit grayscale image.
@@ -86,6 +162,39 @@ impl DynamicImage {
         (self.channels(), self.bit_depth())
     }
 
+    /// Return the width of the image.
+    pub fn width(&self) -> u32 {
+        match_variants!(self, img => img.width())
+    }
+
+    /// Return the height of the image.
+    pub fn height(&self) -> u32 {
+        match_variants!(self, img => img.height())
+    }
+
+    /// Return the dimensions of the image as a `(width, height)` tuple.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match_variants!(self, img => img.dimensions())
+    }
+
+    /// Return a copy of a rectangular region of the image, with the same pixel type.
+    ///
+    /// **Panics** if the specified region crosses image boundaries.
+    pub fn crop(&self, rect: Rect) -> DynamicImage {
+        match_variants!(self, img => DynamicImage::from(img.sub_image(rect).to_owned()))
+    }
+
+    impl_accessors!(
+        LumaU8, Luma<u8>, "an 8 bit grayscale", as_luma_u8_ref, as_luma_u8_mut;
+        LumaU16, Luma<u16>, "a 16 bit grayscale", as_luma_u16_ref, as_luma_u16_mut;
+        LumaAU8, LumaA<u8>, "an 8 bit grayscale with alpha", as_luma_alpha_u8_ref, as_luma_alpha_u8_mut;
+        LumaAU16, LumaA<u16>, "a 16 bit grayscale with alpha", as_luma_alpha_u16_ref, as_luma_alpha_u16_mut;
+        RgbU8, Rgb<u8>, "an 8 bit color", as_rgb_u8_ref, as_rgb_u8_mut;
+        RgbU16, Rgb<u16>, "a 16 bit color", as_rgb_u16_ref, as_rgb_u16_mut;
+        RgbAU8, RgbA<u8>, "an 8 bit color with alpha", as_rgb_alpha_u8_ref, as_rgb_alpha_u8_mut;
+        RgbAU16, RgbA<u16>, "a 16 bit color with alpha", as_rgb_alpha_u16_ref, as_rgb_alpha_u16_mut;
+    );
+
     /// Try extracting the image as an 8 bit grayscale image.
     pub fn into_luma_u8(self) -> Result<Box<ImageBuffer2D<Luma<u8>>>, Error> {
         match self {
@@ -149,4 +258,345 @@ impl DynamicImage {
             _ => bail!("Incorrect image type!"),
         }
     }
+
+    /// Convert the image to 8 bit grayscale. 16 bit images are downscaled to 8 bit, the alpha channel is discarded
+    /// and color images are converted using the Rec. 709 luma coefficients.
+    pub fn to_luma_u8(&self) -> ImageBuffer2D<Luma<u8>> {
+        match self {
+            DynamicImage::LumaU8(img) => Image2D::to_owned(img.as_ref()),
+            DynamicImage::LumaU16(img) => img.map(|p| Luma::new([u16_to_u8(p[0])])),
+            DynamicImage::LumaAU8(img) => img.map(|p| Luma::new([p[0]])),
+            DynamicImage::LumaAU16(img) => img.map(|p| Luma::new([u16_to_u8(p[0])])),
+            DynamicImage::RgbU8(img) => img.map(|p| Luma::new([rgb_to_luma_u8(p[0], p[1], p[2])])),
+            DynamicImage::RgbU16(img) => img.map(|p| {
+                Luma::new([rgb_to_luma_u8(
+                    u16_to_u8(p[0]),
+                    u16_to_u8(p[1]),
+                    u16_to_u8(p[2]),
+                )])
+            }),
+            DynamicImage::RgbAU8(img) => img.map(|p| Luma::new([rgb_to_luma_u8(p[0], p[1], p[2])])),
+            DynamicImage::RgbAU16(img) => img.map(|p| {
+                Luma::new([rgb_to_luma_u8(
+                    u16_to_u8(p[0]),
+                    u16_to_u8(p[1]),
+                    u16_to_u8(p[2]),
+                )])
+            }),
+        }
+    }
+
+    /// Convert the image to 8 bit RGB. 16 bit images are downscaled to 8 bit, the alpha channel is discarded and
+    /// grayscale images have their channel replicated.
+    pub fn to_rgb_u8(&self) -> ImageBuffer2D<Rgb<u8>> {
+        match self {
+            DynamicImage::LumaU8(img) => img.map(|p| Rgb::new([p[0]; 3])),
+            DynamicImage::LumaU16(img) => img.map(|p| Rgb::new([u16_to_u8(p[0]); 3])),
+            DynamicImage::LumaAU8(img) => img.map(|p| Rgb::new([p[0]; 3])),
+            DynamicImage::LumaAU16(img) => img.map(|p| Rgb::new([u16_to_u8(p[0]); 3])),
+            DynamicImage::RgbU8(img) => Image2D::to_owned(img.as_ref()),
+            DynamicImage::RgbU16(img) => {
+                img.map(|p| Rgb::new([u16_to_u8(p[0]), u16_to_u8(p[1]), u16_to_u8(p[2])]))
+            }
+            DynamicImage::RgbAU8(img) => img.map(|p| Rgb::new([p[0], p[1], p[2]])),
+            DynamicImage::RgbAU16(img) => {
+                img.map(|p| Rgb::new([u16_to_u8(p[0]), u16_to_u8(p[1]), u16_to_u8(p[2])]))
+            }
+        }
+    }
+}
+
+/// Operation that can be applied to the image contained in a `DynamicImage`, whatever its pixel type.
+pub trait DynamicVisitor {
+    /// Type of the result of the operation.
+    type Output;
+
+    /// Apply the operation to an image.
+    fn visit<P>(self, img: &ImageBuffer2D<P>) -> Self::Output
+    where
+        P: Pixel;
+}
+
+/// Apply a visitor to the image contained in a `DynamicImage`, so that the same generic code runs over any of its
+/// variants.
+pub fn map_dynamic<F, R>(img: &DynamicImage, f: F) -> R
+where
+    F: DynamicVisitor<Output = R>,
+{
+    match_variants!(img, img => f.visit(img.as_ref()))
+}
+
+impl_from_buffer!(
+    LumaU8, Luma<u8>;
+    LumaU16, Luma<u16>;
+    LumaAU8, LumaA<u8>;
+    LumaAU16, LumaA<u16>;
+    RgbU8, Rgb<u8>;
+    RgbU16, Rgb<u16>;
+    RgbAU8, RgbA<u8>;
+    RgbAU16, RgbA<u16>;
+);
+
+impl Clone for DynamicImage {
+    fn clone(&self) -> DynamicImage {
+        match self {
+            DynamicImage::LumaU8(img) => {
+                DynamicImage::LumaU8(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+            DynamicImage::LumaU16(img) => {
+                DynamicImage::LumaU16(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+            DynamicImage::LumaAU8(img) => {
+                DynamicImage::LumaAU8(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+            DynamicImage::LumaAU16(img) => {
+                DynamicImage::LumaAU16(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+            DynamicImage::RgbU8(img) => {
+                DynamicImage::RgbU8(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+            DynamicImage::RgbU16(img) => {
+                DynamicImage::RgbU16(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+            DynamicImage::RgbAU8(img) => {
+                DynamicImage::RgbAU8(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+            DynamicImage::RgbAU16(img) => {
+                DynamicImage::RgbAU16(Box::new(Image2D::to_owned(img.as_ref())))
+            }
+        }
+    }
+}
+
+impl PartialEq for DynamicImage {
+    /// Images are equal if they have the same type and the same pixels.
+    fn eq(&self, other: &DynamicImage) -> bool {
+        match (self, other) {
+            (DynamicImage::LumaU8(a), DynamicImage::LumaU8(b)) => a == b,
+            (DynamicImage::LumaU16(a), DynamicImage::LumaU16(b)) => a == b,
+            (DynamicImage::LumaAU8(a), DynamicImage::LumaAU8(b)) => a == b,
+            (DynamicImage::LumaAU16(a), DynamicImage::LumaAU16(b)) => a == b,
+            (DynamicImage::RgbU8(a), DynamicImage::RgbU8(b)) => a == b,
+            (DynamicImage::RgbU16(a), DynamicImage::RgbU16(b)) => a == b,
+            (DynamicImage::RgbAU8(a), DynamicImage::RgbAU8(b)) => a == b,
+            (DynamicImage::RgbAU16(a), DynamicImage::RgbAU16(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for DynamicImage {
+    /// Only print the image type and dimensions, not the pixel data.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            DynamicImage::LumaU8(_) => "LumaU8",
+            DynamicImage::LumaU16(_) => "LumaU16",
+            DynamicImage::LumaAU8(_) => "LumaAU8",
+            DynamicImage::LumaAU16(_) => "LumaAU16",
+            DynamicImage::RgbU8(_) => "RgbU8",
+            DynamicImage::RgbU16(_) => "RgbU16",
+            DynamicImage::RgbAU8(_) => "RgbAU8",
+            DynamicImage::RgbAU16(_) => "RgbAU16",
+        };
+        let (w, h) = self.dimensions();
+        write!(f, "{}({}x{})", name, w, h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        map_dynamic, DynamicImage, DynamicVisitor, Image2D, Image2DMut, ImageBuffer2D, Luma, LumaA,
+        Pixel, Rect, Rgb, RgbA,
+    };
+
+    fn all_variants(w: u32, h: u32) -> Vec<DynamicImage> {
+        vec![
+            DynamicImage::LumaU8(Box::new(ImageBuffer2D::new(w, h))),
+            DynamicImage::LumaU16(Box::new(ImageBuffer2D::new(w, h))),
+            DynamicImage::LumaAU8(Box::new(ImageBuffer2D::new(w, h))),
+            DynamicImage::LumaAU16(Box::new(ImageBuffer2D::new(w, h))),
+            DynamicImage::RgbU8(Box::new(ImageBuffer2D::new(w, h))),
+            DynamicImage::RgbU16(Box::new(ImageBuffer2D::new(w, h))),
+            DynamicImage::RgbAU8(Box::new(ImageBuffer2D::new(w, h))),
+            DynamicImage::RgbAU16(Box::new(ImageBuffer2D::new(w, h))),
+        ]
+    }
+
+    #[test]
+    fn test_crop() {
+        for img in all_variants(7, 5) {
+            let cropped = img.crop(Rect::new(2, 1, 4, 3));
+            assert_eq!(cropped.image_type(), img.image_type());
+            assert_eq!(cropped.dimensions(), (4, 3));
+        }
+
+        let img = ImageBuffer2D::generate(4, 4, |(x, y)| Luma::new([(4 * y + x) as u16]));
+        let cropped = DynamicImage::from(img).crop(Rect::new(1, 2, 2, 2));
+        let expected =
+            ImageBuffer2D::generate(2, 2, |(x, y)| Luma::new([(4 * (y + 2) + x + 1) as u16]));
+        assert_eq!(cropped.into_luma_u16().unwrap().as_ref(), &expected);
+    }
+
+    #[test]
+    fn test_to_luma_u8() {
+        for img in all_variants(7, 5) {
+            assert_eq!(img.to_luma_u8().dimensions(), (7, 5));
+        }
+
+        let rgb = DynamicImage::RgbU8(Box::new(
+            ImageBuffer2D::from_vec(
+                3,
+                1,
+                vec![
+                    Rgb::new([255, 0, 0]),
+                    Rgb::new([0, 255, 0]),
+                    Rgb::new([255, 255, 255]),
+                ],
+            )
+            .unwrap(),
+        ));
+        let luma = rgb.to_luma_u8();
+        assert_eq!(luma.get_pixel(0, 0), &Luma::new([54]));
+        assert_eq!(luma.get_pixel(1, 0), &Luma::new([182]));
+        assert_eq!(luma.get_pixel(2, 0), &Luma::new([255]));
+
+        let luma_alpha_u16 = DynamicImage::LumaAU16(Box::new(
+            ImageBuffer2D::from_vec(1, 1, vec![LumaA::new([0xAB12, 0xFFFF])]).unwrap(),
+        ));
+        assert_eq!(
+            luma_alpha_u16.to_luma_u8().get_pixel(0, 0),
+            &Luma::new([0xAB])
+        );
+    }
+
+    #[test]
+    fn test_to_rgb_u8() {
+        for img in all_variants(7, 5) {
+            assert_eq!(img.to_rgb_u8().dimensions(), (7, 5));
+        }
+
+        let rgba_u16 = DynamicImage::RgbAU16(Box::new(
+            ImageBuffer2D::from_vec(1, 1, vec![RgbA::new([0x1000, 0x2000, 0x3000, 0])]).unwrap(),
+        ));
+        assert_eq!(
+            rgba_u16.to_rgb_u8().get_pixel(0, 0),
+            &Rgb::new([0x10, 0x20, 0x30])
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut img = DynamicImage::LumaU8(Box::new(ImageBuffer2D::new(4, 3)));
+        assert!(img.as_rgb_u8_ref().is_none());
+        assert!(img.as_luma_u16_mut().is_none());
+        *img.as_luma_u8_mut().unwrap().get_pixel_mut(2, 1) = Luma::new([42]);
+        assert_eq!(
+            img.as_luma_u8_ref().unwrap().get_pixel(2, 1),
+            &Luma::new([42])
+        );
+
+        let mut img = DynamicImage::RgbAU16(Box::new(ImageBuffer2D::new(4, 3)));
+        img.as_rgb_alpha_u16_mut()
+            .unwrap()
+            .fill(&RgbA::new([1, 2, 3, 4]));
+        assert!(img
+            .as_rgb_alpha_u16_ref()
+            .unwrap()
+            .iter()
+            .all(|p| *p == RgbA::new([1, 2, 3, 4])));
+        assert!(img.as_rgb_alpha_u8_ref().is_none());
+    }
+
+    #[test]
+    fn test_from() {
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([(x + y) as u8]));
+        let dynamic = DynamicImage::from(img.clone());
+        assert_eq!(dynamic.as_luma_u8_ref(), Some(&img));
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([(x * y) as u16 * 1000]));
+        let dynamic = DynamicImage::from(img.clone());
+        assert_eq!(dynamic.as_luma_u16_ref(), Some(&img));
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| LumaA::new([x as u8, y as u8]));
+        let dynamic = DynamicImage::from(img.clone());
+        assert_eq!(dynamic.as_luma_alpha_u8_ref(), Some(&img));
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| LumaA::new([x as u16, 300 * y as u16]));
+        let dynamic = DynamicImage::from(img.clone());
+        assert_eq!(dynamic.as_luma_alpha_u16_ref(), Some(&img));
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Rgb::new([x as u8, y as u8, 7]));
+        let dynamic = DynamicImage::from(img.clone());
+        assert_eq!(dynamic.as_rgb_u8_ref(), Some(&img));
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Rgb::new([x as u16, y as u16, 700]));
+        let dynamic = DynamicImage::from(img.clone());
+        assert_eq!(dynamic.as_rgb_u16_ref(), Some(&img));
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| RgbA::new([x as u8, y as u8, 7, 255]));
+        let dynamic = DynamicImage::from(img.clone());
+        assert_eq!(dynamic.as_rgb_alpha_u8_ref(), Some(&img));
+        assert!(dynamic.as_rgb_u8_ref().is_none());
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| RgbA::new([x as u16, y as u16, 7, 9]));
+        let dynamic: DynamicImage = img.clone().into();
+        assert_eq!(dynamic.as_rgb_alpha_u16_ref(), Some(&img));
+    }
+
+    #[test]
+    fn test_dimensions() {
+        for img in all_variants(7, 5) {
+            assert_eq!(img.width(), 7);
+            assert_eq!(img.height(), 5);
+            assert_eq!(img.dimensions(), (7, 5));
+        }
+        assert!(all_variants(0, 3)
+            .iter()
+            .all(|img| img.dimensions() == (0, 3)));
+    }
+
+    #[test]
+    fn test_clone_eq_debug() {
+        let img = DynamicImage::LumaU8(Box::new(ImageBuffer2D::generate(4, 3, |(x, y)| {
+            Luma::new([(x + y) as u8])
+        })));
+        let mut copy = img.clone();
+        assert_eq!(copy, img);
+        *copy.as_luma_u8_mut().unwrap().get_pixel_mut(0, 0) = Luma::new([99]);
+        assert_ne!(copy, img);
+        assert_eq!(
+            img.as_luma_u8_ref().unwrap().get_pixel(0, 0),
+            &Luma::new([0])
+        );
+
+        let variants = all_variants(7, 5);
+        for (i, a) in variants.iter().enumerate() {
+            for (j, b) in variants.iter().enumerate() {
+                assert_eq!(i == j, a == b);
+            }
+        }
+        assert_eq!(format!("{:?}", img), "LumaU8(4x3)");
+        assert_eq!(format!("{:?}", variants[7]), "RgbAU16(7x5)");
+    }
+
+    #[test]
+    fn test_map_dynamic() {
+        struct Area;
+        impl DynamicVisitor for Area {
+            type Output = u32;
+            fn visit<P: Pixel>(self, img: &ImageBuffer2D<P>) -> u32 {
+                img.width() * img.height()
+            }
+        }
+
+        struct Channels;
+        impl DynamicVisitor for Channels {
+            type Output = u32;
+            fn visit<P: Pixel>(self, _: &ImageBuffer2D<P>) -> u32 {
+                P::N_CHANNELS
+            }
+        }
+
+        let variants = all_variants(7, 5);
+        assert!(variants.iter().all(|img| map_dynamic(img, Area) == 35));
+        let channels: Vec<_> = variants
+            .iter()
+            .map(|img| map_dynamic(img, Channels))
+            .collect();
+        assert_eq!(channels, vec![1, 1, 2, 2, 3, 3, 4, 4]);
+    }
 }
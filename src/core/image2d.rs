@@ -1,18 +1,22 @@
 //! Defines a generic 2D image type.
 #![allow(unknown_lints)]
 
-use core::{Luma, LumaA, Pixel, PixelType, Primitive, Rect, Rgb, RgbA};
+use core::{Luma, LumaA, Pixel, PixelCast, PixelType, Primitive, Rect, Rgb, RgbA};
 
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use failure::Error;
+use helper::generic::{from_f64_clamped, full_intensity, is_integer, source_over};
 use ndarray;
 use ndarray::prelude::*;
 use ndarray::{OwnedRepr, ViewRepr};
-use num_traits::Zero;
+use num_traits::{cast, Bounded, Zero};
 #[cfg(feature = "rand_integration")]
 use rand::{
     distributions::{Distribution, Standard},
     Rng,
 };
+#[cfg(feature = "rayon_integration")]
+use rayon::prelude::*;
 
 use std::cmp::min;
 use std::iter::{DoubleEndedIterator, ExactSizeIterator, IntoIterator};
@@ -27,6 +31,110 @@ pub enum BitDepth {
     _16,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Byte order used to serialize multi-byte values.
+pub enum Endianness {
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Position of an image relative to the canvas it is placed on.
+pub enum Anchor {
+    /// Align the top left corners.
+    TopLeft,
+    /// Align the top edges, centering horizontally.
+    Top,
+    /// Align the top right corners.
+    TopRight,
+    /// Align the left edges, centering vertically.
+    Left,
+    /// Center horizontally and vertically.
+    Center,
+    /// Align the right edges, centering vertically.
+    Right,
+    /// Align the bottom left corners.
+    BottomLeft,
+    /// Align the bottom edges, centering horizontally.
+    Bottom,
+    /// Align the bottom right corners.
+    BottomRight,
+}
+
+impl Anchor {
+    // Return the position of the top left corner of an image of size `size` placed on a canvas of size `canvas`.
+    fn offset(self, size: (u32, u32), canvas: (u32, u32)) -> (i64, i64) {
+        let dx = i64::from(canvas.0) - i64::from(size.0);
+        let dy = i64::from(canvas.1) - i64::from(size.1);
+        let x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => 0,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => dx / 2,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => dx,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => 0,
+            Anchor::Left | Anchor::Center | Anchor::Right => dy / 2,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => dy,
+        };
+        (x, y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Method used to combine source and destination pixels when blending images. Values are normalized from the
+/// `[0, max]` range of the subpixel type for integer types, and are expected to be already normalized for floating
+/// point types. Results are rounded for integer types and clamped to the range of the subpixel type.
+pub enum BlendMode {
+    /// Replace the destination with the source.
+    Copy,
+    /// Add the source to the destination.
+    Add,
+    /// Multiply the source and the destination, which darkens the destination.
+    Multiply,
+    /// Multiply the complements of the source and the destination, which brightens the destination.
+    Screen,
+    /// Composite the source over the destination with the Porter-Duff "source over" operator, like
+    /// `processing::composite::alpha_over`. Only supported by pixel types with an alpha channel, i.e. with 2 or 4
+    /// channels such as `LumaA` and `RgbA`, the last channel being alpha.
+    AlphaOver,
+}
+
+impl BlendMode {
+    // Combine a source and a destination pixel.
+    fn blend<P>(self, src: &P, dst: &P) -> P
+    where
+        P: Pixel,
+    {
+        if self == BlendMode::Copy {
+            return src.clone();
+        }
+        let to_f64 = |v: P::Subpixel| cast::<P::Subpixel, f64>(v).unwrap_or(0.);
+        let max = full_intensity::<P::Subpixel>();
+        let s: Vec<f64> = src.channels().iter().map(|&v| to_f64(v) / max).collect();
+        let d: Vec<f64> = dst.channels().iter().map(|&v| to_f64(v) / max).collect();
+        let mut blended = vec![0.; s.len()];
+        if self == BlendMode::AlphaOver {
+            source_over(&s, &d, &mut blended);
+        } else {
+            for ((b, sv), dv) in blended.iter_mut().zip(&s).zip(&d) {
+                *b = match self {
+                    BlendMode::Add => sv + dv,
+                    BlendMode::Multiply => sv * dv,
+                    BlendMode::Screen => sv + dv - sv * dv,
+                    BlendMode::Copy | BlendMode::AlphaOver => unreachable!(),
+                };
+            }
+        }
+        let mut out = P::zero();
+        for (c, b) in out.channels_mut().iter_mut().zip(blended) {
+            *c = from_f64_clamped(b * max);
+        }
+        out
+    }
+}
+
 /// Type of an image.
 pub type ImageType = (PixelType, BitDepth);
 
@@ -43,6 +151,15 @@ where
     /// **Panics** if the index is out of bounds.
     fn get_pixel(&self, x: u32, y: u32) -> &P;
 
+    /// Return the pixel at the specified coordinates, or `None` if they are out of bounds.
+    fn get_pixel_checked(&self, x: u32, y: u32) -> Option<&P> {
+        if x < self.width() && y < self.height() {
+            Some(self.get_pixel(x, y))
+        } else {
+            None
+        }
+    }
+
     /// Return a view on the image.
     fn get_view(&self) -> Image2DView<P>;
 
@@ -55,9 +172,8 @@ where
         (self.width(), self.height())
     }
 
-    // TODO: map to u32's for coherence
-    /// Return an iterator to the pixels and their indices. The type of the iterator is ((usize, usize), &P)
-    fn enumerate_pixels(&self) -> ndarray::iter::IndexedIter<P, Ix2>;
+    /// Return an iterator over the pixels and their `(x, y)` coordinates, in scanline order.
+    fn enumerate_pixels(&self) -> EnumeratePixels<P>;
 
     /// Return an iterator over the pixels of an image row in left to right order.
     fn row(&self, y: u32) -> Option<RowIter<P>>;
@@ -65,6 +181,14 @@ where
     /// Return an iterator over the rows of an image in scanline order.
     fn rows(&self) -> RowsIter<P>;
 
+    /// Return an iterator over the rows of an image and their `y` coordinate, in scanline order.
+    fn enumerate_rows(&self) -> EnumerateRows<P> {
+        EnumerateRows {
+            iter: self.rows().iter,
+            y: 0,
+        }
+    }
+
     /// Return an iterator over the pixels of an image column in scanline order.
     fn col(&self, x: u32) -> Option<ColIter<P>>;
 
@@ -81,6 +205,20 @@ where
     /// **Panics** if the specified region crosses image boundaries.
     fn rect_iter(&self, rect: Rect) -> RectIter<P>;
 
+    /// Return an iterator over all the `size` shaped views of the image, in scanline order. Only windows that lie
+    /// entirely within the image are yielded, so the iterator is empty if `size` is larger than the image.
+    ///
+    /// **Panics** if a dimension of `size` is zero.
+    fn windows(&self, size: (u32, u32)) -> WindowsIter<P>;
+
+    /// Return an iterator on the part of the specified region that lies within the image, or `None` if they do not
+    /// intersect. Unlike `rect_iter`, this never panics.
+    fn pixels_in_rect(&self, rect: Rect) -> Option<RectIter<P>> {
+        self.rect()
+            .intersection(&rect)
+            .map(|rect| self.rect_iter(rect))
+    }
+
     /// Translate the given `Rect` within the image by the given 2D vector. The parts of the original `Rect` than fall
     /// out of the iamge will be cropped. Return the translated `Rect` if it's not empty, or `None` otherwise.
     fn translate_rect(&self, rect: Rect, x: i64, y: i64) -> Option<Rect> {
@@ -114,6 +252,237 @@ where
 
     /// Return a view over a rectangular region of the image.
     fn sub_image(&self, rect: Rect) -> Image2DView<P>;
+
+    /// Return a view over the transpose of the image, swapping its rows and columns without copying the data.
+    fn transpose(&self) -> Image2DView<P>;
+
+    /// Return an owned, contiguous copy of the transpose of the image.
+    fn transpose_owned(&self) -> ImageBuffer2D<P> {
+        let (w, h) = self.dimensions();
+        ImageBuffer2D::generate(h, w, |(x, y)| self.get_pixel(y, x).clone())
+    }
+
+    /// Sample the image at fractional coordinates using bilinear interpolation. Coordinates falling
+    /// out of the image are clamped to its borders, and the interpolated values are rounded for integer types.
+    ///
+    /// **Panics** if the image is empty.
+    fn sample_bilinear(&self, x: f32, y: f32) -> P {
+        let (w, h) = self.dimensions();
+        assert!(w != 0 && h != 0, "Cannot sample an empty image.");
+        let x = f64::from(x).max(0.).min(f64::from(w - 1));
+        let y = f64::from(y).max(0.).min(f64::from(h - 1));
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = (min(x0 + 1, w - 1), min(y0 + 1, h - 1));
+        let (fx, fy) = (x - f64::from(x0), y - f64::from(y0));
+
+        let (p00, p10) = (self.get_pixel(x0, y0), self.get_pixel(x1, y0));
+        let (p01, p11) = (self.get_pixel(x0, y1), self.get_pixel(x1, y1));
+        let mut out = P::zero();
+        for (c, dst) in out.channels_mut().iter_mut().enumerate() {
+            let v = |p: &P| cast::<P::Subpixel, f64>(p.channels()[c]).unwrap_or(0.);
+            let top = v(p00) * (1. - fx) + v(p10) * fx;
+            let bottom = v(p01) * (1. - fx) + v(p11) * fx;
+            *dst = from_f64_clamped(top * (1. - fy) + bottom * fy);
+        }
+        out
+    }
+
+    /// Sample the image at fractional coordinates using bicubic (Catmull-Rom) interpolation over the 4x4
+    /// neighborhood of the sampled point. Coordinates falling out of the image are clamped to its borders, and the
+    /// interpolated values are rounded for integer types and clamped to the range of the subpixel type.
+    ///
+    /// **Panics** if the image is empty.
+    fn sample_bicubic(&self, x: f32, y: f32) -> P {
+        let (w, h) = self.dimensions();
+        assert!(w != 0 && h != 0, "Cannot sample an empty image.");
+        let x = f64::from(x).max(0.).min(f64::from(w - 1));
+        let y = f64::from(y).max(0.).min(f64::from(h - 1));
+        let (x0, y0) = (x.floor() as i64, y.floor() as i64);
+        let (wx, wy) = (
+            catmull_rom_weights(x - x0 as f64),
+            catmull_rom_weights(y - y0 as f64),
+        );
+        let clamp = |v: i64, max: u32| v.max(0).min(i64::from(max) - 1) as u32;
+
+        let mut acc = vec![0f64; P::N_CHANNELS as usize];
+        for (j, wy) in wy.iter().enumerate() {
+            let sy = clamp(y0 + j as i64 - 1, h);
+            for (i, wx) in wx.iter().enumerate() {
+                let sx = clamp(x0 + i as i64 - 1, w);
+                for (a, c) in acc.iter_mut().zip(self.get_pixel(sx, sy).channels()) {
+                    *a += wx * wy * cast::<P::Subpixel, f64>(*c).unwrap_or(0.);
+                }
+            }
+        }
+        let mut out = P::zero();
+        for (dst, a) in out.channels_mut().iter_mut().zip(acc) {
+            *dst = from_f64_clamped(a);
+        }
+        out
+    }
+
+    /// Return the pixels made of the per-channel minimum and maximum values of the image.
+    ///
+    /// **Panics** if the image is empty.
+    fn min_max(&self) -> (P, P) {
+        let mut iter = self.iter();
+        let first = iter
+            .next()
+            .expect("Cannot compute the extrema of an empty image.");
+        let (mut lo, mut hi) = (first.clone(), first.clone());
+        for p in iter {
+            for ((l, h), c) in lo
+                .channels_mut()
+                .iter_mut()
+                .zip(hi.channels_mut().iter_mut())
+                .zip(p.channels())
+            {
+                if *c < *l {
+                    *l = *c;
+                }
+                if *c > *h {
+                    *h = *c;
+                }
+            }
+        }
+        (lo, hi)
+    }
+
+    /// Return the mean value of each channel of the image. The values are NaN if the image is empty.
+    fn mean(&self) -> Vec<f64> {
+        channel_moments(self).0
+    }
+
+    /// Return the (population) standard deviation of each channel of the image. The values are NaN if the image is
+    /// empty.
+    fn std_dev(&self) -> Vec<f64> {
+        channel_moments(self)
+            .1
+            .iter()
+            .map(|var| var.sqrt())
+            .collect()
+    }
+
+    /// Check whether two images have the same dimensions and all their corresponding channels differ by at most
+    /// `epsilon`. Mostly useful to compare floating point images, for which exact comparisons are too strict.
+    fn approx_eq(&self, other: &Image2D<P>, epsilon: P::Subpixel) -> bool {
+        // Compare on f64, the difference of two channels may not be representable by signed subpixel types.
+        let to_f64 = |v: P::Subpixel| cast::<P::Subpixel, f64>(v).unwrap_or(0.);
+        let epsilon = to_f64(epsilon);
+        self.dimensions() == other.dimensions()
+            && self.iter().zip(other.iter()).all(|(a, b)| {
+                a.channels()
+                    .iter()
+                    .zip(b.channels())
+                    .all(|(&a, &b)| (to_f64(a) - to_f64(b)).abs() <= epsilon)
+            })
+    }
+
+    /// Return the smallest rectangle containing all the pixels of the image that are different from `background`, or
+    /// `None` if the whole image is background. Use with `sub_image` to crop the empty borders of an image.
+    fn content_bounds(&self, background: P) -> Option<Rect> {
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for ((x, y), p) in self.enumerate_pixels() {
+            if *p == background {
+                continue;
+            }
+            bounds = Some(match bounds {
+                None => (x, y, x, y),
+                Some((l, t, r, b)) => (min(l, x), min(t, y), r.max(x), b.max(y)),
+            });
+        }
+        bounds.map(|(l, t, r, b)| Rect::new(l, t, r - l + 1, b - t + 1))
+    }
+
+    /// Return the size in bytes of a row of the image once serialized by `to_bytes`.
+    fn bytes_per_row(&self) -> usize {
+        self.width() as usize * P::N_CHANNELS as usize * ::std::mem::size_of::<P::Subpixel>()
+    }
+
+    /// Serialize the pixels of the image into a tightly packed buffer of interleaved channels in row-major order,
+    /// regardless of the memory layout of the image. Multi-byte subpixels are written in the given byte order.
+    ///
+    /// **Panics** if the subpixel type is wider than 64 bits.
+    fn to_bytes(&self, endianness: Endianness) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.bytes_per_row() * self.height() as usize);
+        for p in self.iter() {
+            for c in p.channels() {
+                write_subpixel(*c, endianness, &mut bytes);
+            }
+        }
+        bytes
+    }
+}
+
+// Append the bytes of a subpixel value to `out` in the given byte order.
+fn write_subpixel<T>(v: T, endianness: Endianness, out: &mut Vec<u8>)
+where
+    T: Primitive,
+{
+    let size = ::std::mem::size_of::<T>();
+    assert!(
+        size <= 8,
+        "Subpixel types wider than 64 bits are not supported."
+    );
+    let bits = if !is_integer::<T>() {
+        match size {
+            4 => u64::from(cast::<T, f32>(v).unwrap().to_bits()),
+            _ => cast::<T, f64>(v).unwrap().to_bits(),
+        }
+    } else if T::min_value() < T::zero() {
+        cast::<T, i64>(v).unwrap() as u64
+    } else {
+        cast::<T, u64>(v).unwrap()
+    };
+    // Keep only the low bytes of the value, which discards the sign extension of negative integers.
+    let bits = if size == 8 {
+        bits
+    } else {
+        bits & ((1 << (8 * size)) - 1)
+    };
+    let mut buf = [0u8; 8];
+    match endianness {
+        Endianness::Little => LittleEndian::write_uint(&mut buf, bits, size),
+        Endianness::Big => BigEndian::write_uint(&mut buf, bits, size),
+    }
+    out.extend_from_slice(&buf[..size]);
+}
+
+// Compute the per-channel mean and variance of an image in a single pass with Welford's algorithm.
+fn channel_moments<P, I>(img: &I) -> (Vec<f64>, Vec<f64>)
+where
+    P: Pixel,
+    I: Image2D<P> + ?Sized,
+{
+    let n_channels = P::N_CHANNELS as usize;
+    let (mut mean, mut m2) = (vec![0.; n_channels], vec![0.; n_channels]);
+    let mut n = 0.;
+    for p in img.iter() {
+        n += 1.;
+        for (c, v) in p.channels().iter().enumerate() {
+            let v = cast::<P::Subpixel, f64>(*v).unwrap_or(0.);
+            let delta = v - mean[c];
+            mean[c] += delta / n;
+            m2[c] += delta * (v - mean[c]);
+        }
+    }
+    if n == 0. {
+        let nan = vec![::std::f64::NAN; n_channels];
+        return (nan.clone(), nan);
+    }
+    (mean, m2.iter().map(|m| m / n).collect())
+}
+
+// Return the weights of the Catmull-Rom cubic kernel for the 4 samples at offsets -1, 0, 1 and 2 from the integer
+// part of a coordinate whose fractional part is `t`.
+fn catmull_rom_weights(t: f64) -> [f64; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        (-t3 + 2. * t2 - t) / 2.,
+        (3. * t3 - 5. * t2 + 2.) / 2.,
+        (-3. * t3 + 4. * t2 + t) / 2.,
+        (t3 - t2) / 2.,
+    ]
 }
 
 impl<'a, P> IntoIterator for &'a Image2D<P>
@@ -253,9 +622,29 @@ where
     /// **Panics** if the index is out of bounds.
     fn put_pixel(&mut self, x: u32, y: u32, pixel: P);
 
-    // TODO: map to u32's for coherence
-    /// Return an iterator to the pixels and their indices. The type of the iterator is ((usize, usize), &mut P)
-    fn enumerate_pixels_mut(&mut self) -> ndarray::iter::IndexedIterMut<P, Ix2>;
+    /// Return a mutable reference to the requested pixel, or `None` if the index is out of bounds.
+    fn get_pixel_mut_checked(&mut self, x: u32, y: u32) -> Option<&mut P> {
+        if x < self.width() && y < self.height() {
+            Some(self.get_pixel_mut(x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Set the pixel at the specified coordinates to the specified value if they are within bounds. Return whether
+    /// the pixel was set.
+    fn put_pixel_checked(&mut self, x: u32, y: u32, pixel: P) -> bool {
+        match self.get_pixel_mut_checked(x, y) {
+            Some(p) => {
+                *p = pixel;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return a mutable iterator over the pixels and their `(x, y)` coordinates, in scanline order.
+    fn enumerate_pixels_mut(&mut self) -> EnumeratePixelsMut<P>;
 
     /// Return an iterator over the pixels of an image row in left to right order.
     fn row_mut(&mut self, y: u32) -> Option<RowIterMut<P>>;
@@ -263,6 +652,14 @@ where
     /// Return a mutable iterator over the rows of an image in scanline order.
     fn rows_mut(&mut self) -> RowsIterMut<P>;
 
+    /// Return a mutable iterator over the rows of an image and their `y` coordinate, in scanline order.
+    fn enumerate_rows_mut(&mut self) -> EnumerateRowsMut<P> {
+        EnumerateRowsMut {
+            iter: self.rows_mut().iter,
+            y: 0,
+        }
+    }
+
     /// Return an iterator over the pixels of an image column in scanline order.
     fn col_mut(&mut self, x: u32) -> Option<ColIterMut<P>>;
 
@@ -286,6 +683,20 @@ where
 
     /// Blit (i.e. copy) a `Rect` from the source image onto the destination image.
     fn blit_rect(&mut self, src_rect: Rect, dst_rect: Rect, img: &Image2D<P>) -> Result<(), Error>
+    where
+        Self: ::std::marker::Sized,
+    {
+        self.blit_rect_blend(src_rect, dst_rect, img, BlendMode::Copy)
+    }
+
+    /// Blend a `Rect` from the source image onto the destination image with the given blend mode.
+    fn blit_rect_blend(
+        &mut self,
+        src_rect: Rect,
+        dst_rect: Rect,
+        img: &Image2D<P>,
+        mode: BlendMode,
+    ) -> Result<(), Error>
     where
         Self: ::std::marker::Sized,
     {
@@ -301,6 +712,9 @@ where
             );
         }
 
+        if mode == BlendMode::AlphaOver && P::N_CHANNELS != 2 && P::N_CHANNELS != 4 {
+            bail!("Alpha blending requires a pixel type with an alpha channel.");
+        }
         if !src_rect.fits_image(img) {
             bail!("Source rect does not fit source image.");
         }
@@ -309,11 +723,54 @@ where
         }
 
         for (src_pixel, dst_pixel) in img.rect_iter(src_rect).zip(self.rect_iter_mut(dst_rect)) {
-            *dst_pixel = src_pixel.clone();
+            *dst_pixel = mode.blend(src_pixel, dst_pixel);
         }
         Ok(())
     }
 
+    /// Copy `img` onto the image with its top left corner at the given, possibly negative, coordinates. The parts of
+    /// `img` that fall out of the image are ignored.
+    fn paste(&mut self, img: &Image2D<P>, at: (i32, i32)) {
+        if img.width() == 0 || img.height() == 0 {
+            return;
+        }
+        let (x, y) = (i64::from(at.0), i64::from(at.1));
+        if let Some(dst_rect) = self.translate_rect(img.rect(), x, y) {
+            let src_rect = Rect::new(
+                (i64::from(dst_rect.left()) - x) as u32,
+                (i64::from(dst_rect.top()) - y) as u32,
+                dst_rect.width(),
+                dst_rect.height(),
+            );
+            for (src_pixel, dst_pixel) in img.rect_iter(src_rect).zip(self.rect_iter_mut(dst_rect))
+            {
+                *dst_pixel = src_pixel.clone();
+            }
+        }
+    }
+
+    /// Apply a function to every pixel of the image in place.
+    fn apply<F>(&mut self, f: F)
+    where
+        Self: ::std::marker::Sized,
+        F: Fn(&mut P),
+    {
+        for pixel in self.iter_mut() {
+            f(pixel);
+        }
+    }
+
+    /// Apply a function to every pixel of the image in place, along with its `(x, y)` coordinates.
+    fn apply_at<F>(&mut self, f: F)
+    where
+        Self: ::std::marker::Sized,
+        F: Fn((u32, u32), &mut P),
+    {
+        for (coords, pixel) in self.enumerate_pixels_mut() {
+            f(coords, pixel);
+        }
+    }
+
     /// Return a mutable Iterator on the image pixels.
     fn iter_mut(&mut self) -> IterMut<P>;
 
@@ -401,9 +858,10 @@ where
         }
     }
 
-    // TODO: map to u32's for coherence
-    fn enumerate_pixels(&self) -> ndarray::iter::IndexedIter<P, Ix2> {
-        self.buffer.indexed_iter()
+    fn enumerate_pixels(&self) -> EnumeratePixels<P> {
+        EnumeratePixels {
+            iter: self.buffer.indexed_iter(),
+        }
     }
 
     fn row(&self, y: u32) -> Option<RowIter<P>> {
@@ -449,6 +907,18 @@ where
         }
     }
 
+    fn windows(&self, size: (u32, u32)) -> WindowsIter<P> {
+        let (w, h) = self.dimensions();
+        let len = (w + 1).saturating_sub(size.0) as usize * (h + 1).saturating_sub(size.1) as usize;
+        WindowsIter {
+            iter: self
+                .buffer
+                .windows((size.1 as usize, size.0 as usize))
+                .into_iter(),
+            len,
+        }
+    }
+
     fn iter(&self) -> Iter<P> {
         self.buffer.into_iter()
     }
@@ -467,6 +937,12 @@ where
             ]),
         }
     }
+
+    fn transpose(&self) -> Image2DView<P> {
+        Image2DRepr {
+            buffer: self.buffer.t(),
+        }
+    }
 }
 
 impl<D, P> Index<(u32, u32)> for Image2DRepr<D, P>
@@ -494,8 +970,10 @@ where
         self.buffer[[y as usize, x as usize]] = pixel;
     }
 
-    fn enumerate_pixels_mut(&mut self) -> ndarray::iter::IndexedIterMut<P, Ix2> {
-        self.buffer.indexed_iter_mut()
+    fn enumerate_pixels_mut(&mut self) -> EnumeratePixelsMut<P> {
+        EnumeratePixelsMut {
+            iter: self.buffer.indexed_iter_mut(),
+        }
     }
 
     fn row_mut(&mut self, y: u32) -> Option<RowIterMut<P>> {
@@ -586,6 +1064,24 @@ where
     }
 }
 
+impl<P> IntoIterator for ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    type Item = P;
+    type IntoIter = ::std::vec::IntoIter<P>;
+
+    /// Consume the image and return an iterator over its pixels by value, in scanline order.
+    fn into_iter(self) -> Self::IntoIter {
+        let v = if self.buffer.is_standard_layout() {
+            self.buffer.into_raw_vec()
+        } else {
+            self.buffer.iter().cloned().collect()
+        };
+        v.into_iter()
+    }
+}
+
 impl<'a, D, P> IntoIterator for &'a mut Image2DRepr<D, P>
 where
     P: Pixel + 'a,
@@ -599,6 +1095,137 @@ where
     }
 }
 
+impl<D, P> Image2DRepr<D, P>
+where
+    P: Pixel,
+    D: ndarray::Data<Elem = P>,
+{
+    /// Return an owned copy of the image. Same as `Image2D::to_owned`, which can't be called with method syntax on
+    /// `ImageBuffer2D` when `Image2D` is in scope since it conflicts with the blanket `ToOwned` implementation.
+    pub fn to_owned(&self) -> ImageBuffer2D<P> {
+        Image2D::to_owned(self)
+    }
+
+    /// Create a new image by applying a function to each pixel of the image. The resulting image can have a different
+    /// pixel type.
+    pub fn map<Q, F>(&self, f: F) -> ImageBuffer2D<Q>
+    where
+        Q: Pixel,
+        F: Fn(&P) -> Q,
+    {
+        ImageBuffer2D {
+            buffer: self.buffer.map(f),
+        }
+    }
+
+    /// Return a copy of the image with every pixel cast to the `O` subpixel type using `PixelCast`. Values that can't
+    /// be represented by `O` are set to zero.
+    pub fn cast<O>(&self) -> ImageBuffer2D<<P as PixelCast<O>>::Output>
+    where
+        P: PixelCast<O>,
+        O: Primitive,
+    {
+        self.map(PixelCast::<O>::cast)
+    }
+
+    /// Return the number of pixels of the image matching a predicate.
+    pub fn count_where<F>(&self, pred: F) -> usize
+    where
+        F: Fn(&P) -> bool,
+    {
+        self.iter().filter(|p| pred(p)).count()
+    }
+
+    /// Return an ndarray view of the image pixels. The array is indexed by `[y, x]`, i.e. its rows are the image rows.
+    pub fn as_array_view(&self) -> ArrayView2<P> {
+        self.buffer.view()
+    }
+
+    /// Return a copy of the image placed on a canvas of a different size without resampling it. The image is
+    /// positioned on the canvas according to `anchor`, the parts of the image falling out of the canvas are cropped
+    /// and the parts of the canvas not covered by the image are set to `fill`.
+    pub fn resize_canvas(
+        &self,
+        new_w: u32,
+        new_h: u32,
+        anchor: Anchor,
+        fill: P,
+    ) -> ImageBuffer2D<P> {
+        let mut canvas = ImageBuffer2D::solid(new_w, new_h, fill);
+        if new_w != 0 && new_h != 0 {
+            let (x, y) = anchor.offset(self.dimensions(), (new_w, new_h));
+            canvas.paste(self, (x as i32, y as i32));
+        }
+        canvas
+    }
+
+    /// Return the `(x, y)` coordinates of the pixels of the image matching a predicate, in scanline order.
+    pub fn find_pixels<F>(&self, pred: F) -> Vec<(u32, u32)>
+    where
+        F: Fn(&P) -> bool,
+    {
+        self.enumerate_pixels()
+            .filter(|(_, p)| pred(p))
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+}
+
+impl<D, P> Image2DRepr<D, P>
+where
+    P: Pixel,
+    D: ndarray::DataMut<Elem = P>,
+{
+    /// Return a mutable ndarray view of the image pixels. The array is indexed by `[y, x]`, i.e. its rows are the image
+    /// rows.
+    pub fn as_array_view_mut(&mut self) -> ArrayViewMut2<P> {
+        self.buffer.view_mut()
+    }
+}
+
+// Characters used by `to_ascii_art`, from dark to bright.
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+impl<D> Image2DRepr<D, Luma<u8>>
+where
+    D: ndarray::Data<Elem = Luma<u8>>,
+{
+    /// Render the image as ASCII art `width` characters wide, for quick inspection in a terminal. The image is
+    /// downsampled by averaging blocks of pixels twice as tall as they are wide to account for the aspect ratio of
+    /// characters, and brightness is mapped to the ` .:-=+*#%@` ramp. Each line of the result ends with a newline.
+    pub fn to_ascii_art(&self, width: u32) -> String {
+        let (w, h) = self.dimensions();
+        if width == 0 || w == 0 || h == 0 {
+            return String::new();
+        }
+        let (w, h, width) = (u64::from(w), u64::from(h), u64::from(width));
+        let height = ((h * width + w) / (2 * w)).max(1);
+        let bounds = |i: u64, n: u64, size: u64| {
+            let start = i * size / n;
+            (start, ((i + 1) * size / n).max(start + 1))
+        };
+        let mut out = String::with_capacity(((width + 1) * height) as usize);
+        for cy in 0..height {
+            let (top, bottom) = bounds(cy, height, h);
+            for cx in 0..width {
+                let (left, right) = bounds(cx, width, w);
+                let rect = Rect::new(
+                    left as u32,
+                    top as u32,
+                    (right - left) as u32,
+                    (bottom - top) as u32,
+                );
+                let sum: u64 = self.rect_iter(rect).map(|p| u64::from(p[0])).sum();
+                let mean = sum as f64 / ((right - left) * (bottom - top)) as f64;
+                let idx = (mean / 255. * (ASCII_RAMP.len() - 1) as f64).round() as usize;
+                out.push(ASCII_RAMP[idx] as char);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
 /// Owned 2D image representation.
 pub type ImageBuffer2D<P> = Image2DRepr<ndarray::OwnedRepr<P>, P>;
 /// Borrowed 2D image representation.
@@ -606,6 +1233,17 @@ pub type Image2DView<'a, P> = Image2DRepr<ndarray::ViewRepr<&'a P>, P>;
 /// Mutably borrowed 2D image representation.
 pub type Image2DViewMut<'a, P> = Image2DRepr<ndarray::ViewRepr<&'a mut P>, P>;
 
+impl<P> Clone for ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    fn clone(&self) -> ImageBuffer2D<P> {
+        ImageBuffer2D {
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
 // Type of ndarray iterators.
 type Iter<'a, P> = ndarray::iter::Iter<'a, P, Ix2>;
 type IterMut<'a, P> = ndarray::iter::IterMut<'a, P, Ix2>;
@@ -629,6 +1267,17 @@ where
         self.buffer.into_raw_vec()
     }
 
+    /// Create a new image from an ndarray array without copying it. The array is indexed by `[y, x]`, i.e. its rows
+    /// become the image rows.
+    pub fn from_array(arr: Array2<P>) -> ImageBuffer2D<P> {
+        ImageBuffer2D { buffer: arr }
+    }
+
+    /// Consume self and return the underlying ndarray array, indexed by `[y, x]`.
+    pub fn into_array(self) -> Array2<P> {
+        self.buffer
+    }
+
     /// Create a new image of specified dimensions from a `Vec` of the specified pixel type.
     ///
     /// **Error**: `InvalidDimensions` if the dimensions do not match the length of `v`.
@@ -656,6 +1305,53 @@ where
         Ok(ImageBuffer2D { buffer: buf })
     }
 
+    /// Create a new image of specified dimensions from a buffer of subpixels whose rows may be padded, such as the
+    /// frames received from cameras. Each row starts `row_stride` subpixels after the previous one, which is a number
+    /// of bytes for 8 bit subpixels. The padding of the last row may be omitted. `channels` is the number of channels
+    /// per pixel in the buffer.
+    ///
+    /// **Error**: if `channels` is not the number of channels of `P`, `row_stride` is smaller than a row, or the
+    /// buffer is too small.
+    pub fn from_raw_vec_with_stride(
+        w: u32,
+        h: u32,
+        channels: u32,
+        row_stride: usize,
+        v: &[P::Subpixel],
+    ) -> Result<ImageBuffer2D<P>, Error> {
+        ensure!(
+            channels == P::N_CHANNELS,
+            "Buffer has {} channels, expected {}.",
+            channels,
+            P::N_CHANNELS
+        );
+        let row_len = w as usize * channels as usize;
+        ensure!(
+            row_stride >= row_len,
+            "Row stride {} is smaller than the row length {}.",
+            row_stride,
+            row_len
+        );
+        let size = if h == 0 {
+            0
+        } else {
+            (h as usize - 1) * row_stride + row_len
+        };
+        ensure!(
+            v.len() >= size,
+            "Buffer has incorrect size {}, expected at least {}.",
+            v.len(),
+            size
+        );
+        let mut v_pixels = Vec::with_capacity(w as usize * h as usize);
+        for y in 0..h as usize {
+            let row = &v[y * row_stride..y * row_stride + row_len];
+            v_pixels.extend(row.chunks(channels as usize).map(P::from_slice));
+        }
+        let buf = try!(Array2::from_shape_vec((h as usize, w as usize), v_pixels));
+        Ok(ImageBuffer2D { buffer: buf })
+    }
+
     /// Generate a new image from a closure that will be called with the index of each pixel.
     pub fn generate<F>(w: u32, h: u32, mut f: F) -> ImageBuffer2D<P>
     where
@@ -667,6 +1363,40 @@ where
             }),
         }
     }
+
+    /// Create a new image of specified dimensions filled with the given color.
+    pub fn solid(w: u32, h: u32, color: P) -> ImageBuffer2D<P> {
+        ImageBuffer2D {
+            buffer: Array2::from_elem((h as usize, w as usize), color),
+        }
+    }
+
+    /// Create a checkerboard pattern made of square tiles of side `tile` alternating between the colors `a` and `b`,
+    /// starting with `a` in the top left corner.
+    ///
+    /// # Panics
+    ///
+    /// If `tile` is 0.
+    pub fn checkerboard(w: u32, h: u32, tile: u32, a: P, b: P) -> ImageBuffer2D<P> {
+        assert!(tile > 0, "Checkerboard tile size must be non zero");
+        ImageBuffer2D::generate(w, h, |(x, y)| {
+            if (x / tile + y / tile) % 2 == 0 {
+                a.clone()
+            } else {
+                b.clone()
+            }
+        })
+    }
+
+    /// Create a horizontal gradient going from zero on the left column to the maximum value of the subpixel type on
+    /// the right column, every channel being set to the same value.
+    pub fn horizontal_gradient(w: u32, h: u32) -> ImageBuffer2D<P> {
+        let max = cast::<P::Subpixel, f64>(P::Subpixel::max_value()).unwrap();
+        let step = if w > 1 { max / f64::from(w - 1) } else { 0. };
+        ImageBuffer2D::generate(w, h, |(x, _)| {
+            P::from_value(cast(f64::from(x) * step).unwrap_or_else(P::Subpixel::max_value))
+        })
+    }
 }
 
 #[cfg(feature = "rand_integration")]
@@ -698,6 +1428,58 @@ where
     }
 }
 
+#[cfg(feature = "rayon_integration")]
+impl<P> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    /// Parallel version of `generate`, distributing the image rows across threads.
+    pub fn generate_parallel<F>(w: u32, h: u32, f: F) -> ImageBuffer2D<P>
+    where
+        F: Fn((u32, u32)) -> P + Sync,
+    {
+        let mut img = ImageBuffer2D::new(w, h);
+        img.par_enumerate_pixels_mut(|pos, pix| *pix = f(pos));
+        img
+    }
+
+    /// Call a closure on every pixel and its `(x, y)` coordinates, distributing the image rows across threads.
+    pub fn par_enumerate_pixels_mut<F>(&mut self, f: F)
+    where
+        F: Fn((u32, u32), &mut P) + Sync + Send,
+    {
+        let w = self.width() as usize;
+        if w == 0 {
+            return;
+        }
+        match self.buffer.as_slice_mut() {
+            Some(slice) => slice.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+                for (x, pix) in row.iter_mut().enumerate() {
+                    f((x as u32, y as u32), pix);
+                }
+            }),
+            None => {
+                for ((y, x), pix) in self.buffer.indexed_iter_mut() {
+                    f((x as u32, y as u32), pix);
+                }
+            }
+        }
+    }
+
+    /// Parallel version of `map`.
+    pub fn par_map<Q, F>(&self, f: F) -> ImageBuffer2D<Q>
+    where
+        Q: Pixel,
+        F: Fn(&P) -> Q + Sync + Send,
+    {
+        let v: Vec<Q> = match self.as_slice() {
+            Some(slice) => slice.par_iter().map(f).collect(),
+            None => self.iter().map(f).collect(),
+        };
+        ImageBuffer2D::from_vec(self.width(), self.height(), v).unwrap()
+    }
+}
+
 macro_rules! impl_iterators {
     ( $( $(#[$attr:meta])* $name:ident: $t:ty;)+ ) => {
         $(
@@ -776,12 +1558,134 @@ impl_double_ended_iterators!(
     ColsIterMut: ndarray::iter::AxisIterMut<'a, P, Ix1>
 );
 
-/// Discard the alpha component of an `RgbA` image.
-pub fn rgba_to_rgb<P>(img: &Image2D<RgbA<P>>) -> ImageBuffer2D<Rgb<P>>
+/// Iterator over the fixed size sub-views of an image. Created by `Image2D`'s `windows` method.
+pub struct WindowsIter<'a, P>
 where
-    P: Primitive,
+    P: Pixel + 'a,
 {
-    let mut res = ImageBuffer2D::<Rgb<P>>::new(img.width(), img.height());
+    iter: <ndarray::iter::Windows<'a, P, Ix2> as IntoIterator>::IntoIter,
+    len: usize,
+}
+
+impl<'a, P> Iterator for WindowsIter<'a, P>
+where
+    P: Pixel + 'a,
+{
+    type Item = Image2DView<'a, P>;
+
+    fn next(&mut self) -> Option<Image2DView<'a, P>> {
+        let buffer = self.iter.next()?;
+        self.len -= 1;
+        Some(Image2DRepr { buffer })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, P> ExactSizeIterator for WindowsIter<'a, P> where P: Pixel + 'a {}
+
+// Implement the iterators over the pixels and their coordinates, converting ndarray's `(row, column)` indices to
+// `(x, y)` coordinates.
+macro_rules! impl_enumerate_iterators {
+    ( $( $(#[$attr:meta])* $name:ident: $t:ty, $item:ty;)+ ) => {
+        $(
+        $( #[$attr] )*
+        pub struct $name<'a, P>
+            where P: Pixel + 'a
+        {
+            iter: $t
+        }
+
+        impl<'a, P> Iterator for $name<'a, P>
+            where P: Pixel + 'a
+        {
+            type Item = ((u32, u32), $item);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.iter.next().map(|((y, x), p)| ((x as u32, y as u32), p))
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.size_hint()
+            }
+        }
+
+        impl<'a, P> ExactSizeIterator for $name<'a, P>
+            where P: Pixel + 'a
+        {
+            fn len(&self) -> usize {
+                self.iter.len()
+            }
+        }
+        )+
+    };
+}
+
+impl_enumerate_iterators!(
+    /// Iterator over the pixels of an image and their `(x, y)` coordinates. Created by `Image2D`'s `enumerate_pixels`
+    /// method.
+    EnumeratePixels: ndarray::iter::IndexedIter<'a, P, Ix2>, &'a P;
+    /// Mutable iterator over the pixels of an image and their `(x, y)` coordinates. Created by `Image2DMut`'s
+    /// `enumerate_pixels_mut` method.
+    EnumeratePixelsMut: ndarray::iter::IndexedIterMut<'a, P, Ix2>, &'a mut P;
+);
+
+// Implement the iterators over the rows of an image and their `y` coordinate.
+macro_rules! impl_enumerate_rows_iterators {
+    ( $( $(#[$attr:meta])* $name:ident: $t:ty, $row:ident;)+ ) => {
+        $(
+        $( #[$attr] )*
+        pub struct $name<'a, P>
+            where P: Pixel + 'a
+        {
+            iter: $t,
+            y: u32,
+        }
+
+        impl<'a, P> Iterator for $name<'a, P>
+            where P: Pixel + 'a
+        {
+            type Item = (u32, $row<'a, P>);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let row = self.iter.next()?;
+                let y = self.y;
+                self.y += 1;
+                Some((y, $row { iter: row.into_iter() }))
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.size_hint()
+            }
+        }
+
+        impl<'a, P> ExactSizeIterator for $name<'a, P>
+            where P: Pixel + 'a
+        {
+            fn len(&self) -> usize {
+                self.iter.len()
+            }
+        }
+        )+
+    };
+}
+
+impl_enumerate_rows_iterators!(
+    /// Iterator over the rows of an image and their `y` coordinate. Created by `Image2D`'s `enumerate_rows` method.
+    EnumerateRows: ndarray::iter::AxisIter<'a, P, Ix1>, RowIter;
+    /// Mutable iterator over the rows of an image and their `y` coordinate. Created by `Image2DMut`'s
+    /// `enumerate_rows_mut` method.
+    EnumerateRowsMut: ndarray::iter::AxisIterMut<'a, P, Ix1>, RowIterMut;
+);
+
+/// Discard the alpha component of an `RgbA` image.
+pub fn rgba_to_rgb<P>(img: &Image2D<RgbA<P>>) -> ImageBuffer2D<Rgb<P>>
+where
+    P: Primitive,
+{
+    let mut res = ImageBuffer2D::<Rgb<P>>::new(img.width(), img.height());
     for (src_pixel, dst_pixel) in img.into_iter().zip((&mut res).into_iter()) {
         *dst_pixel = src_pixel.into();
     }
@@ -800,9 +1704,152 @@ where
     res
 }
 
+/// Convert a `Luma` image to `Rgb` by replicating the luminance channel.
+pub fn luma_to_rgb<P>(img: &Image2D<Luma<P>>) -> ImageBuffer2D<Rgb<P>>
+where
+    P: Primitive,
+{
+    let mut res = ImageBuffer2D::<Rgb<P>>::new(img.width(), img.height());
+    for (src_pixel, dst_pixel) in img.into_iter().zip((&mut res).into_iter()) {
+        *dst_pixel = src_pixel.into();
+    }
+    res
+}
+
+/// Convert a `LumaA` image to `RgbA` by replicating the luminance channel.
+pub fn luma_alpha_to_rgba<P>(img: &Image2D<LumaA<P>>) -> ImageBuffer2D<RgbA<P>>
+where
+    P: Primitive,
+{
+    let mut res = ImageBuffer2D::<RgbA<P>>::new(img.width(), img.height());
+    for (src_pixel, dst_pixel) in img.into_iter().zip((&mut res).into_iter()) {
+        *dst_pixel = src_pixel.into();
+    }
+    res
+}
+
+/// Add an opaque alpha channel to an `Rgb` image, with a value of `P::max_value()` for integer types and 1 for floating
+/// point types.
+pub fn rgb_to_rgba<P>(img: &Image2D<Rgb<P>>) -> ImageBuffer2D<RgbA<P>>
+where
+    P: Primitive,
+{
+    let mut res = ImageBuffer2D::<RgbA<P>>::new(img.width(), img.height());
+    for (src_pixel, dst_pixel) in img.into_iter().zip((&mut res).into_iter()) {
+        *dst_pixel = src_pixel.into();
+    }
+    res
+}
+
+// Extract channel `c` of an image as a grayscale image.
+fn extract_channel<P>(img: &Image2D<P>, c: usize) -> ImageBuffer2D<Luma<P::Subpixel>>
+where
+    P: Pixel,
+{
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        Luma::new([img.get_pixel(x, y).channels()[c]])
+    })
+}
+
+// Build an image whose channels are taken from a list of grayscale images, one for each channel.
+fn merge<P>(channels: &[&Image2D<Luma<P::Subpixel>>]) -> Result<ImageBuffer2D<P>, Error>
+where
+    P: Pixel,
+{
+    let dims = channels[0].dimensions();
+    for c in channels {
+        ensure!(
+            c.dimensions() == dims,
+            "Image dimensions do not match: {:?} and {:?}",
+            dims,
+            c.dimensions()
+        );
+    }
+    let (w, h) = dims;
+    Ok(ImageBuffer2D::generate(w, h, |(x, y)| {
+        let mut p = P::zero();
+        for (dst, c) in p.channels_mut().iter_mut().zip(channels) {
+            *dst = c.get_pixel(x, y)[0];
+        }
+        p
+    }))
+}
+
+/// Split an `Rgb` image into 3 grayscale images containing its red, green and blue channels.
+pub fn split_channels<P>(
+    img: &Image2D<Rgb<P>>,
+) -> (
+    ImageBuffer2D<Luma<P>>,
+    ImageBuffer2D<Luma<P>>,
+    ImageBuffer2D<Luma<P>>,
+)
+where
+    P: Primitive,
+{
+    (
+        extract_channel(img, 0),
+        extract_channel(img, 1),
+        extract_channel(img, 2),
+    )
+}
+
+/// Merge 3 grayscale images containing the red, green and blue channels of an image into an `Rgb` image.
+///
+/// **Error**: if the images have different dimensions.
+pub fn merge_channels<P>(
+    r: &Image2D<Luma<P>>,
+    g: &Image2D<Luma<P>>,
+    b: &Image2D<Luma<P>>,
+) -> Result<ImageBuffer2D<Rgb<P>>, Error>
+where
+    P: Primitive,
+{
+    merge(&[r, g, b])
+}
+
+/// Split an `RgbA` image into 4 grayscale images containing its red, green, blue and alpha channels.
+pub fn split_channels_alpha<P>(
+    img: &Image2D<RgbA<P>>,
+) -> (
+    ImageBuffer2D<Luma<P>>,
+    ImageBuffer2D<Luma<P>>,
+    ImageBuffer2D<Luma<P>>,
+    ImageBuffer2D<Luma<P>>,
+)
+where
+    P: Primitive,
+{
+    (
+        extract_channel(img, 0),
+        extract_channel(img, 1),
+        extract_channel(img, 2),
+        extract_channel(img, 3),
+    )
+}
+
+/// Merge 4 grayscale images containing the red, green, blue and alpha channels of an image into an `RgbA` image.
+///
+/// **Error**: if the images have different dimensions.
+pub fn merge_channels_alpha<P>(
+    r: &Image2D<Luma<P>>,
+    g: &Image2D<Luma<P>>,
+    b: &Image2D<Luma<P>>,
+    a: &Image2D<Luma<P>>,
+) -> Result<ImageBuffer2D<RgbA<P>>, Error>
+where
+    P: Primitive,
+{
+    merge(&[r, g, b, a])
+}
+
 #[cfg(test)]
 mod tests {
-    use core::{Image2D, Image2DMut, ImageBuffer2D, Luma, Pixel, Rect, Region};
+    use core::{
+        luma_alpha_to_rgba, luma_to_rgb, merge_channels, merge_channels_alpha, rgb_to_rgba,
+        split_channels, split_channels_alpha, Anchor, BlendMode, Endianness, Image2D, Image2DMut,
+        ImageBuffer2D, Luma, LumaA, Pixel, Rect, Region, Rgb, RgbA,
+    };
 
     use num_traits::Zero;
     #[cfg(feature = "rand_integration")]
@@ -832,6 +1879,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_raw_vec_with_stride() {
+        // 3x2 RGB image with rows padded to 12 bytes.
+        let v = [
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, //
+            10, 11, 12, 13, 14, 15, 16, 17, 18, 0, 0, 0, //
+        ];
+        let img = ImageBuffer2D::<Rgb<u8>>::from_raw_vec_with_stride(3, 2, 3, 12, &v).unwrap();
+        let tight: Vec<u8> = (1..19).collect();
+        assert_eq!(img, ImageBuffer2D::from_raw_vec(3, 2, &tight).unwrap());
+        // The padding of the last row can be omitted.
+        let img2 =
+            ImageBuffer2D::<Rgb<u8>>::from_raw_vec_with_stride(3, 2, 3, 12, &v[..21]).unwrap();
+        assert_eq!(img2, img);
+        let img3 = ImageBuffer2D::<Rgb<u8>>::from_raw_vec_with_stride(3, 2, 3, 9, &tight).unwrap();
+        assert_eq!(img3, img);
+
+        assert!(ImageBuffer2D::<Rgb<u8>>::from_raw_vec_with_stride(3, 2, 3, 12, &v[..20]).is_err());
+        assert!(ImageBuffer2D::<Rgb<u8>>::from_raw_vec_with_stride(3, 2, 3, 8, &v).is_err());
+        assert!(ImageBuffer2D::<RgbA<u8>>::from_raw_vec_with_stride(3, 2, 3, 12, &v).is_err());
+        let empty = ImageBuffer2D::<Luma<u8>>::from_raw_vec_with_stride(4, 0, 1, 4, &[]).unwrap();
+        assert_eq!(empty.dimensions(), (4, 0));
+    }
+
     #[test]
     fn test_new() {
         fn test_zeros_helper<P>(w: u32, h: u32)
@@ -848,12 +1919,67 @@ mod tests {
         test_zeros_helper::<Luma<f32>>(100, 200);
     }
 
+    #[test]
+    fn test_clone() {
+        #[derive(Clone)]
+        struct Holder {
+            img: ImageBuffer2D<Rgb<u8>>,
+        }
+
+        let holder = Holder {
+            img: ImageBuffer2D::generate(4, 3, |(x, y)| Rgb::new([x as u8, y as u8, 7])),
+        };
+        let mut cloned = holder.clone();
+        assert_eq!(cloned.img, holder.img);
+        cloned.img.put_pixel(1, 2, Rgb::new([9, 9, 9]));
+        assert_ne!(cloned.img, holder.img);
+        assert_eq!(holder.img.get_pixel(1, 2), &Rgb::new([1, 2, 7]));
+
+        // `to_owned` stays callable with method syntax despite `ToOwned` being implemented.
+        let owned = holder.img.to_owned();
+        assert_eq!(owned, holder.img);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut img = ImageBuffer2D::generate(5, 4, |(x, y)| Luma::new([(x + y) as u8 + 1]));
+        img.apply(|p| p.data[0] = 0);
+        assert_eq!(img, ImageBuffer2D::new(5, 4));
+
+        img.apply_at(|(x, y), p| p.data[0] = (10 * x + y) as u8);
+        assert!(img
+            .enumerate_pixels()
+            .all(|((x, y), p)| p[0] == (10 * x + y) as u8));
+
+        img.sub_image_mut(Rect::new(1, 1, 2, 2))
+            .apply_at(|(x, y), p| p.data[0] = 100 + (x + 2 * y) as u8);
+        assert_eq!(img.get_pixel(2, 2), &Luma::new([103]));
+        assert_eq!(img.get_pixel(3, 2), &Luma::new([32]));
+    }
+
+    #[test]
+    fn test_to_ascii_art() {
+        let white = ImageBuffer2D::solid(40, 20, Luma::new([255u8]));
+        let art = white.to_ascii_art(10);
+        assert_eq!(art, "@@@@@@@@@@\n".repeat(3));
+
+        let img = ImageBuffer2D::generate(20, 4, |(x, _)| Luma::new([(x * 255 / 19) as u8]));
+        assert_eq!(img.to_ascii_art(20), "  ..::--==++**##%%@@\n".repeat(2));
+        assert_eq!(img.sub_image(Rect::new(0, 0, 2, 2)).to_ascii_art(1), " \n");
+        assert_eq!(img.to_ascii_art(0), "");
+        // Upsampling repeats pixels.
+        assert_eq!(
+            ImageBuffer2D::solid(1, 1, Luma::new([0u8])).to_ascii_art(3),
+            "   \n".repeat(2)
+        );
+    }
+
     #[test]
     fn test_into_iter() {
         let v: Vec<Luma<u8>> = (1..10).map(Luma::from).collect();
         let img = ImageBuffer2D::from_vec(3, 3, v.clone()).unwrap();
 
-        for (p, i) in img.into_iter().zip(v.into_iter()) {
+        for (p, i) in (&img).into_iter().zip(v.into_iter()) {
             assert!(&i == p);
         }
     }
@@ -862,12 +1988,26 @@ mod tests {
     fn test_enumerate_pixels() {
         let img = ImageBuffer2D::generate(5, 3, |(x, y)| Luma::from((2 * x + 3 * y) as u8));
 
+        for ((x, y), p) in img.enumerate_pixels() {
+            assert_eq!((2 * x + 3 * y) as u8, p.channels()[0]);
+            assert_eq!(img.get_pixel(x, y), p);
+        }
+        let coords: Vec<(u32, u32)> = img.enumerate_pixels().map(|(pos, _)| pos).collect();
+        assert_eq!(coords.len(), 15);
+        assert_eq!(
+            &coords[..6],
+            &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (0, 1)]
+        );
+
+        let mut img = img;
         for ((x, y), p) in img
-            .enumerate_pixels()
-            .map(|((y, x), p)| ((x, y), p.channels()[0]))
+            .sub_image_mut(Rect::new(1, 1, 4, 2))
+            .enumerate_pixels_mut()
         {
-            assert_eq!((2 * x + 3 * y) as u8, p);
+            p.data[0] = (10 * x + y) as u8;
         }
+        assert_eq!(img.get_pixel(4, 1), &Luma::new([30]));
+        assert_eq!(img.get_pixel(1, 2), &Luma::new([1]));
     }
 
     #[test]
@@ -973,6 +2113,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_enumerate_rows() {
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([(10 * y + x) as u8]));
+        let mut v = Vec::new();
+        for (y, row) in img.enumerate_rows() {
+            assert_eq!(row.len(), 4);
+            for (x, pix) in row.enumerate() {
+                assert_eq!(pix[0], (10 * y + x as u32) as u8);
+                v.push(pix[0]);
+            }
+        }
+        assert_eq!(img.enumerate_rows().len(), 3);
+        assert_eq!(ImageBuffer2D::from_raw_vec(4, 3, &v).unwrap(), img);
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(4, 3);
+        for (y, row) in img.enumerate_rows_mut() {
+            for pix in row {
+                pix.data[0] = y as u8;
+            }
+        }
+        assert!(img.enumerate_pixels().all(|((_, y), p)| p[0] == y as u8));
+        let mut view = img.sub_image_mut(Rect::new(1, 1, 2, 2));
+        let ys: Vec<_> = view.enumerate_rows_mut().map(|(y, _)| y).collect();
+        assert_eq!(ys, vec![0, 1]);
+    }
+
     #[test]
     fn test_col() {
         let v = [0, 1, 2, 3, 4, 5, 6, 7, 8];
@@ -1101,7 +2267,7 @@ mod tests {
         let r = Rect::new(1, 1, 3, 3);
         img.fill_rect(r, &Luma::<u8>::new([255]));
         for ((x, y), &pixel) in img.enumerate_pixels() {
-            if r.contains(x as u32, y as u32) {
+            if r.contains(x, y) {
                 assert_eq!(pixel, Luma::<u8>::new([255]));
             } else {
                 assert_eq!(pixel, Luma::<u8>::new([0]));
@@ -1119,6 +2285,62 @@ mod tests {
         assert_eq!(img1, img2);
     }
 
+    #[test]
+    fn test_blit_rect_blend() {
+        let half = ImageBuffer2D::solid(4, 4, Luma::new([128u8]));
+        let mut img = ImageBuffer2D::solid(4, 4, Luma::new([127u8]));
+        let r = Rect::new(0, 0, 4, 4);
+        img.blit_rect_blend(r, r, &half, BlendMode::Add).unwrap();
+        assert!(img.iter().all(|p| p[0] == 255));
+        img.blit_rect_blend(r, r, &half, BlendMode::Add).unwrap();
+        assert!(img.iter().all(|p| p[0] == 255));
+
+        let src = ImageBuffer2D::generate(3, 2, |(x, y)| Rgb::new([x as u8 * 50, y as u8, 200]));
+        let white = ImageBuffer2D::solid(3, 2, Rgb::new([255u8; 3]));
+        let mut dst = white.clone();
+        let r = Rect::new(0, 0, 3, 2);
+        dst.blit_rect_blend(r, r, &src, BlendMode::Multiply)
+            .unwrap();
+        assert_eq!(dst, src);
+        let mut dst = src.clone();
+        dst.blit_rect_blend(r, r, &white, BlendMode::Screen)
+            .unwrap();
+        assert_eq!(dst, white);
+        let mut dst = white.clone();
+        dst.blit_rect_blend(r, r, &src, BlendMode::Copy).unwrap();
+        assert_eq!(dst, src);
+
+        let top = ImageBuffer2D::solid(2, 2, RgbA::new([1f32, 1., 1., 0.5]));
+        let mut bottom = ImageBuffer2D::solid(4, 4, RgbA::new([0f32, 0., 0., 0.5]));
+        bottom
+            .blit_rect_blend(
+                Rect::new(0, 0, 2, 2),
+                Rect::new(2, 2, 2, 2),
+                &top,
+                BlendMode::AlphaOver,
+            )
+            .unwrap();
+        let p = bottom.get_pixel(3, 3);
+        assert_eq!(p[3], 0.75);
+        assert!((p[0] - 2. / 3.).abs() < 1e-6);
+        assert_eq!(bottom.get_pixel(1, 1), &RgbA::new([0., 0., 0., 0.5]));
+        assert!(bottom
+            .blit_rect_blend(
+                Rect::new(0, 0, 2, 2),
+                Rect::new(3, 3, 2, 2),
+                &top,
+                BlendMode::Add
+            )
+            .is_err());
+
+        // Pixel types without alpha are rejected.
+        let mut dst = white.clone();
+        assert!(dst
+            .blit_rect_blend(r, r, &src, BlendMode::AlphaOver)
+            .is_err());
+        assert_eq!(dst, white);
+    }
+
     #[test]
     fn test_sub_image() {
         let img = ImageBuffer2D::generate(5, 5, |(x, y)| {
@@ -1131,7 +2353,7 @@ mod tests {
         let sub_img = img.sub_image(Rect::new(1, 1, 3, 3));
 
         let mut i = 0;
-        for ((y, x), p) in sub_img.enumerate_pixels() {
+        for ((x, y), p) in sub_img.enumerate_pixels() {
             assert_eq!(&Luma::new([(2 * (x + 1) + 3 * (y + 1)) as u8]), p);
             i += 1;
         }
@@ -1143,12 +2365,12 @@ mod tests {
         let mut img = ImageBuffer2D::<Luma<u8>>::new(5, 5);
         {
             let mut sub_img = img.sub_image_mut(Rect::new(1, 1, 3, 3));
-            for ((y, x), mut p) in sub_img.enumerate_pixels_mut() {
+            for ((x, y), mut p) in sub_img.enumerate_pixels_mut() {
                 p.data[0] = (2 * (x + 1) + 3 * (y + 1)) as u8;
             }
         }
 
-        for ((y, x), p) in img.enumerate_pixels() {
+        for ((x, y), p) in img.enumerate_pixels() {
             if x >= 1 && x <= 3 && y >= 1 && y <= 3 {
                 assert_eq!(&Luma::new([(2 * x + 3 * y) as u8]), p);
             } else {
@@ -1168,7 +2390,7 @@ mod tests {
             }
         }
 
-        for ((y, x), p) in img.enumerate_pixels() {
+        for ((x, y), p) in img.enumerate_pixels() {
             if x >= 1 && x <= 3 && y >= 1 && y <= 3 {
                 assert_eq!(&Luma::new([(2 * x + 3 * y) as u8]), p);
             } else {
@@ -1188,7 +2410,7 @@ mod tests {
             }
         }
 
-        for ((y, x), p) in img.enumerate_pixels() {
+        for ((x, y), p) in img.enumerate_pixels() {
             if x >= 1 && x <= 3 && y >= 1 && y <= 3 {
                 assert_eq!(&Luma::new([(2 * x + 3 * y) as u8]), p);
             } else {
@@ -1208,7 +2430,7 @@ mod tests {
             }
         }
 
-        for ((y, x), p) in img.enumerate_pixels() {
+        for ((x, y), p) in img.enumerate_pixels() {
             if x >= 1 && x <= 3 && y >= 1 && y <= 3 {
                 assert_eq!(&Luma::new([(2 * x + 3 * y) as u8]), p);
             } else {
@@ -1220,11 +2442,269 @@ mod tests {
     #[test]
     fn test_generate() {
         let img = ImageBuffer2D::generate(1280, 720, |(x, y)| Luma::new([5 * x + 13 * y]));
-        for ((y, x), pix) in img.enumerate_pixels() {
+        for ((x, y), pix) in img.enumerate_pixels() {
             assert_eq!(pix, &Luma::new([(5 * x + 13 * y) as u32]));
         }
     }
 
+    #[test]
+    fn test_map() {
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([(x * 60 + y) as u8]));
+        let mapped = img.map(|p| Luma::new([f32::from(p.data[0]) / 255.0]));
+        assert_eq!(mapped.dimensions(), (4, 3));
+        for ((x, y), p) in mapped.enumerate_pixels() {
+            assert_eq!(p.data[0], (x * 60 + y) as f32 / 255.0);
+        }
+
+        let view_mapped = img
+            .sub_image(Rect::new(1, 1, 2, 2))
+            .map(|p| Luma::new([p.data[0] / 2]));
+        assert_eq!(view_mapped.get_pixel(0, 0), &Luma::new([30]));
+        assert_eq!(view_mapped.get_pixel(1, 1), &Luma::new([61]));
+    }
+
+    #[test]
+    fn test_count_where_find_pixels() {
+        let img = ImageBuffer2D::generate(6, 4, |(x, y)| {
+            Luma::new([if (x + y) % 3 == 0 { 255u8 } else { 0 }])
+        });
+        assert_eq!(img.count_where(|p| p[0] == 255), 8);
+        assert_eq!(img.count_where(|p| p[0] == 128), 0);
+        assert_eq!(
+            img.find_pixels(|p| p[0] == 255),
+            vec![
+                (0, 0),
+                (3, 0),
+                (2, 1),
+                (5, 1),
+                (1, 2),
+                (4, 2),
+                (0, 3),
+                (3, 3)
+            ]
+        );
+
+        let view = img.sub_image(Rect::new(2, 1, 3, 2));
+        assert_eq!(view.count_where(|p| p[0] == 255), 2);
+        assert_eq!(view.find_pixels(|p| p[0] == 255), vec![(0, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn test_resize_canvas() {
+        let img = ImageBuffer2D::generate(2, 2, |(x, y)| Luma::new([1 + x as u8 + 2 * y as u8]));
+        let fill = Luma::new([9]);
+        let centered = img.resize_canvas(4, 4, Anchor::Center, fill);
+        let expected = [9, 9, 9, 9, 9, 1, 2, 9, 9, 3, 4, 9, 9, 9, 9, 9];
+        assert!(centered.iter().map(|p| p[0]).eq(expected.iter().cloned()));
+
+        let corner = img.resize_canvas(3, 4, Anchor::BottomRight, fill);
+        assert_eq!(corner.get_pixel(1, 2), &Luma::new([1]));
+        assert_eq!(corner.get_pixel(2, 3), &Luma::new([4]));
+        assert_eq!(corner.get_pixel(0, 3), &fill);
+
+        // Shrinking the canvas crops the image.
+        let img = ImageBuffer2D::generate(5, 4, |(x, y)| Luma::new([(x + 10 * y) as u8]));
+        let cropped = img.resize_canvas(3, 2, Anchor::Center, fill);
+        assert_eq!(
+            cropped,
+            Image2D::to_owned(&img.sub_image(Rect::new(1, 1, 3, 2)))
+        );
+        let cropped = img
+            .sub_image(Rect::new(1, 1, 4, 3))
+            .resize_canvas(2, 5, Anchor::Top, fill);
+        assert_eq!(cropped.get_pixel(0, 0), &Luma::new([12]));
+        assert_eq!(cropped.get_pixel(1, 2), &Luma::new([33]));
+        assert_eq!(cropped.get_pixel(1, 3), &fill);
+        assert_eq!(
+            img.resize_canvas(0, 3, Anchor::Left, fill).dimensions(),
+            (0, 3)
+        );
+    }
+
+    #[test]
+    fn test_array_views() {
+        let mut img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([(x + 10 * y) as u8]));
+        assert_eq!(img.as_array_view().dim(), (3, 4));
+        assert_eq!(img.as_array_view()[[2, 1]], Luma::new([21]));
+
+        img.as_array_view_mut()[[1, 3]] = Luma::new([99]);
+        assert_eq!(img.get_pixel(3, 1), &Luma::new([99]));
+        for p in img
+            .sub_image_mut(Rect::new(0, 2, 2, 1))
+            .as_array_view_mut()
+            .iter_mut()
+        {
+            p.data[0] = 7;
+        }
+        assert_eq!(img.get_pixel(1, 2), &Luma::new([7]));
+        assert_eq!(
+            img.sub_image(Rect::new(1, 1, 3, 2)).as_array_view()[[0, 2]],
+            Luma::new([99])
+        );
+
+        let arr = img.to_owned().into_array();
+        assert_eq!(ImageBuffer2D::from_array(arr), img);
+    }
+
+    #[test]
+    fn test_cast() {
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([(x * 100 + y) as u16]));
+        let narrow = img.cast::<u8>();
+        // Values above 255 can't be represented and become 0.
+        let expected = ImageBuffer2D::generate(4, 3, |(x, y)| {
+            Luma::new([if x < 3 { (x * 100 + y) as u8 } else { 0 }])
+        });
+        assert_eq!(narrow, expected);
+        let wide = narrow.cast::<u16>();
+        assert_eq!(wide, expected.map(|p| Luma::new([u16::from(p[0])])));
+
+        let view = img.sub_image(Rect::new(1, 1, 2, 2)).cast::<f64>();
+        assert_eq!(view.get_pixel(1, 1), &Luma::new([202.]));
+    }
+
+    #[test]
+    fn test_sample_bicubic() {
+        let img = ImageBuffer2D::generate(5, 4, |(x, y)| Luma::new([10. * x as f64 + y as f64]));
+        // Exact on integer coordinates and on linear functions
+        assert_eq!(img.sample_bicubic(2., 1.), *img.get_pixel(2, 1));
+        assert!((img.sample_bicubic(1.5, 1.25)[0] - 16.25).abs() < 1e-9);
+        assert_eq!(img.sample_bicubic(-4., 10.), *img.get_pixel(0, 3));
+
+        // Overshoot is clamped to the subpixel range
+        let step =
+            ImageBuffer2D::generate(4, 1, |(x, _)| Luma::new([if x < 2 { 0u8 } else { 255 }]));
+        assert_eq!(step.sample_bicubic(0.5, 0.), Luma::new([0]));
+        assert_eq!(step.sample_bicubic(2.5, 0.), Luma::new([255]));
+
+        // Integer results are rounded, 16.25 and 16.75 on a linear ramp
+        let ramp = ImageBuffer2D::generate(5, 4, |(x, y)| Luma::new([(10 * x + y) as u8]));
+        assert_eq!(ramp.sample_bicubic(1.5, 1.25), Luma::new([16]));
+        assert_eq!(ramp.sample_bicubic(1.5, 1.75), Luma::new([17]));
+    }
+
+    #[test]
+    fn test_transpose() {
+        let img = ImageBuffer2D::generate(5, 3, |(x, y)| Luma::new([(x + 10 * y) as u8]));
+        let t = img.transpose();
+        assert_eq!(t.dimensions(), (3, 5));
+        for y in 0..3 {
+            for x in 0..5 {
+                assert_eq!(t.get_pixel(y, x), img.get_pixel(x, y));
+            }
+        }
+        assert!(t.as_slice().is_none());
+        let owned = img.transpose_owned();
+        assert!(owned.get_view() == t);
+        assert!(owned.as_slice().is_some());
+        assert!(owned.transpose() == img.get_view());
+        assert_eq!(
+            img.sub_image(Rect::new(1, 1, 2, 2))
+                .transpose()
+                .get_pixel(1, 0),
+            &Luma::new([21])
+        );
+    }
+
+    #[test]
+    fn test_color_conversions() {
+        let luma = ImageBuffer2D::generate(3, 2, |(x, y)| Luma::new([(x + 3 * y) as u8]));
+        let rgb = luma_to_rgb(&luma);
+        assert_eq!(rgb.dimensions(), (3, 2));
+        for (l, c) in luma.iter().zip(rgb.iter()) {
+            assert_eq!(c, &Rgb::new([l[0], l[0], l[0]]));
+        }
+
+        let rgba = rgb_to_rgba(&rgb);
+        for (c, ca) in rgb.iter().zip(rgba.iter()) {
+            assert_eq!(ca, &RgbA::new([c[0], c[1], c[2], 255]));
+        }
+        // Floating point images are opaque at 1.
+        let rgba = rgb_to_rgba(&ImageBuffer2D::solid(2, 2, Rgb::new([0.25f32, 0.5, 1.])));
+        assert!(rgba.iter().all(|p| *p == RgbA::new([0.25, 0.5, 1., 1.])));
+
+        let luma_alpha = ImageBuffer2D::generate(3, 2, |(x, y)| LumaA::new([x as u16, y as u16]));
+        let rgba = luma_alpha_to_rgba(&luma_alpha.sub_image(Rect::new(1, 0, 2, 2)));
+        assert_eq!(rgba.dimensions(), (2, 2));
+        assert_eq!(rgba.get_pixel(1, 1), &RgbA::new([2, 2, 2, 1]));
+    }
+
+    #[test]
+    fn test_split_merge_channels() {
+        let img =
+            ImageBuffer2D::generate(5, 3, |(x, y)| Rgb::new([x as u8, y as u8, (x * y) as u8]));
+        let (r, g, b) = split_channels(&img);
+        assert_eq!(r.get_pixel(4, 2), &Luma::new([4]));
+        assert_eq!(g.get_pixel(4, 2), &Luma::new([2]));
+        assert_eq!(b.get_pixel(4, 2), &Luma::new([8]));
+        assert_eq!(merge_channels(&r, &g, &b).unwrap(), img);
+        assert!(merge_channels(&r, &g, &ImageBuffer2D::new(5, 2)).is_err());
+
+        let img = ImageBuffer2D::generate(5, 3, |(x, y)| RgbA::new([x as f32, y as f32, 0.5, 1.]));
+        let (r, g, b, a) = split_channels_alpha(&img.sub_image(Rect::new(1, 1, 3, 2)));
+        assert_eq!(a, ImageBuffer2D::solid(3, 2, Luma::new([1.])));
+        let merged = merge_channels_alpha(&r, &g, &b, &a).unwrap();
+        assert_eq!(
+            merged,
+            Image2D::to_owned(&img.sub_image(Rect::new(1, 1, 3, 2)))
+        );
+        assert!(merge_channels_alpha(&r, &g, &b, &ImageBuffer2D::new(2, 3)).is_err());
+    }
+
+    #[test]
+    fn test_sample_bilinear() {
+        let img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([10. * x as f32 + y as f32]));
+        assert_eq!(img.sample_bilinear(1.5, 0.), Luma::new([15.]));
+        assert_eq!(img.sample_bilinear(2., 1.), *img.get_pixel(2, 1));
+        assert_eq!(img.sample_bilinear(1.5, 0.5), Luma::new([15.5]));
+        assert_eq!(img.sample_bilinear(3., 2.), Luma::new([32.]));
+        assert_eq!(img.sample_bilinear(10., -3.), Luma::new([30.]));
+        assert_eq!(img.sample_bilinear(-1., 7.5), Luma::new([2.]));
+
+        let img = ImageBuffer2D::generate(3, 1, |(x, _)| Rgb::new([x as u8 * 100, 0, 200]));
+        assert_eq!(img.sample_bilinear(1.5, 0.), Rgb::new([150, 0, 200]));
+
+        // Integer results are rounded, 7.9 and -50.5
+        let img = ImageBuffer2D::generate(2, 1, |(x, _)| Luma::new([x as u8 * 10]));
+        assert_eq!(img.sample_bilinear(0.79, 0.), Luma::new([8]));
+        let img =
+            ImageBuffer2D::generate(2, 1, |(x, _)| Luma::new([if x == 0 { -100i8 } else { -1 }]));
+        assert_eq!(img.sample_bilinear(0.5, 0.), Luma::new([-51]));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon_integration")]
+    fn test_par_enumerate_pixels_mut() {
+        let mut seq = ImageBuffer2D::<Luma<u32>>::new(123, 45);
+        let mut par = ImageBuffer2D::<Luma<u32>>::new(123, 45);
+        for ((x, y), p) in seq.enumerate_pixels_mut() {
+            p.data[0] = 7 * x + 1000 * y;
+        }
+        par.par_enumerate_pixels_mut(|(x, y), p| p.data[0] = 7 * x + 1000 * y);
+        assert_eq!(seq, par);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon_integration")]
+    fn test_generate_parallel() {
+        let f = |(x, y): (u32, u32)| Rgb::new([x, y, x * y]);
+        assert_eq!(
+            ImageBuffer2D::generate_parallel(123, 45, f),
+            ImageBuffer2D::generate(123, 45, f)
+        );
+        assert_eq!(
+            ImageBuffer2D::generate_parallel(0, 3, f),
+            ImageBuffer2D::generate(0, 3, f)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rayon_integration")]
+    fn test_par_map() {
+        let img = ImageBuffer2D::generate(123, 45, |(x, y)| Luma::new([x + y]));
+        let f = |p: &Luma<u32>| Luma::new([f64::from(p.data[0]) * 0.5]);
+        assert_eq!(img.map(f), img.par_map(f));
+    }
+
     #[test]
     #[cfg(feature = "rand_integration")]
     fn test_rand() {
@@ -1234,4 +2714,235 @@ mod tests {
             .fold(0u32, |acc, p| acc + u32::from(p.data[0]));
         assert!(sum > 100_000_000 && sum < 130_000_000);
     }
+
+    #[test]
+    fn test_checkerboard() {
+        let (a, b) = (Luma::new([3u8]), Luma::new([7u8]));
+        let img = ImageBuffer2D::checkerboard(4, 4, 1, a, b);
+        for ((x, y), p) in img.enumerate_pixels() {
+            assert_eq!(p, if (x + y) % 2 == 0 { &a } else { &b });
+        }
+        let img = ImageBuffer2D::checkerboard(5, 3, 2, a, b);
+        assert_eq!(img.get_pixel(1, 1), &a);
+        assert_eq!(img.get_pixel(2, 1), &b);
+        assert_eq!(img.get_pixel(2, 2), &a);
+        assert_eq!(img.get_pixel(4, 0), &a);
+    }
+
+    #[test]
+    fn test_solid_and_gradient() {
+        let color = Rgb::new([1u8, 2, 3]);
+        let img = ImageBuffer2D::solid(3, 2, color);
+        assert_eq!(img.dimensions(), (3, 2));
+        assert!(img.iter().all(|p| *p == color));
+
+        let img = ImageBuffer2D::<Rgb<u8>>::horizontal_gradient(6, 2);
+        for row in img.rows() {
+            let row: Vec<_> = row.iter().map(|p| p.data).collect();
+            assert_eq!(
+                row,
+                vec![[0; 3], [51; 3], [102; 3], [153; 3], [204; 3], [255; 3]]
+            );
+        }
+        let img = ImageBuffer2D::<Luma<u16>>::horizontal_gradient(1, 1);
+        assert_eq!(img.get_pixel(0, 0), &Luma::new([0]));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let close = |a: Vec<f64>, b: &[f64]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-12);
+        let img = ImageBuffer2D::generate(5, 4, |(x, y)| Rgb::new([x as u8, (10 * y) as u8, 7]));
+        let (lo, hi) = img.min_max();
+        assert_eq!(lo, Rgb::new([0, 0, 7]));
+        assert_eq!(hi, Rgb::new([4, 30, 7]));
+        assert!(close(img.mean(), &[2., 15., 7.]));
+        assert!(close(img.std_dev(), &[2f64.sqrt(), 125f64.sqrt(), 0.]));
+
+        let view = img.sub_image(Rect::new(1, 1, 2, 2));
+        assert_eq!(view.min_max(), (Rgb::new([1, 10, 7]), Rgb::new([2, 20, 7])));
+        assert!(close(view.mean(), &[1.5, 15., 7.]));
+
+        let empty = ImageBuffer2D::<Luma<f32>>::new(0, 3);
+        assert!(empty.mean()[0].is_nan());
+        assert!(empty.std_dev()[0].is_nan());
+    }
+
+    #[test]
+    fn test_content_bounds() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        assert_eq!(img.content_bounds(Luma::new([0])), None);
+        img.fill_rect(Rect::new(2, 2, 3, 3), &Luma::new([255]));
+        assert_eq!(
+            img.content_bounds(Luma::new([0])),
+            Some(Rect::new(2, 2, 3, 3))
+        );
+        assert_eq!(
+            img.content_bounds(Luma::new([255])),
+            Some(Rect::new(0, 0, 10, 10))
+        );
+        *img.get_pixel_mut(7, 1) = Luma::new([3]);
+        assert_eq!(
+            img.content_bounds(Luma::new([0])),
+            Some(Rect::new(2, 1, 6, 4))
+        );
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        *img.get_pixel_mut(9, 9) = Luma::new([1]);
+        assert_eq!(
+            img.content_bounds(Luma::new([0])),
+            Some(Rect::new(9, 9, 1, 1))
+        );
+    }
+
+    #[test]
+    fn test_checked_accessors() {
+        let mut img = ImageBuffer2D::generate(4, 3, |(x, y)| Luma::new([(x + 4 * y) as u8]));
+        for ((x, y), p) in img.enumerate_pixels() {
+            assert_eq!(img.get_pixel_checked(x, y), Some(p));
+        }
+        assert_eq!(img.get_pixel_checked(4, 3), None);
+        assert_eq!(img.get_pixel_checked(4, 0), None);
+        assert_eq!(img.get_pixel_checked(0, 3), None);
+        assert_eq!(
+            img.sub_image(Rect::new(1, 1, 2, 2)).get_pixel_checked(2, 0),
+            None
+        );
+
+        assert!(img.put_pixel_checked(3, 2, Luma::new([99])));
+        assert_eq!(img.get_pixel(3, 2), &Luma::new([99]));
+        assert!(!img.put_pixel_checked(3, 3, Luma::new([99])));
+        assert!(img.get_pixel_mut_checked(0, 5).is_none());
+        *img.get_pixel_mut_checked(0, 0).unwrap() = Luma::new([42]);
+        assert_eq!(img.get_pixel(0, 0), &Luma::new([42]));
+    }
+
+    #[test]
+    fn test_into_iter_owned() {
+        let img = ImageBuffer2D::generate(5, 3, |(x, y)| Luma::new([(x + 5 * y) as u8]));
+        let raw = Image2D::to_owned(&img).into_raw_vec();
+        let pixels: Vec<Luma<u8>> = img.into_iter().collect();
+        assert_eq!(pixels, raw);
+
+        // Scanline order is preserved for images whose memory layout is not the standard one
+        let img = ImageBuffer2D::generate(3, 5, |(x, y)| Luma::new([(y + 5 * x) as u8]));
+        let transposed = img.transpose().to_owned();
+        let mut values = Vec::new();
+        for pix in transposed {
+            values.push(pix[0]);
+        }
+        assert_eq!(values, (0..15).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = ImageBuffer2D::generate(4, 3, |(x, y)| Rgb::new([x as f32, y as f32, 0.5]));
+        let b = ImageBuffer2D::generate(4, 3, |(x, y)| {
+            Rgb::new([x as f32 + 1e-4, y as f32 - 1e-4, 0.5])
+        });
+        assert!(a.approx_eq(&b, 1e-3));
+        assert!(!a.approx_eq(&b, 1e-5));
+        assert!(!a.approx_eq(&a.sub_image(Rect::new(0, 0, 3, 3)), 1.));
+
+        let a = ImageBuffer2D::solid(2, 2, Luma::new([10u8]));
+        let b = ImageBuffer2D::solid(2, 2, Luma::new([12u8]));
+        assert!(a.approx_eq(&b, 2) && b.approx_eq(&a, 2));
+        assert!(!a.approx_eq(&b, 1));
+
+        // The difference overflows the subpixel type.
+        let a = ImageBuffer2D::solid(2, 2, Luma::new([127i8]));
+        let b = ImageBuffer2D::solid(2, 2, Luma::new([-128i8]));
+        assert!(!a.approx_eq(&b, 100) && !b.approx_eq(&a, 100));
+        assert!(!a.approx_eq(&b, 127) && a.approx_eq(&a, 0));
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let img = ImageBuffer2D::generate(2, 2, |(x, y)| {
+            let i = 3 * (2 * y + x) as u8;
+            Rgb::new([i, i + 1, i + 2])
+        });
+        assert_eq!(img.bytes_per_row(), 6);
+        let bytes = img.to_bytes(Endianness::Little);
+        assert_eq!(bytes, (0..12).collect::<Vec<u8>>());
+        assert_eq!(
+            img.transpose().to_bytes(Endianness::Big),
+            vec![0, 1, 2, 6, 7, 8, 3, 4, 5, 9, 10, 11]
+        );
+
+        let img = ImageBuffer2D::generate(2, 1, |(x, _)| Luma::new([0x0102u16 + x as u16]));
+        assert_eq!(img.bytes_per_row(), 4);
+        assert_eq!(img.to_bytes(Endianness::Little), vec![2, 1, 3, 1]);
+        assert_eq!(img.to_bytes(Endianness::Big), vec![1, 2, 1, 3]);
+
+        let img = ImageBuffer2D::solid(1, 1, Luma::new([-2i16]));
+        assert_eq!(img.to_bytes(Endianness::Big), vec![0xff, 0xfe]);
+        let img = ImageBuffer2D::solid(1, 1, Luma::new([1f32]));
+        assert_eq!(
+            img.to_bytes(Endianness::Little),
+            1f32.to_bits().to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_pixels_in_rect() {
+        let img = ImageBuffer2D::generate(5, 5, |(x, y)| Luma::new([(5 * y + x) as u8]));
+        let pixels: Vec<u8> = img
+            .pixels_in_rect(Rect::new(3, 3, 5, 5))
+            .unwrap()
+            .map(|p| p[0])
+            .collect();
+        assert_eq!(pixels, vec![18, 19, 23, 24]);
+        assert_eq!(
+            img.pixels_in_rect(Rect::new(1, 1, 2, 2)).unwrap().count(),
+            4
+        );
+        assert!(img.pixels_in_rect(Rect::new(5, 0, 2, 2)).is_none());
+    }
+
+    #[test]
+    fn test_paste() {
+        let src = ImageBuffer2D::generate(4, 4, |(x, y)| Luma::new([(1 + x + 4 * y) as u8]));
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        img.paste(&src, (-2, -2));
+        for ((x, y), p) in img.enumerate_pixels() {
+            let expected = if x < 2 && y < 2 { 11 + x + 4 * y } else { 0 };
+            assert_eq!(u32::from(p[0]), expected);
+        }
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        img.paste(&src, (8, 3));
+        assert_eq!(
+            img.content_bounds(Luma::new([0])),
+            Some(Rect::new(8, 3, 2, 4))
+        );
+        assert_eq!(img.get_pixel(9, 6), &Luma::new([14]));
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        img.paste(&src, (10, 0));
+        img.paste(&src, (-4, 0));
+        assert!(img.iter().all(|p| p[0] == 0));
+    }
+
+    #[test]
+    fn test_windows() {
+        let img = ImageBuffer2D::generate(5, 5, |(x, y)| Luma::new([(5 * y + x) as u8]));
+        let windows = img.windows((3, 3));
+        assert_eq!(windows.len(), 9);
+        let windows: Vec<_> = windows.collect();
+        assert_eq!(windows.len(), 9);
+        assert_eq!(windows[0].get_pixel(0, 0), img.get_pixel(0, 0));
+        for (i, window) in windows.iter().enumerate() {
+            let (x, y) = (i as u32 % 3, i as u32 / 3);
+            assert!(*window == img.sub_image(Rect::new(x, y, 3, 3)));
+        }
+
+        let transposed = img.transpose();
+        let mut windows = transposed.windows((2, 5));
+        assert_eq!(windows.len(), 4);
+        windows.next();
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows.next().unwrap().get_pixel(0, 3), &Luma::new([8]));
+        assert_eq!(img.windows((6, 1)).len(), 0);
+        assert_eq!(img.windows((6, 1)).count(), 0);
+    }
 }
@@ -0,0 +1,245 @@
+//! Contains the definition of the `Mask` type.
+
+use core::{Image2D, ImageBuffer2D, Luma, Pixel, Region};
+
+use failure::Error;
+
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+/// Binary mask, storing one boolean per pixel. Pixels whose value is `true` are said to be set.
+///
+/// Masks are complemented with `!`, and references to masks are combined pixel by pixel with `&`, `|` and `^`, which
+/// return an error if the masks have different dimensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    data: Vec<bool>,
+    width: u32,
+    height: u32,
+}
+
+impl Mask {
+    /// Create a new mask of the given dimensions, with no pixel set.
+    pub fn new(width: u32, height: u32) -> Mask {
+        Mask {
+            data: vec![false; width as usize * height as usize],
+            width,
+            height,
+        }
+    }
+
+    /// Create a new mask by calling a function on the `(x, y)` coordinates of each pixel.
+    pub fn generate<F>(width: u32, height: u32, mut f: F) -> Mask
+    where
+        F: FnMut((u32, u32)) -> bool,
+    {
+        let mut data = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(f((x, y)));
+            }
+        }
+        Mask {
+            data,
+            width,
+            height,
+        }
+    }
+
+    /// Create a mask from a binary image, setting the pixels whose value is not 0.
+    pub fn from_image(img: &Image2D<Luma<u8>>) -> Mask {
+        Mask {
+            data: img.iter().map(|p| p[0] != 0).collect(),
+            width: img.width(),
+            height: img.height(),
+        }
+    }
+
+    /// Convert the mask to a binary image, set pixels being 255 and others 0.
+    pub fn to_image(&self) -> ImageBuffer2D<Luma<u8>> {
+        ImageBuffer2D::generate(self.width, self.height, |(x, y)| {
+            Luma::new([if self.get(x, y) { 255 } else { 0 }])
+        })
+    }
+
+    /// Return the width of the mask.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Return the height of the mask.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Return the dimensions of the mask as a `(width, height)` tuple.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Return whether the pixel at the specified coordinates is set.
+    ///
+    /// **Panics** if the coordinates are out of the mask.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        assert!(
+            x < self.width && y < self.height,
+            "Coordinates out of the mask."
+        );
+        self.data[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Set or clear the pixel at the specified coordinates.
+    ///
+    /// **Panics** if the coordinates are out of the mask.
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        assert!(
+            x < self.width && y < self.height,
+            "Coordinates out of the mask."
+        );
+        self.data[y as usize * self.width as usize + x as usize] = value;
+    }
+
+    /// Return the number of set pixels.
+    pub fn count(&self) -> usize {
+        self.data.iter().filter(|&&v| v).count()
+    }
+
+    // Combine two masks pixel by pixel.
+    fn combine<F>(&self, other: &Mask, f: F) -> Result<Mask, Error>
+    where
+        F: Fn(bool, bool) -> bool,
+    {
+        ensure!(
+            self.dimensions() == other.dimensions(),
+            "Mask dimensions do not match: {:?} and {:?}",
+            self.dimensions(),
+            other.dimensions()
+        );
+        Ok(Mask {
+            data: self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(&a, &b)| f(a, b))
+                .collect(),
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// Return a copy of an image where the pixels that are not set in the mask are replaced by `bg`.
+    ///
+    /// **Error**: if the image and the mask have different dimensions.
+    pub fn apply<P>(&self, img: &Image2D<P>, bg: P) -> Result<ImageBuffer2D<P>, Error>
+    where
+        P: Pixel,
+    {
+        ensure!(
+            self.dimensions() == img.dimensions(),
+            "Image dimensions do not match the mask: {:?} and {:?}",
+            img.dimensions(),
+            self.dimensions()
+        );
+        Ok(ImageBuffer2D::generate(
+            self.width,
+            self.height,
+            |(x, y)| {
+                if self.get(x, y) {
+                    img.get_pixel(x, y).clone()
+                } else {
+                    bg.clone()
+                }
+            },
+        ))
+    }
+}
+
+// Implement a binary operator for mask references, combining the masks pixel by pixel with `$f`.
+macro_rules! impl_mask_op {
+    ($op_name:ident, $op_fn:ident, $f:expr) => {
+        impl<'a, 'b> $op_name<&'a Mask> for &'b Mask {
+            type Output = Result<Mask, Error>;
+
+            fn $op_fn(self, rhs: &'a Mask) -> Result<Mask, Error> {
+                self.combine(rhs, $f)
+            }
+        }
+    };
+}
+
+impl_mask_op!(BitAnd, bitand, |a, b| a && b);
+impl_mask_op!(BitOr, bitor, |a, b| a || b);
+impl_mask_op!(BitXor, bitxor, |a, b| a != b);
+
+impl<'a> Not for &'a Mask {
+    type Output = Mask;
+
+    /// Return the complement of the mask.
+    fn not(self) -> Mask {
+        Mask {
+            data: self.data.iter().map(|&v| !v).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl Not for Mask {
+    type Output = Mask;
+
+    /// Return the complement of the mask.
+    fn not(mut self) -> Mask {
+        for v in &mut self.data {
+            *v = !*v;
+        }
+        self
+    }
+}
+
+impl Region for Mask {
+    /// Return `true` if the pixel is within the mask and set.
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.get(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Rect, Rgb};
+
+    fn rect_mask(w: u32, h: u32, r: Rect) -> Mask {
+        Mask::generate(w, h, |(x, y)| r.contains(x, y))
+    }
+
+    #[test]
+    fn test_logical_ops() {
+        let a = rect_mask(10, 8, Rect::new(1, 1, 5, 4));
+        let b = rect_mask(10, 8, Rect::new(3, 2, 6, 6));
+        assert_eq!(!!a.clone(), a);
+        assert_eq!((!&a).count(), 80 - 20);
+        assert_eq!((&a & &b).unwrap(), rect_mask(10, 8, Rect::new(3, 2, 3, 3)));
+        assert_eq!((&a | &b).unwrap().count(), 20 + 36 - 9);
+        assert_eq!(
+            (&a ^ &b).unwrap(),
+            (&(&a | &b).unwrap() & &!(&a & &b).unwrap()).unwrap()
+        );
+        assert!((&a & &Mask::new(10, 7)).is_err());
+    }
+
+    #[test]
+    fn test_image_conversions() {
+        let img =
+            ImageBuffer2D::generate(6, 4, |(x, y)| Luma::new([if x > y { 255u8 } else { 0 }]));
+        let mask = Mask::from_image(&img);
+        assert!(mask.get(3, 1) && mask.contains(3, 1));
+        assert!(!mask.get(1, 3) && !mask.contains(6, 0));
+        assert_eq!(mask.to_image(), img);
+
+        let img = ImageBuffer2D::generate(6, 4, |(x, y)| Rgb::new([x as u8, y as u8, 1]));
+        let bg = Rgb::new([9, 9, 9]);
+        let masked = mask.apply(&img, bg).unwrap();
+        assert_eq!(masked.get_pixel(3, 1), img.get_pixel(3, 1));
+        assert_eq!(masked.get_pixel(1, 3), &bg);
+        assert!(mask.apply(&ImageBuffer2D::new(4, 6), bg).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 
 mod dynamic_image;
 mod image2d;
+mod mask;
 mod neighborhood;
 pub mod padding;
 mod pixel_types;
@@ -10,6 +11,7 @@ mod traits;
 
 pub use self::dynamic_image::*;
 pub use self::image2d::*;
+pub use self::mask::*;
 pub use self::neighborhood::*;
 pub use self::pixel_types::*;
 pub use self::rect::*;
@@ -1,5 +1,10 @@
 //! Contains the definition of neighborhood shapes and neighborhood iterators.
 
+use core::padding::{pad, Padding};
+use core::{Image2D, Image2DView, ImageBuffer2D, Pixel, Rect};
+
+use num_traits::Zero;
+
 /// Trait for types describing neighborhood shapes. Broadly speaking, a Neighborhood is defined by its origin and a set
 /// of pixels whose position is relative to the origin.
 pub trait Neighborhood {
@@ -7,8 +12,8 @@ pub trait Neighborhood {
     fn origin(&self) -> (u32, u32);
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Rectangular neighborhood with a specified origin.
-#[allow(dead_code)]
 pub struct RectNeighborhood {
     size: (u32, u32),
     origin: (u32, u32),
@@ -23,22 +28,161 @@ impl Neighborhood for RectNeighborhood {
 impl RectNeighborhood {
     /// Create a new `RectNeighborhood`. The origin must be inside of the neighborhood and size must be non zero.
     pub fn new(size: (u32, u32), origin: (u32, u32)) -> Option<RectNeighborhood> {
-        if origin.0 <= size.0 && origin.1 < size.1 && size.0 > 0 && size.1 > 0 {
+        if origin.0 < size.0 && origin.1 < size.1 && size.0 > 0 && size.1 > 0 {
             Some(RectNeighborhood { size, origin })
         } else {
             None
         }
     }
+
+    /// Return the size of the neighborhood.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Return an iterator over the neighborhoods of the pixels of an image. Pixels whose neighborhood would not fit
+    /// inside of the image are skipped.
+    pub fn iter<'a, P>(&self, img: &'a Image2D<P>) -> RectNeighborhoodIter<'a, P>
+    where
+        P: Pixel,
+    {
+        let (w, h) = img.dimensions();
+        let end_x = (w + self.origin.0 + 1).saturating_sub(self.size.0);
+        let end_y = (h + self.origin.1 + 1).saturating_sub(self.size.1);
+        RectNeighborhoodIter::new(
+            img,
+            *self,
+            (self.origin.0, self.origin.1),
+            (end_x, end_y),
+            0,
+        )
+    }
+
+    /// Pad an image so that the neighborhood of every one of its pixels can be iterated over with
+    /// `PaddedNeighborhoods::iter`.
+    pub fn pad<P>(&self, img: &Image2D<P>, padding: Padding) -> PaddedNeighborhoods<P>
+    where
+        P: Pixel + Zero,
+    {
+        let radius = *[
+            self.origin.0,
+            self.origin.1,
+            self.size.0 - 1 - self.origin.0,
+            self.size.1 - 1 - self.origin.1,
+        ]
+        .iter()
+        .max()
+        .unwrap();
+        PaddedNeighborhoods {
+            padded: pad(img, radius, padding),
+            dimensions: img.dimensions(),
+            radius,
+            neighborhood: *self,
+        }
+    }
+}
+
+/// Padded image whose pixel neighborhoods can all be iterated over. See `RectNeighborhood::pad`.
+pub struct PaddedNeighborhoods<P>
+where
+    P: Pixel,
+{
+    padded: ImageBuffer2D<P>,
+    dimensions: (u32, u32),
+    radius: u32,
+    neighborhood: RectNeighborhood,
+}
+
+impl<P> PaddedNeighborhoods<P>
+where
+    P: Pixel,
+{
+    /// Return an iterator over the neighborhoods of every pixel of the original image.
+    pub fn iter(&self) -> RectNeighborhoodIter<P> {
+        RectNeighborhoodIter::new(
+            &self.padded,
+            self.neighborhood,
+            (0, 0),
+            self.dimensions,
+            self.radius,
+        )
+    }
+}
+
+/// Iterator over the rectangular neighborhoods of the pixels of an image. Yields the coordinates of the pixels
+/// along with a view of their neighborhood, in scanline order.
+pub struct RectNeighborhoodIter<'a, P>
+where
+    P: Pixel + 'a,
+{
+    img: &'a Image2D<P>,
+    neighborhood: RectNeighborhood,
+    start_x: u32,
+    end: (u32, u32),
+    pos: (u32, u32),
+    // Offset between the coordinates yielded and the coordinates in img
+    offset: u32,
+}
+
+impl<'a, P> RectNeighborhoodIter<'a, P>
+where
+    P: Pixel,
+{
+    fn new(
+        img: &'a Image2D<P>,
+        neighborhood: RectNeighborhood,
+        start: (u32, u32),
+        end: (u32, u32),
+        offset: u32,
+    ) -> RectNeighborhoodIter<'a, P> {
+        // Make the iterator empty right away if there is nothing to iterate on a line
+        let pos = if start.0 < end.0 {
+            start
+        } else {
+            (start.0, end.1)
+        };
+        RectNeighborhoodIter {
+            img,
+            neighborhood,
+            start_x: start.0,
+            end,
+            pos,
+            offset,
+        }
+    }
 }
 
-// TODO
-// Iterator over a rectangular image region.
-//pub struct RectNeighborhoodIter {
-//}
+impl<'a, P> Iterator for RectNeighborhoodIter<'a, P>
+where
+    P: Pixel,
+{
+    type Item = ((u32, u32), Image2DView<'a, P>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, y) = self.pos;
+        if y >= self.end.1 {
+            return None;
+        }
+        self.pos = if x + 1 < self.end.0 {
+            (x + 1, y)
+        } else {
+            (self.start_x, y + 1)
+        };
+        let (size, origin) = (self.neighborhood.size, self.neighborhood.origin);
+        let rect = Rect::new(
+            x + self.offset - origin.0,
+            y + self.offset - origin.1,
+            size.0,
+            size.1,
+        );
+        Some(((x, y), self.img.sub_image(rect)))
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use core::RectNeighborhood;
+    use core::padding::Padding;
+    use core::{Image2D, ImageBuffer2D, Luma, RectNeighborhood};
 
     #[test]
     fn test_new_rect_neighborhood() {
@@ -50,4 +194,45 @@ mod tests {
         assert!(RectNeighborhood::new((100, 0), (0, 0)).is_none());
         assert!(RectNeighborhood::new((0, 0), (0, 0)).is_none());
     }
+
+    #[test]
+    fn test_rect_neighborhood_iter() {
+        let img = ImageBuffer2D::generate(5, 5, |(x, y)| Luma::new([(x + 5 * y) as u8]));
+        let nbh = RectNeighborhood::new((3, 3), (1, 1)).unwrap();
+        let windows: Vec<_> = nbh.iter(&img).collect();
+        assert_eq!(windows.len(), 9);
+        for (i, &((x, y), ref win)) in windows.iter().enumerate() {
+            assert_eq!((x, y), (1 + i as u32 % 3, 1 + i as u32 / 3));
+            assert_eq!(win.dimensions(), (3, 3));
+            assert_eq!(win.get_pixel(1, 1), img.get_pixel(x, y));
+            assert_eq!(win.get_pixel(0, 0), img.get_pixel(x - 1, y - 1));
+        }
+
+        // Asymmetric neighborhood
+        let nbh = RectNeighborhood::new((2, 3), (0, 2)).unwrap();
+        let coords: Vec<_> = nbh.iter(&img).map(|(c, _)| c).collect();
+        assert_eq!(coords.len(), 4 * 3);
+        assert_eq!(coords[0], (0, 2));
+        assert_eq!(coords[11], (3, 4));
+
+        // Neighborhood larger than the image
+        let nbh = RectNeighborhood::new((7, 3), (3, 1)).unwrap();
+        assert_eq!(nbh.iter(&img).count(), 0);
+    }
+
+    #[test]
+    fn test_padded_neighborhoods() {
+        let img = ImageBuffer2D::generate(5, 4, |(x, y)| Luma::new([1 + (x + 5 * y) as u8]));
+        let nbh = RectNeighborhood::new((3, 3), (1, 1)).unwrap();
+        let padded = nbh.pad(&img, Padding::Zeros);
+        let windows: Vec<_> = padded.iter().collect();
+        assert_eq!(windows.len(), 20);
+        for &((x, y), ref win) in &windows {
+            assert_eq!(win.dimensions(), (3, 3));
+            assert_eq!(win.get_pixel(1, 1), img.get_pixel(x, y));
+        }
+        let ((_, _), ref corner) = windows[0];
+        assert_eq!(corner.get_pixel(0, 0), &Luma::new([0]));
+        assert_eq!(corner.get_pixel(2, 2), img.get_pixel(1, 1));
+    }
 }
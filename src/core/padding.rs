@@ -4,6 +4,32 @@ use core::{Image2D, Image2DMut, ImageBuffer2D, Pixel, Rect};
 
 use num_traits::Zero;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Enumerate the padding methods.
+pub enum Padding {
+    /// Pad with zeros. See `pad_zeros`.
+    Zeros,
+    /// Pad by replicating the image borders. See `pad_replicate`.
+    Replicate,
+    /// Pad by wrapping around the image borders. See `pad_wrap`.
+    Wrap,
+    /// Pad by mirroring the image borders. See `pad_mirror`.
+    Mirror,
+}
+
+/// Pad an image with the specified padding method.
+pub fn pad<P>(img: &Image2D<P>, radius: u32, padding: Padding) -> ImageBuffer2D<P>
+where
+    P: Pixel + Zero,
+{
+    match padding {
+        Padding::Zeros => pad_zeros(img, radius),
+        Padding::Replicate => pad_replicate(img, radius),
+        Padding::Wrap => pad_wrap(img, radius),
+        Padding::Mirror => pad_mirror(img, radius),
+    }
+}
+
 /// Pad an image with zeros.
 pub fn pad_zeros<P>(img: &Image2D<P>, radius: u32) -> ImageBuffer2D<P>
 where
@@ -16,6 +42,19 @@ where
     padded
 }
 
+/// Pad an image with the given constant value.
+pub fn pad_constant<P>(img: &Image2D<P>, radius: u32, value: &P) -> ImageBuffer2D<P>
+where
+    P: Pixel + Zero,
+{
+    let (w, h) = img.dimensions();
+    let mut padded = ImageBuffer2D::new(w + 2 * radius, h + 2 * radius);
+    padded.fill(value);
+    let r = Rect::new(radius, radius, w, h);
+    padded.blit_rect(img.rect(), r, img).unwrap();
+    padded
+}
+
 /// Pad an image by replicating its borders.
 pub fn pad_replicate<P>(img: &Image2D<P>, radius: u32) -> ImageBuffer2D<P>
 where
@@ -211,7 +250,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use core::padding::pad_zeros;
+    use core::padding::{pad_constant, pad_zeros};
     use core::{Image2D, Image2DMut, ImageBuffer2D, Luma};
 
     use num_traits::Zero;
@@ -222,7 +261,7 @@ mod tests {
         img.fill(&Luma::new([255u8]));
         let padded_img = pad_zeros(&img, 5);
         assert_eq!(padded_img.dimensions(), (110, 110));
-        for ((y, x), pix) in padded_img.enumerate_pixels() {
+        for ((x, y), pix) in padded_img.enumerate_pixels() {
             if x < 5 || y < 5 || x > 104 || y > 104 {
                 assert_eq!(pix, &Luma::zero());
             } else {
@@ -230,4 +269,18 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_pad_constant() {
+        let img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        let padded_img = pad_constant(&img, 2, &Luma::new([7u8]));
+        assert_eq!(padded_img.dimensions(), (14, 14));
+        for ((x, y), pix) in padded_img.enumerate_pixels() {
+            if x < 2 || y < 2 || x > 11 || y > 11 {
+                assert_eq!(pix, &Luma::new([7u8]));
+            } else {
+                assert_eq!(pix, &Luma::zero());
+            }
+        }
+    }
 }
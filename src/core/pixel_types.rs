@@ -9,6 +9,7 @@ use rand::{
 };
 
 use core::{Pixel, PixelCast, Primitive};
+use helper::generic::{from_f64_clamped, full_intensity, is_integer};
 
 use std::convert::From;
 use std::ops::{
@@ -55,6 +56,28 @@ pub enum PixelType {
     RgbA,
 }
 
+// Compute the luminance of a pixel from its channels: the Rec. 709 weighted sum of the color channels for RGB(A)
+// pixels, the gray channel for grayscale pixels. Alpha is ignored.
+fn luminance<P>(data: &[P]) -> f64
+where
+    P: Primitive,
+{
+    let c = |i: usize| cast::<P, f64>(data[i]).unwrap_or(0.);
+    if data.len() >= 3 {
+        0.2126 * c(0) + 0.7152 * c(1) + 0.0722 * c(2)
+    } else {
+        c(0)
+    }
+}
+
+// The luminance of a YCbCr pixel is its luma channel.
+fn luma_channel<P>(data: &[P]) -> f64
+where
+    P: Primitive,
+{
+    cast::<P, f64>(data[0]).unwrap_or(0.)
+}
+
 // TODO: impl_op! macro
 
 macro_rules! impl_pixel_op {
@@ -230,7 +253,7 @@ macro_rules! impl_pixel_op_assign {
 }
 
 macro_rules! impl_pixels {
-    ( $( $(#[$attr:meta])* $name:ident, $n_channels:expr);+ ) =>
+    ( $( $(#[$attr:meta])* $name:ident, $n_channels:expr, $luminance:ident);+ ) =>
     {$(
         #[derive(Debug, Copy, Clone, PartialEq)]
         $( #[$attr] )*
@@ -338,6 +361,10 @@ macro_rules! impl_pixels {
                 p
             }
 
+            fn from_value(s: Self::Subpixel) -> $name<P> {
+                $name { data: [s; $n_channels] }
+            }
+
             fn set_to_slice(&mut self, s: &[Self::Subpixel]) {
                 for (n, e) in self.data.iter_mut().zip(s.iter()) {
                     *n = *e;
@@ -354,6 +381,10 @@ macro_rules! impl_pixels {
                 p
             }
 
+            fn luminance(&self) -> f64 {
+                $luminance(&self.data)
+            }
+
             #[cfg(feature = "rand_integration")]
             fn rand<R>(rng: &mut R) -> $name<P>
                 where R: Rng,
@@ -376,20 +407,18 @@ macro_rules! impl_pixels {
             }
         }
 
-        impl<S, O> PixelCast<$name<O>, S, O> for $name<S>
+        impl<S, O> PixelCast<O> for $name<S>
             where O: Primitive,
                   S: Primitive
         {
-            fn cast_from(&mut self, other: &$name<O>) {
-                for (src, dst) in other.channels().into_iter().zip(self.channels_mut().into_iter()) {
-                    *dst = cast::<O, S>(src.clone()).unwrap_or(<S as Zero>::zero());
-                }
-            }
+            type Output = $name<O>;
 
-            fn cast_to(&self, other: &mut $name<O>) {
-                for (dst, src) in other.channels_mut().into_iter().zip(self.channels().into_iter()) {
-                    *dst = cast::<S, O>(src.clone()).unwrap_or(<O as Zero>::zero());
+            fn cast(&self) -> $name<O> {
+                let mut p = $name::<O>::zero();
+                for (dst, src) in p.data.iter_mut().zip(self.data.iter()) {
+                    *dst = cast::<S, O>(*src).unwrap_or_else(<O as Zero>::zero);
                 }
+                p
             }
         }
     )+}
@@ -397,15 +426,84 @@ macro_rules! impl_pixels {
 
 impl_pixels!(
     /// Grayscale pixel type
-    Luma, 1;
+    Luma, 1, luminance;
     /// Grayscale with alpha pixel type
-    LumaA, 2;
+    LumaA, 2, luminance;
     /// RGB pixel type
-    Rgb, 3;
+    Rgb, 3, luminance;
     /// RGB with alpha pixel type
-    RgbA, 4
+    RgbA, 4, luminance;
+    /// YCbCr pixel type, using the full range BT.601 (JPEG) definition. The chroma channels are centered on the middle
+    /// of the range of the subpixel type, e.g. 128 for `u8` and 0.5 for floating point types.
+    YCbCr, 3, luma_channel
 );
 
+// Offset of the chroma channels of YCbCr pixels.
+fn chroma_offset<P>() -> f64
+where
+    P: Primitive,
+{
+    if is_integer::<P>() {
+        (full_intensity::<P>() + 1.) / 2.
+    } else {
+        0.5
+    }
+}
+
+impl<'a, P> From<&'a Rgb<P>> for YCbCr<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: &'a Rgb<P>) -> YCbCr<P> {
+        let c = |i: usize| cast::<P, f64>(pixel.data[i]).unwrap_or(0.);
+        let (r, g, b) = (c(0), c(1), c(2));
+        let offset = chroma_offset::<P>();
+        YCbCr {
+            data: [
+                from_f64_clamped(0.299 * r + 0.587 * g + 0.114 * b),
+                from_f64_clamped(offset - 0.168_736 * r - 0.331_264 * g + 0.5 * b),
+                from_f64_clamped(offset + 0.5 * r - 0.418_688 * g - 0.081_312 * b),
+            ],
+        }
+    }
+}
+
+impl<P> From<Rgb<P>> for YCbCr<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: Rgb<P>) -> YCbCr<P> {
+        YCbCr::from(&pixel)
+    }
+}
+
+impl<'a, P> From<&'a YCbCr<P>> for Rgb<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: &'a YCbCr<P>) -> Rgb<P> {
+        let c = |i: usize| cast::<P, f64>(pixel.data[i]).unwrap_or(0.);
+        let offset = chroma_offset::<P>();
+        let (y, cb, cr) = (c(0), c(1) - offset, c(2) - offset);
+        Rgb {
+            data: [
+                from_f64_clamped(y + 1.402 * cr),
+                from_f64_clamped(y - 0.344_136 * cb - 0.714_136 * cr),
+                from_f64_clamped(y + 1.772 * cb),
+            ],
+        }
+    }
+}
+
+impl<P> From<YCbCr<P>> for Rgb<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: YCbCr<P>) -> Rgb<P> {
+        Rgb::from(&pixel)
+    }
+}
+
 impl<P> From<LumaA<P>> for Luma<P>
 where
     P: Primitive,
@@ -450,6 +548,80 @@ where
     }
 }
 
+impl<P> From<Luma<P>> for Rgb<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: Luma<P>) -> Rgb<P> {
+        Rgb {
+            data: [pixel.data[0], pixel.data[0], pixel.data[0]],
+        }
+    }
+}
+
+impl<'a, P> From<&'a Luma<P>> for Rgb<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: &'a Luma<P>) -> Rgb<P> {
+        Rgb {
+            data: [pixel.data[0], pixel.data[0], pixel.data[0]],
+        }
+    }
+}
+
+impl<P> From<LumaA<P>> for RgbA<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: LumaA<P>) -> RgbA<P> {
+        RgbA {
+            data: [pixel.data[0], pixel.data[0], pixel.data[0], pixel.data[1]],
+        }
+    }
+}
+
+impl<'a, P> From<&'a LumaA<P>> for RgbA<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: &'a LumaA<P>) -> RgbA<P> {
+        RgbA {
+            data: [pixel.data[0], pixel.data[0], pixel.data[0], pixel.data[1]],
+        }
+    }
+}
+
+// Alpha value of opaque pixels: the maximum value for integer types, 1 for floating point types.
+fn opaque_alpha<P>() -> P
+where
+    P: Primitive,
+{
+    cast(full_intensity::<P>()).unwrap_or_else(P::max_value)
+}
+
+impl<P> From<Rgb<P>> for RgbA<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: Rgb<P>) -> RgbA<P> {
+        RgbA {
+            data: [pixel.data[0], pixel.data[1], pixel.data[2], opaque_alpha()],
+        }
+    }
+}
+
+impl<'a, P> From<&'a Rgb<P>> for RgbA<P>
+where
+    P: Primitive,
+{
+    fn from(pixel: &'a Rgb<P>) -> RgbA<P> {
+        RgbA {
+            data: [pixel.data[0], pixel.data[1], pixel.data[2], opaque_alpha()],
+        }
+    }
+}
+
 impl<P> From<P> for Luma<P>
 where
     P: Primitive,
@@ -461,7 +633,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use core::Luma;
+    use core::{Luma, LumaA, Pixel, PixelCast, Rgb, RgbA, YCbCr};
 
     #[test]
     fn test_pixel_add() {
@@ -484,4 +656,86 @@ mod tests {
         let l4 = Luma::new([3u8]);
         assert_eq!(l3 - 5u8, l4);
     }
+
+    #[test]
+    fn test_pixel_cast() {
+        let p = Rgb::new([0u8, 127, 255]);
+        let p_f64: Rgb<f64> = p.cast();
+        assert_eq!(p_f64, Rgb::new([0., 127., 255.]));
+        let p_u8: Rgb<u8> = p_f64.cast();
+        assert_eq!(p_u8, p);
+
+        // Out of range values are cast to zero.
+        let l: Luma<u8> = Luma::new([300.5f64]).cast();
+        assert_eq!(l, Luma::new([0]));
+        let l: Luma<u8> = Luma::new([-1i32]).cast();
+        assert_eq!(l, Luma::new([0]));
+    }
+
+    #[test]
+    fn test_from_conversions() {
+        assert_eq!(Rgb::from(Luma::new([3u8])), Rgb::new([3, 3, 3]));
+        assert_eq!(RgbA::from(&LumaA::new([3u8, 4])), RgbA::new([3, 3, 3, 4]));
+        assert_eq!(
+            RgbA::from(Rgb::new([1u16, 2, 3])),
+            RgbA::new([1, 2, 3, 65535])
+        );
+        assert_eq!(
+            RgbA::from(&Rgb::new([0.5f32, 1., 0.])),
+            RgbA::new([0.5, 1., 0., 1.])
+        );
+    }
+
+    #[test]
+    fn test_from_value() {
+        assert_eq!(Rgb::<u8>::from_value(7), Rgb::new([7, 7, 7]));
+        assert_eq!(Luma::<f32>::from_value(1.5), Luma::new([1.5]));
+    }
+
+    #[test]
+    fn test_luminance() {
+        assert!((Rgb::new([255u8, 255, 255]).luminance() - 255.).abs() < 1e-9);
+        assert!((Rgb::new([0u8, 255, 0]).luminance() - 0.7152 * 255.).abs() < 1e-9);
+        assert!((RgbA::new([0., 1., 0., 0.5f32]).luminance() - 0.7152).abs() < 1e-6);
+        assert_eq!(Luma::new([42u16]).luminance(), 42.);
+        assert_eq!(LumaA::new([42u8, 7]).luminance(), 42.);
+    }
+
+    #[test]
+    fn test_clamp() {
+        let mut p = Luma::<i32>::new([300]);
+        p.clamp(0, 255);
+        assert_eq!(p, Luma::new([255]));
+        let p = Rgb::new([-4i16, 100, 1000]);
+        assert_eq!(p.clamped(0, 255), Rgb::new([0, 100, 255]));
+        assert_eq!(p, Rgb::new([-4, 100, 1000]));
+        assert_eq!(
+            RgbA::new([1.5f32, 0.5, -0.5, 1.]).clamped(0., 1.),
+            RgbA::new([1., 0.5, 0., 1.])
+        );
+    }
+
+    #[test]
+    fn test_ycbcr() {
+        for r in (0..256).step_by(5) {
+            for g in (0..256).step_by(5) {
+                for b in (0..256).step_by(5) {
+                    let p = Rgb::new([r as u8, g as u8, b as u8]);
+                    let q = Rgb::from(YCbCr::from(p));
+                    for (c1, c2) in p.data.iter().zip(q.data.iter()) {
+                        assert!((i32::from(*c1) - i32::from(*c2)).abs() <= 1);
+                    }
+                }
+            }
+        }
+        for v in 0..256 {
+            let p = YCbCr::from(Rgb::new([v as u8; 3]));
+            assert_eq!(p, YCbCr::new([v as u8, 128, 128]));
+        }
+        let p = YCbCr::from(Rgb::new([0.5f32; 3]));
+        assert!(
+            (p[0] - 0.5).abs() < 1e-6 && (p[1] - 0.5).abs() < 1e-6 && (p[2] - 0.5).abs() < 1e-6
+        );
+        assert_eq!(YCbCr::new([42u8, 128, 128]).luminance(), 42.);
+    }
 }
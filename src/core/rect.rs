@@ -2,6 +2,8 @@
 
 use core::{Image2D, Pixel, Region};
 
+use failure::Error;
+
 use std::cmp::{max, min};
 
 /// Represent a rectangle
@@ -15,18 +17,26 @@ pub struct Rect {
 
 impl Rect {
     /// Create a new `Rect`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `w` or `h` is zero. Use `try_new` to handle that case gracefully.
     pub fn new(x: u32, y: u32, w: u32, h: u32) -> Rect {
-        // TODO: Result
-        assert!(
+        Rect::try_new(x, y, w, h).unwrap()
+    }
+
+    /// Create a new `Rect`, or return an error if `w` or `h` is zero.
+    pub fn try_new(x: u32, y: u32, w: u32, h: u32) -> Result<Rect, Error> {
+        ensure!(
             w != 0 && h != 0,
             "Rect dimensions must be strictly positive."
         );
-        Rect {
+        Ok(Rect {
             left: x,
             top: y,
             width: w,
             height: h,
-        }
+        })
     }
 
     /// Return the left coordinate of the `Rect`
@@ -69,6 +79,24 @@ impl Rect {
         (self.width, self.height)
     }
 
+    /// Return the area of the `Rect`, i.e. its number of points.
+    pub fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    /// Return the aspect ratio of the `Rect`, i.e. its width divided by its height.
+    pub fn aspect_ratio(&self) -> f64 {
+        f64::from(self.width) / f64::from(self.height)
+    }
+
+    /// Test whether `other` is fully inside the `Rect`.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.left() >= self.left()
+            && other.top() >= self.top()
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
     /// Return the intersection of two `Rect`s if it exists, `None` otherwise.
     pub fn intersection(&self, other: &Rect) -> Option<Rect> {
         let left = max(self.left(), other.left());
@@ -83,6 +111,68 @@ impl Rect {
         }
     }
 
+    /// Return the smallest `Rect` containing both `self` and `other`.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let left = min(self.left(), other.left());
+        let top = min(self.top(), other.top());
+        let right = max(self.right(), other.right());
+        let bottom = max(self.bottom(), other.bottom());
+        Rect::new(left, top, right - left + 1, bottom - top + 1)
+    }
+
+    /// Return the `Rect` shifted by `(dx, dy)`, or `None` if it would move to negative coordinates or
+    /// out of the `u32` range.
+    pub fn translate(&self, dx: i32, dy: i32) -> Option<Rect> {
+        let left = i64::from(self.left) + i64::from(dx);
+        let top = i64::from(self.top) + i64::from(dy);
+        let max_left = i64::from(u32::max_value() - self.width) + 1;
+        let max_top = i64::from(u32::max_value() - self.height) + 1;
+        if left < 0 || top < 0 || left > max_left || top > max_top {
+            return None;
+        }
+        Some(Rect::new(left as u32, top as u32, self.width, self.height))
+    }
+
+    /// Return the `Rect` shrunk by `margin` on all sides, or `None` if nothing would be left of it.
+    pub fn inset(&self, margin: u32) -> Option<Rect> {
+        let shrink = u64::from(margin) * 2;
+        if shrink >= u64::from(self.width) || shrink >= u64::from(self.height) {
+            return None;
+        }
+        let shrink = shrink as u32;
+        Some(Rect::new(
+            self.left + margin,
+            self.top + margin,
+            self.width - shrink,
+            self.height - shrink,
+        ))
+    }
+
+    /// Return the `Rect` grown by `margin` on all sides, or `None` if it would extend to negative coordinates or
+    /// out of the `u32` range.
+    pub fn expand(&self, margin: u32) -> Option<Rect> {
+        if margin > self.left || margin > self.top {
+            return None;
+        }
+        let grow = u64::from(margin) * 2;
+        let (w, h) = (u64::from(self.width) + grow, u64::from(self.height) + grow);
+        let (left, top) = (self.left - margin, self.top - margin);
+        let max = u64::from(u32::max_value());
+        if w > max || h > max || u64::from(left) + w > max + 1 || u64::from(top) + h > max + 1 {
+            return None;
+        }
+        Some(Rect::new(left, top, w as u32, h as u32))
+    }
+
+    /// Return an iterator over the `(x, y)` coordinates of all the points of the `Rect`, in scanline order.
+    pub fn iter_coords(&self) -> CoordsIter {
+        CoordsIter {
+            rect: *self,
+            next: 0,
+            len: self.area(),
+        }
+    }
+
     /// Test whether the Rect fits in the given image.
     pub fn fits_image<P>(&self, img: &Image2D<P>) -> bool
     where
@@ -96,11 +186,41 @@ impl Rect {
     where
         P: Pixel,
     {
-        let r = Rect::new(0, 0, img.width(), img.height());
-        self.intersection(&r)
+        Rect::try_new(0, 0, img.width(), img.height())
+            .ok()
+            .and_then(|r| self.intersection(&r))
+    }
+}
+
+/// Iterator over the coordinates of the points of a `Rect`. Created by `Rect`'s `iter_coords` method.
+#[derive(Debug, Clone)]
+pub struct CoordsIter {
+    rect: Rect,
+    next: u64,
+    len: u64,
+}
+
+impl Iterator for CoordsIter {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<(u32, u32)> {
+        if self.next == self.len {
+            return None;
+        }
+        let w = u64::from(self.rect.width);
+        let (x, y) = ((self.next % w) as u32, (self.next / w) as u32);
+        self.next += 1;
+        Some((self.rect.left + x, self.rect.top + y))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.len - self.next) as usize;
+        (len, Some(len))
     }
 }
 
+impl ExactSizeIterator for CoordsIter {}
+
 impl Region for Rect {
     fn contains(&self, x: u32, y: u32) -> bool {
         x >= self.left() && y >= self.top() && x <= self.right() && y <= self.bottom()
@@ -120,6 +240,39 @@ mod tests {
         assert_eq!(r.bottom(), 9);
     }
 
+    #[test]
+    fn test_try_new() {
+        assert!(Rect::try_new(0, 0, 0, 5).is_err());
+        assert!(Rect::try_new(0, 0, 5, 0).is_err());
+        assert_eq!(Rect::try_new(0, 0, 5, 5).unwrap(), Rect::new(0, 0, 5, 5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_zero_size() {
+        Rect::new(0, 0, 0, 5);
+    }
+
+    #[test]
+    fn test_area_aspect_ratio() {
+        assert_eq!(Rect::new(3, 4, 20, 10).area(), 200);
+        let big = Rect::new(0, 0, u32::max_value(), u32::max_value());
+        assert_eq!(big.area(), u64::from(u32::max_value()).pow(2));
+        assert_eq!(Rect::new(3, 4, 20, 10).aspect_ratio(), 2.);
+        assert_eq!(Rect::new(0, 0, 3, 4).aspect_ratio(), 0.75);
+    }
+
+    #[test]
+    fn test_contains_rect() {
+        let r = Rect::new(0, 0, 100, 100);
+        assert!(r.contains_rect(&Rect::new(10, 10, 50, 50)));
+        assert!(r.contains_rect(&r));
+        assert!(r.contains_rect(&Rect::new(99, 0, 1, 100)));
+        assert!(!r.contains_rect(&Rect::new(60, 60, 50, 50)));
+        assert!(!r.contains_rect(&Rect::new(100, 0, 1, 1)));
+        assert!(!Rect::new(10, 10, 50, 50).contains_rect(&r));
+    }
+
     #[test]
     fn test_intersection() {
         let r1 = Rect::new(0, 0, 150, 150);
@@ -130,6 +283,54 @@ mod tests {
         assert_eq!(r1.intersection(&r3), Some(Rect::new(0, 140, 150, 10)));
     }
 
+    #[test]
+    fn test_union() {
+        let r1 = Rect::new(0, 0, 10, 10);
+        let r2 = Rect::new(20, 20, 5, 5);
+        let r3 = Rect::new(2, 3, 4, 5);
+
+        assert_eq!(r1.union(&r2), Rect::new(0, 0, 25, 25));
+        assert_eq!(r2.union(&r1), Rect::new(0, 0, 25, 25));
+        assert_eq!(r1.union(&r1), r1);
+        assert_eq!(r1.union(&r3), r1);
+        assert_eq!(r3.union(&r1), r1);
+    }
+
+    #[test]
+    fn test_translate() {
+        let r = Rect::new(5, 10, 20, 30);
+        assert_eq!(r.translate(3, -4), Some(Rect::new(8, 6, 20, 30)));
+        assert_eq!(r.translate(-5, -10), Some(Rect::new(0, 0, 20, 30)));
+        assert_eq!(r.translate(-6, 0), None);
+        assert_eq!(r.translate(0, -11), None);
+        let r = Rect::new(u32::max_value() - 1, 0, 1, 1);
+        assert_eq!(
+            r.translate(1, 0),
+            Some(Rect::new(u32::max_value(), 0, 1, 1))
+        );
+        assert_eq!(r.translate(2, 0), None);
+    }
+
+    #[test]
+    fn test_inset_expand() {
+        let r = Rect::new(0, 0, 10, 10);
+        assert_eq!(r.inset(2), Some(Rect::new(2, 2, 6, 6)));
+        assert_eq!(r.inset(4), Some(Rect::new(4, 4, 2, 2)));
+        assert_eq!(r.inset(5), None);
+        assert_eq!(r.inset(0), Some(r));
+        assert_eq!(Rect::new(3, 3, 10, 2).inset(1), None);
+
+        assert_eq!(r.expand(1), None);
+        assert_eq!(r.expand(0), Some(r));
+        let r = Rect::new(2, 2, 6, 6);
+        assert_eq!(r.expand(2), Some(Rect::new(0, 0, 10, 10)));
+        assert_eq!(r.expand(2).and_then(|r| r.inset(2)), Some(r));
+        let r = Rect::new(1, 1, u32::max_value() - 2, 1);
+        assert_eq!(r.expand(1), Some(Rect::new(0, 0, u32::max_value(), 3)));
+        let r = Rect::new(1, 1, u32::max_value() - 1, 1);
+        assert_eq!(r.expand(1), None);
+    }
+
     #[test]
     fn test_crop_to_image() {
         let r1 = Rect::new(500, 500, 500, 500);
@@ -137,6 +338,8 @@ mod tests {
         let img: ImageBuffer2D<Luma<u8>> = ImageBuffer2D::new(800, 600);
         assert_eq!(r1.crop_to_image(&img), Some(Rect::new(500, 500, 300, 100)));
         assert_eq!(r2.crop_to_image(&img), None);
+        let empty: ImageBuffer2D<Luma<u8>> = ImageBuffer2D::new(0, 0);
+        assert_eq!(r1.crop_to_image(&empty), None);
     }
 
     #[test]
@@ -153,6 +356,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_iter_coords() {
+        let r = Rect::new(1, 1, 2, 3);
+        assert_eq!(r.iter_coords().len(), 6);
+        let coords: Vec<_> = r.iter_coords().collect();
+        assert_eq!(coords, vec![(1, 1), (2, 1), (1, 2), (2, 2), (1, 3), (2, 3)]);
+        assert!(coords.iter().all(|&(x, y)| r.contains(x, y)));
+    }
+
     #[test]
     fn test_fits_image() {
         let r1 = Rect::new(10, 10, 32, 32);
@@ -1,6 +1,6 @@
 //! Contains the definitions of the various traits used in this crate.
 
-use num_traits::{Bounded, NumAssign, NumCast, NumRef, Zero};
+use num_traits::{cast, Bounded, NumAssign, NumCast, NumRef, Zero};
 #[cfg(feature = "rand_integration")]
 use rand::{
     distributions::{Distribution, Standard}, Rng,
@@ -60,6 +60,9 @@ pub trait Pixel: Clone + PartialEq + Sync + Send + Zero {
     /// number of channels in the pixel.
     fn from_slice(s: &[Self::Subpixel]) -> Self;
 
+    /// Create a new pixel with all channels set to the specified value.
+    fn from_value(s: Self::Subpixel) -> Self;
+
     /// Set the value of the pixel from a slice.
     ///
     /// **Panics**: the length of the slice is not checked, so this function will panic if s.len() is less than the
@@ -85,6 +88,24 @@ pub trait Pixel: Clone + PartialEq + Sync + Send + Zero {
     where
         F: Fn(Self::Subpixel) -> Self::Subpixel;
 
+    /// Clamp every channel of the pixel in place to the `[low, high]` range.
+    fn clamp(&mut self, low: Self::Subpixel, high: Self::Subpixel) {
+        for c in self.channels_mut() {
+            if *c < low {
+                *c = low;
+            } else if *c > high {
+                *c = high;
+            }
+        }
+    }
+
+    /// Return a copy of the pixel with every channel clamped to the `[low, high]` range.
+    fn clamped(&self, low: Self::Subpixel, high: Self::Subpixel) -> Self {
+        let mut p = self.clone();
+        p.clamp(low, high);
+        p
+    }
+
     /// Compute the sum of the pixel components.
     fn sum(&self) -> Self::Subpixel
     where
@@ -94,6 +115,17 @@ pub trait Pixel: Clone + PartialEq + Sync + Send + Zero {
             .iter()
             .fold(Self::Subpixel::zero(), |s1, s2| s1 + *s2)
     }
+
+    /// Return the perceived brightness of the pixel, in the same range as its subpixels. The default implementation
+    /// averages the channels.
+    fn luminance(&self) -> f64 {
+        let channels = self.channels();
+        channels
+            .iter()
+            .map(|c| cast::<Self::Subpixel, f64>(*c).unwrap_or(0.))
+            .sum::<f64>()
+            / channels.len() as f64
+    }
 }
 
 /// Trait for types representing image regions.
@@ -108,15 +140,14 @@ pub trait Region {
 /// this restriction is not enforced. However, all implementations of this trait by pixel within this crate are bounded
 /// to only cast between related pixel types only differing by their subpixel associated type. If you're implementing
 /// your own pixel types, you should probably do the same.
-pub trait PixelCast<P, S, O>: Pixel<Subpixel = S>
+pub trait PixelCast<O>: Pixel
 where
-    P: Pixel<Subpixel = O>,
     O: Primitive,
-    S: Primitive,
 {
-    /// Cast `other` into Self and assign the value to self.
-    fn cast_from(&mut self, other: &P);
+    /// Pixel type resulting from the cast.
+    type Output: Pixel<Subpixel = O>;
 
-    /// Cast self into P and assign the value to `other`.
-    fn cast_to(&self, other: &mut P);
+    /// Cast each channel of the pixel to the `O` subpixel type. Channels whose value can't be represented by `O` are
+    /// set to zero.
+    fn cast(&self) -> <Self as PixelCast<O>>::Output;
 }
@@ -0,0 +1,548 @@
+//! Contains functions for drawing shapes onto images.
+
+use core::{Image2DMut, Pixel, Rect};
+
+use std::cmp::{max, min};
+
+// Set the pixel at the specified coordinates if it lies within the image bounds.
+fn put_pixel_clipped<P>(img: &mut Image2DMut<P>, x: i64, y: i64, color: &P)
+where
+    P: Pixel,
+{
+    if x >= 0 && y >= 0 && x < i64::from(img.width()) && y < i64::from(img.height()) {
+        img.put_pixel(x as u32, y as u32, color.clone());
+    }
+}
+
+/// Draw a line segment between two points using Bresenham's algorithm. Both endpoints are included in the segment.
+/// The segment is clipped to the image before being drawn, so the cost only depends on the length of its visible part.
+pub fn draw_line<P>(img: &mut Image2DMut<P>, from: (i32, i32), to: (i32, i32), color: P)
+where
+    P: Pixel,
+{
+    let (x0, y0) = (i64::from(from.0), i64::from(from.1));
+    let (x1, y1) = (i64::from(to.0), i64::from(to.1));
+    let (w, h) = (i64::from(img.width()), i64::from(img.height()));
+
+    // Skip segments whose bounding box does not intersect the image.
+    if x0.max(x1) < 0 || y0.max(y1) < 0 || x0.min(x1) >= w || y0.min(y1) >= h {
+        return;
+    }
+
+    // Walk along the major axis. The pixel at step `k` is offset by `round(k * d_minor / d_major)` along the minor
+    // axis, ties being rounded away from the starting point, which is what the incremental error term of Bresenham's
+    // algorithm computes.
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    let (major0, major1, minor0, minor1, major_len) = if steep {
+        (y0, y1, x0, x1, h)
+    } else {
+        (x0, x1, y0, y1, w)
+    };
+    let d_major = (major1 - major0).abs();
+    let d_minor = (minor1 - minor0).abs();
+    let s_major = if major0 < major1 { 1 } else { -1 };
+    let s_minor = if minor0 < minor1 { 1 } else { -1 };
+
+    // Clip the range of steps to the part of the segment whose major coordinate lies within the image.
+    let (k_first, k_last) = if s_major > 0 {
+        (max(0, -major0), min(d_major, major_len - 1 - major0))
+    } else {
+        (max(0, major0 - (major_len - 1)), min(d_major, major0))
+    };
+    for k in k_first..=k_last {
+        let major = major0 + s_major * k;
+        let minor = if d_major == 0 {
+            minor0
+        } else {
+            let offset = (2 * i128::from(k) * i128::from(d_minor) + i128::from(d_major))
+                / (2 * i128::from(d_major));
+            minor0 + s_minor * offset as i64
+        };
+        if steep {
+            put_pixel_clipped(img, minor, major, &color);
+        } else {
+            put_pixel_clipped(img, major, minor, &color);
+        }
+    }
+}
+
+/// Draw the outline of a `Rect`. The parts of the outline falling outside of the image are ignored.
+pub fn draw_rect<P>(img: &mut Image2DMut<P>, rect: Rect, color: P)
+where
+    P: Pixel,
+{
+    let (left, top) = (i64::from(rect.left()), i64::from(rect.top()));
+    let (right, bottom) = (i64::from(rect.right()), i64::from(rect.bottom()));
+    let (w, h) = (i64::from(img.width()), i64::from(img.height()));
+
+    for x in max(left, 0)..=min(right, w - 1) {
+        put_pixel_clipped(img, x, top, &color);
+        put_pixel_clipped(img, x, bottom, &color);
+    }
+    for y in max(top, 0)..=min(bottom, h - 1) {
+        put_pixel_clipped(img, left, y, &color);
+        put_pixel_clipped(img, right, y, &color);
+    }
+}
+
+// Return the range of coordinates covered by an arm of a cross centered on `center` along an axis of length `limit`,
+// clamped to the image.
+fn arm_bounds(center: i64, arm: u32, limit: i64) -> (i64, i64) {
+    let arm = i64::from(arm);
+    (max(center - arm, 0), min(center + arm, limit - 1))
+}
+
+/// Draw a cross centered on `center`, made of a horizontal and a vertical line with arms of `arm` pixels on each side
+/// of the center. The parts of the cross falling outside of the image are ignored.
+pub fn draw_cross<P>(img: &mut Image2DMut<P>, center: (i32, i32), arm: u32, color: P)
+where
+    P: Pixel,
+{
+    let (cx, cy) = (i64::from(center.0), i64::from(center.1));
+    let (left, right) = arm_bounds(cx, arm, i64::from(img.width()));
+    let (top, bottom) = arm_bounds(cy, arm, i64::from(img.height()));
+    for x in left..=right {
+        put_pixel_clipped(img, x, cy, &color);
+    }
+    for y in top..=bottom {
+        put_pixel_clipped(img, cx, y, &color);
+    }
+}
+
+/// Fill a `Rect` with the given color. Unlike `Image2DMut::fill_rect`, the `Rect` is cropped to the image bounds
+/// instead of panicking.
+pub fn fill_rect<P>(img: &mut Image2DMut<P>, rect: Rect, color: P)
+where
+    P: Pixel,
+{
+    if let Some(r) = rect.intersection(&img.rect()) {
+        img.fill_rect(r, &color);
+    }
+}
+
+// Draw a horizontal line between x0 and x1 (inclusive), ignoring the parts falling outside of the image.
+fn draw_hline_clipped<P>(img: &mut Image2DMut<P>, x0: i64, x1: i64, y: i64, color: &P)
+where
+    P: Pixel,
+{
+    if y < 0 || y >= i64::from(img.height()) {
+        return;
+    }
+    for x in max(x0, 0)..=min(x1, i64::from(img.width()) - 1) {
+        img.put_pixel(x as u32, y as u32, color.clone());
+    }
+}
+
+// Call `f` with the offsets of the first octant of a circle of the given radius, computed with the midpoint circle
+// algorithm.
+fn midpoint_circle<F>(radius: u32, mut f: F)
+where
+    F: FnMut(i64, i64),
+{
+    let mut x = i64::from(radius);
+    let mut y = 0;
+    let mut err = 1 - x;
+    while x >= y {
+        f(x, y);
+        y += 1;
+        if err < 0 {
+            err += 2 * y + 1;
+        } else {
+            x -= 1;
+            err += 2 * (y - x) + 1;
+        }
+    }
+}
+
+/// Draw the outline of a circle using the midpoint circle algorithm. The parts of the circle falling outside of the
+/// image are ignored.
+pub fn draw_circle<P>(img: &mut Image2DMut<P>, center: (i32, i32), radius: u32, color: P)
+where
+    P: Pixel,
+{
+    let (cx, cy) = (i64::from(center.0), i64::from(center.1));
+    midpoint_circle(radius, |x, y| {
+        for &(dx, dy) in &[(x, y), (y, x)] {
+            put_pixel_clipped(img, cx + dx, cy + dy, &color);
+            put_pixel_clipped(img, cx - dx, cy + dy, &color);
+            put_pixel_clipped(img, cx + dx, cy - dy, &color);
+            put_pixel_clipped(img, cx - dx, cy - dy, &color);
+        }
+    });
+}
+
+/// Draw a filled circle. The parts of the circle falling outside of the image are ignored.
+pub fn draw_filled_circle<P>(img: &mut Image2DMut<P>, center: (i32, i32), radius: u32, color: P)
+where
+    P: Pixel,
+{
+    let (cx, cy) = (i64::from(center.0), i64::from(center.1));
+    midpoint_circle(radius, |x, y| {
+        draw_hline_clipped(img, cx - x, cx + x, cy + y, &color);
+        draw_hline_clipped(img, cx - x, cx + x, cy - y, &color);
+        draw_hline_clipped(img, cx - y, cx + y, cy + x, &color);
+        draw_hline_clipped(img, cx - y, cx + y, cy - x, &color);
+    });
+}
+
+/// Width and height in pixels of the glyphs of the font used by `draw_text`.
+pub const GLYPH_SIZE: u32 = 8;
+
+// 8x8 bitmap font covering the printable ASCII characters, from ' ' (0x20) to '~' (0x7E). Each glyph is stored as 8
+// rows from top to bottom, the lowest bit of each row being its leftmost pixel.
+const FONT: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x08, 0x00], // '!'
+    [0x14, 0x14, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x14, 0x14, 0x3E, 0x14, 0x3E, 0x14, 0x14, 0x00], // '#'
+    [0x08, 0x3C, 0x0A, 0x1C, 0x28, 0x1E, 0x08, 0x00], // '$'
+    [0x06, 0x26, 0x10, 0x08, 0x04, 0x32, 0x30, 0x00], // '%'
+    [0x0C, 0x12, 0x0A, 0x04, 0x2A, 0x12, 0x2C, 0x00], // '&'
+    [0x08, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00], // '\''
+    [0x10, 0x08, 0x04, 0x04, 0x04, 0x08, 0x10, 0x00], // '('
+    [0x04, 0x08, 0x10, 0x10, 0x10, 0x08, 0x04, 0x00], // ')'
+    [0x00, 0x08, 0x2A, 0x1C, 0x2A, 0x08, 0x00, 0x00], // '*'
+    [0x00, 0x08, 0x08, 0x3E, 0x08, 0x08, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x08, 0x04, 0x00], // ','
+    [0x00, 0x00, 0x00, 0x3E, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // '.'
+    [0x00, 0x20, 0x10, 0x08, 0x04, 0x02, 0x00, 0x00], // '/'
+    [0x1C, 0x22, 0x32, 0x2A, 0x26, 0x22, 0x1C, 0x00], // '0'
+    [0x08, 0x0C, 0x08, 0x08, 0x08, 0x08, 0x1C, 0x00], // '1'
+    [0x1C, 0x22, 0x20, 0x10, 0x08, 0x04, 0x3E, 0x00], // '2'
+    [0x3E, 0x10, 0x08, 0x10, 0x20, 0x22, 0x1C, 0x00], // '3'
+    [0x10, 0x18, 0x14, 0x12, 0x3E, 0x10, 0x10, 0x00], // '4'
+    [0x3E, 0x02, 0x1E, 0x20, 0x20, 0x22, 0x1C, 0x00], // '5'
+    [0x18, 0x04, 0x02, 0x1E, 0x22, 0x22, 0x1C, 0x00], // '6'
+    [0x3E, 0x20, 0x10, 0x08, 0x04, 0x04, 0x04, 0x00], // '7'
+    [0x1C, 0x22, 0x22, 0x1C, 0x22, 0x22, 0x1C, 0x00], // '8'
+    [0x1C, 0x22, 0x22, 0x3C, 0x20, 0x10, 0x0C, 0x00], // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00, 0x00], // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x08, 0x04, 0x00], // ';'
+    [0x10, 0x08, 0x04, 0x02, 0x04, 0x08, 0x10, 0x00], // '<'
+    [0x00, 0x00, 0x3E, 0x00, 0x3E, 0x00, 0x00, 0x00], // '='
+    [0x04, 0x08, 0x10, 0x20, 0x10, 0x08, 0x04, 0x00], // '>'
+    [0x1C, 0x22, 0x20, 0x10, 0x08, 0x00, 0x08, 0x00], // '?'
+    [0x1C, 0x22, 0x20, 0x2C, 0x2A, 0x2A, 0x1C, 0x00], // '@'
+    [0x1C, 0x22, 0x22, 0x3E, 0x22, 0x22, 0x22, 0x00], // 'A'
+    [0x1E, 0x22, 0x22, 0x1E, 0x22, 0x22, 0x1E, 0x00], // 'B'
+    [0x1C, 0x22, 0x02, 0x02, 0x02, 0x22, 0x1C, 0x00], // 'C'
+    [0x0E, 0x12, 0x22, 0x22, 0x22, 0x12, 0x0E, 0x00], // 'D'
+    [0x3E, 0x02, 0x02, 0x1E, 0x02, 0x02, 0x3E, 0x00], // 'E'
+    [0x3E, 0x02, 0x02, 0x1E, 0x02, 0x02, 0x02, 0x00], // 'F'
+    [0x1C, 0x22, 0x02, 0x3A, 0x22, 0x22, 0x3C, 0x00], // 'G'
+    [0x22, 0x22, 0x22, 0x3E, 0x22, 0x22, 0x22, 0x00], // 'H'
+    [0x1C, 0x08, 0x08, 0x08, 0x08, 0x08, 0x1C, 0x00], // 'I'
+    [0x38, 0x10, 0x10, 0x10, 0x10, 0x12, 0x0C, 0x00], // 'J'
+    [0x22, 0x12, 0x0A, 0x06, 0x0A, 0x12, 0x22, 0x00], // 'K'
+    [0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x3E, 0x00], // 'L'
+    [0x22, 0x36, 0x2A, 0x2A, 0x22, 0x22, 0x22, 0x00], // 'M'
+    [0x22, 0x22, 0x26, 0x2A, 0x32, 0x22, 0x22, 0x00], // 'N'
+    [0x1C, 0x22, 0x22, 0x22, 0x22, 0x22, 0x1C, 0x00], // 'O'
+    [0x1E, 0x22, 0x22, 0x1E, 0x02, 0x02, 0x02, 0x00], // 'P'
+    [0x1C, 0x22, 0x22, 0x22, 0x2A, 0x12, 0x2C, 0x00], // 'Q'
+    [0x1E, 0x22, 0x22, 0x1E, 0x0A, 0x12, 0x22, 0x00], // 'R'
+    [0x3C, 0x02, 0x02, 0x1C, 0x20, 0x20, 0x1E, 0x00], // 'S'
+    [0x3E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00], // 'T'
+    [0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x1C, 0x00], // 'U'
+    [0x22, 0x22, 0x22, 0x22, 0x22, 0x14, 0x08, 0x00], // 'V'
+    [0x22, 0x22, 0x22, 0x2A, 0x2A, 0x2A, 0x14, 0x00], // 'W'
+    [0x22, 0x22, 0x14, 0x08, 0x14, 0x22, 0x22, 0x00], // 'X'
+    [0x22, 0x22, 0x14, 0x08, 0x08, 0x08, 0x08, 0x00], // 'Y'
+    [0x3E, 0x20, 0x10, 0x08, 0x04, 0x02, 0x3E, 0x00], // 'Z'
+    [0x1C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x1C, 0x00], // '['
+    [0x00, 0x02, 0x04, 0x08, 0x10, 0x20, 0x00, 0x00], // '\\'
+    [0x1C, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1C, 0x00], // ']'
+    [0x08, 0x14, 0x22, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3E, 0x00], // '_'
+    [0x04, 0x08, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x1C, 0x20, 0x3C, 0x22, 0x3C, 0x00], // 'a'
+    [0x02, 0x02, 0x1A, 0x26, 0x22, 0x22, 0x1E, 0x00], // 'b'
+    [0x00, 0x00, 0x1C, 0x02, 0x02, 0x22, 0x1C, 0x00], // 'c'
+    [0x20, 0x20, 0x2C, 0x32, 0x22, 0x22, 0x3C, 0x00], // 'd'
+    [0x00, 0x00, 0x1C, 0x22, 0x3E, 0x02, 0x1C, 0x00], // 'e'
+    [0x18, 0x24, 0x04, 0x0E, 0x04, 0x04, 0x04, 0x00], // 'f'
+    [0x00, 0x00, 0x3C, 0x22, 0x22, 0x3C, 0x20, 0x1C], // 'g'
+    [0x02, 0x02, 0x1A, 0x26, 0x22, 0x22, 0x22, 0x00], // 'h'
+    [0x08, 0x00, 0x0C, 0x08, 0x08, 0x08, 0x1C, 0x00], // 'i'
+    [0x10, 0x00, 0x18, 0x10, 0x10, 0x10, 0x12, 0x0C], // 'j'
+    [0x02, 0x02, 0x12, 0x0A, 0x06, 0x0A, 0x12, 0x00], // 'k'
+    [0x0C, 0x08, 0x08, 0x08, 0x08, 0x08, 0x1C, 0x00], // 'l'
+    [0x00, 0x00, 0x16, 0x2A, 0x2A, 0x22, 0x22, 0x00], // 'm'
+    [0x00, 0x00, 0x1A, 0x26, 0x22, 0x22, 0x22, 0x00], // 'n'
+    [0x00, 0x00, 0x1C, 0x22, 0x22, 0x22, 0x1C, 0x00], // 'o'
+    [0x00, 0x00, 0x1E, 0x22, 0x22, 0x1E, 0x02, 0x02], // 'p'
+    [0x00, 0x00, 0x3C, 0x22, 0x22, 0x3C, 0x20, 0x20], // 'q'
+    [0x00, 0x00, 0x1A, 0x26, 0x02, 0x02, 0x02, 0x00], // 'r'
+    [0x00, 0x00, 0x1C, 0x02, 0x1C, 0x20, 0x1E, 0x00], // 's'
+    [0x04, 0x04, 0x0E, 0x04, 0x04, 0x24, 0x18, 0x00], // 't'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x32, 0x2C, 0x00], // 'u'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x14, 0x08, 0x00], // 'v'
+    [0x00, 0x00, 0x22, 0x22, 0x2A, 0x2A, 0x14, 0x00], // 'w'
+    [0x00, 0x00, 0x22, 0x14, 0x08, 0x14, 0x22, 0x00], // 'x'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x3C, 0x20, 0x1C], // 'y'
+    [0x00, 0x00, 0x3E, 0x10, 0x08, 0x04, 0x3E, 0x00], // 'z'
+    [0x10, 0x08, 0x08, 0x04, 0x08, 0x08, 0x10, 0x00], // '{'
+    [0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00], // '|'
+    [0x04, 0x08, 0x08, 0x10, 0x08, 0x08, 0x04, 0x00], // '}'
+    [0x00, 0x00, 0x04, 0x2A, 0x10, 0x00, 0x00, 0x00], // '~'
+];
+
+/// Draw a line of text with a built-in 8x8 bitmap font, `pos` being the top left corner of the first glyph. Glyphs are
+/// drawn from left to right, each one advancing the pen by `GLYPH_SIZE` pixels. Characters that are not printable
+/// ASCII are rendered as `'?'`. The parts of the text falling outside of the image are ignored.
+pub fn draw_text<P>(img: &mut Image2DMut<P>, text: &str, pos: (u32, u32), color: P)
+where
+    P: Pixel,
+{
+    let (x0, y0) = (i64::from(pos.0), i64::from(pos.1));
+    for (i, c) in text.chars().enumerate() {
+        let c = if c >= ' ' && c <= '~' { c } else { '?' };
+        let glyph = &FONT[c as usize - 0x20];
+        let left = x0 + i as i64 * i64::from(GLYPH_SIZE);
+        if left >= i64::from(img.width()) {
+            break;
+        }
+        for (dy, row) in glyph.iter().enumerate() {
+            for dx in 0..GLYPH_SIZE {
+                if row & (1 << dx) != 0 {
+                    put_pixel_clipped(img, left + i64::from(dx), y0 + dy as i64, &color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Image2D, ImageBuffer2D, Luma, Rect, Region};
+
+    fn set_pixels(img: &ImageBuffer2D<Luma<u8>>) -> Vec<(u32, u32)> {
+        img.enumerate_pixels()
+            .filter(|(_, p)| p.data[0] != 0)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    #[test]
+    fn test_draw_line_diagonal() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(&mut img, (0, 0), (9, 9), Luma::new([255]));
+        let expected: Vec<(u32, u32)> = (0..10).map(|i| (i, i)).collect();
+        assert_eq!(set_pixels(&img), expected);
+    }
+
+    #[test]
+    fn test_draw_line_octants() {
+        // Steep line, drawn in both directions
+        let mut img1 = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        let mut img2 = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(&mut img1, (2, 0), (4, 9), Luma::new([255]));
+        draw_line(&mut img2, (4, 9), (2, 0), Luma::new([255]));
+        assert_eq!(set_pixels(&img1).len(), 10);
+        assert_eq!(set_pixels(&img2).len(), 10);
+
+        // Negative slope
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(&mut img, (0, 9), (9, 0), Luma::new([255]));
+        let mut expected: Vec<(u32, u32)> = (0..10).map(|i| (9 - i, i)).collect();
+        expected.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(set_pixels(&img), expected);
+    }
+
+    #[test]
+    fn test_draw_line_clipping() {
+        // Single point
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(&mut img, (3, 4), (3, 4), Luma::new([255]));
+        assert_eq!(set_pixels(&img), vec![(3, 4)]);
+
+        // Fully off-screen
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(&mut img, (-5, -5), (-1, 20), Luma::new([255]));
+        draw_line(&mut img, (10, 0), (20, 9), Luma::new([255]));
+        assert!(set_pixels(&img).is_empty());
+
+        // Partially off-screen
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(&mut img, (-5, 2), (15, 2), Luma::new([255]));
+        let expected: Vec<(u32, u32)> = (0..10).map(|x| (x, 2)).collect();
+        assert_eq!(set_pixels(&img), expected);
+
+        // Extreme off-screen endpoints, only the visible part of the segment is walked.
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(&mut img, (i32::MIN, 0), (i32::MAX, 0), Luma::new([255]));
+        draw_line(&mut img, (5, i32::MAX), (5, i32::MIN), Luma::new([255]));
+        let mut expected: Vec<(u32, u32)> = (0..10).map(|x| (x, 0)).collect();
+        expected.extend((1..10).map(|y| (5, y)));
+        expected.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(set_pixels(&img), expected);
+
+        // Clipping a diagonal keeps the pixels it goes through.
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_line(
+            &mut img,
+            (-1_000_000, -1_000_000),
+            (1_000_000, 1_000_000),
+            Luma::new([255]),
+        );
+        let expected: Vec<(u32, u32)> = (0..10).map(|i| (i, i)).collect();
+        assert_eq!(set_pixels(&img), expected);
+    }
+
+    #[test]
+    fn test_draw_cross() {
+        // Tall and narrow image, the vertical arm must be clipped against the height, not the width.
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(5, 20);
+        draw_cross(&mut img, (2, 17), 4, Luma::new([255]));
+        let vertical: Vec<u32> = set_pixels(&img)
+            .into_iter()
+            .filter(|&(x, y)| x == 2 && y != 17)
+            .map(|(_, y)| y)
+            .collect();
+        assert_eq!(vertical, vec![13, 14, 15, 16, 18, 19]);
+        assert_eq!(set_pixels(&img).len(), 5 + 6);
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(20, 5);
+        draw_cross(&mut img, (18, -1), 2, Luma::new([255]));
+        assert_eq!(set_pixels(&img), vec![(18, 0), (18, 1)]);
+    }
+
+    #[test]
+    fn test_draw_rect() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        let r = Rect::new(2, 2, 4, 4);
+        draw_rect(&mut img, r, Luma::new([255]));
+        for ((x, y), p) in img.enumerate_pixels() {
+            let on_border = r.contains(x, y)
+                && (x == r.left() || x == r.right() || y == r.top() || y == r.bottom());
+            assert_eq!(p.data[0] != 0, on_border, "pixel ({}, {})", x, y);
+        }
+        assert_eq!(set_pixels(&img).len(), 12);
+    }
+
+    #[test]
+    fn test_draw_rect_clipping() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_rect(&mut img, Rect::new(5, 5, 10, 10), Luma::new([255]));
+        // Only the top and left edges are visible.
+        assert_eq!(set_pixels(&img).len(), 9);
+        assert!(set_pixels(&img)
+            .iter()
+            .all(|&(x, y)| (x == 5 && y >= 5) || (y == 5 && x >= 5)));
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        draw_rect(&mut img, Rect::new(20, 20, 5, 5), Luma::new([255]));
+        assert!(set_pixels(&img).is_empty());
+    }
+
+    #[test]
+    fn test_fill_rect_clipping() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(10, 10);
+        fill_rect(&mut img, Rect::new(8, 8, 5, 5), Luma::new([255]));
+        assert_eq!(set_pixels(&img), vec![(8, 8), (9, 8), (8, 9), (9, 9)]);
+        fill_rect(&mut img, Rect::new(10, 10, 5, 5), Luma::new([255]));
+        assert_eq!(set_pixels(&img).len(), 4);
+    }
+
+    #[test]
+    fn test_draw_circle() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(20, 20);
+        draw_circle(&mut img, (10, 10), 3, Luma::new([255]));
+        let pixels = set_pixels(&img);
+        assert_eq!(pixels.len(), 16);
+        for &(x, y) in &pixels {
+            let (dx, dy) = (x as i32 - 10, y as i32 - 10);
+            // Every octant must contain the mirrored pixel.
+            for &(mx, my) in &[
+                (dx, dy),
+                (-dx, dy),
+                (dx, -dy),
+                (-dx, -dy),
+                (dy, dx),
+                (-dy, dx),
+                (dy, -dx),
+                (-dy, -dx),
+            ] {
+                assert_eq!(
+                    img.get_pixel((10 + mx) as u32, (10 + my) as u32).data[0],
+                    255
+                );
+            }
+        }
+        for &(x, y) in &[(13, 10), (7, 10), (10, 13), (10, 7), (12, 12), (8, 8)] {
+            assert!(pixels.contains(&(x, y)));
+        }
+    }
+
+    #[test]
+    fn test_draw_circle_edge_cases() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(20, 20);
+        draw_circle(&mut img, (4, 5), 0, Luma::new([255]));
+        assert_eq!(set_pixels(&img), vec![(4, 5)]);
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(20, 20);
+        draw_circle(&mut img, (-2, 10), 3, Luma::new([255]));
+        assert_eq!(
+            set_pixels(&img),
+            vec![(0, 8), (1, 9), (1, 10), (1, 11), (0, 12)]
+        );
+
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(20, 20);
+        draw_filled_circle(&mut img, (-2, 10), 3, Luma::new([255]));
+        assert_eq!(set_pixels(&img).len(), 8);
+    }
+
+    #[test]
+    fn test_draw_filled_circle() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(20, 20);
+        draw_filled_circle(&mut img, (10, 10), 3, Luma::new([255]));
+        let pixels = set_pixels(&img);
+        for &(x, y) in &pixels {
+            let (dx, dy) = (x as i32 - 10, y as i32 - 10);
+            assert!(dx * dx + dy * dy <= 3 * 3 + 3);
+        }
+        for y in 7..14 {
+            assert!(pixels.contains(&(10, y)));
+        }
+        assert_eq!(pixels.len(), 37);
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(20, 12);
+        draw_text(&mut img, "AB", (2, 3), Luma::new([255]));
+        let pixels = set_pixels(&img);
+        // Top of the 'A' and stem of the 'B'
+        for &(x, y) in &[(4, 3), (5, 3), (6, 3), (3, 4), (7, 4), (3, 6), (7, 6)] {
+            assert!(pixels.contains(&(x, y)));
+        }
+        for y in 3..10 {
+            assert!(pixels.contains(&(11, y)));
+        }
+        assert!(!pixels.contains(&(3, 3)));
+        assert!(pixels
+            .iter()
+            .all(|&(x, y)| x >= 3 && x < 18 && y >= 3 && y < 10));
+        // Each glyph drawn alone matches its part of the text
+        let mut b = ImageBuffer2D::<Luma<u8>>::new(20, 12);
+        draw_text(&mut b, "B", (10, 3), Luma::new([255]));
+        let b_pixels = set_pixels(&b);
+        assert!(b_pixels.iter().all(|p| pixels.contains(p)));
+        assert!(pixels.iter().filter(|&&(x, _)| x >= 10).count() == b_pixels.len());
+
+        // Clipping on the right and bottom edges
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(12, 6);
+        draw_text(&mut img, "AB", (2, 3), Luma::new([255]));
+        assert!(set_pixels(&img).contains(&(11, 3)));
+        assert!(set_pixels(&img).iter().all(|&(_, y)| y < 6));
+
+        // Unknown characters
+        let mut q1 = ImageBuffer2D::<Luma<u8>>::new(8, 8);
+        let mut q2 = ImageBuffer2D::<Luma<u8>>::new(8, 8);
+        draw_text(&mut q1, "é", (0, 0), Luma::new([255]));
+        draw_text(&mut q2, "?", (0, 0), Luma::new([255]));
+        assert_eq!(q1, q2);
+    }
+}
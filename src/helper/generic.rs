@@ -1,4 +1,6 @@
-use num_traits::{Float, NumCast};
+use core::Primitive;
+
+use num_traits::{cast, Float, NumCast};
 
 pub fn f64_to_float<T>(f: f64) -> T
 where
@@ -6,3 +8,53 @@ where
 {
     <T as NumCast>::from::<f64>(f).unwrap()
 }
+
+// Check whether a primitive type is an integer type, i.e. whether it cannot represent 0.5.
+pub fn is_integer<T>() -> bool
+where
+    T: Primitive,
+{
+    cast::<f64, T>(0.5).map_or(true, |v| cast::<T, f64>(v) == Some(0.))
+}
+
+// Return the value corresponding to full intensity for a subpixel type: its maximum value for integer types, 1 for
+// floating point types.
+pub fn full_intensity<T>() -> f64
+where
+    T: Primitive,
+{
+    if is_integer::<T>() {
+        cast::<T, f64>(T::max_value()).unwrap()
+    } else {
+        1.
+    }
+}
+
+// Convert a value computed on f64 to a subpixel type: the value is clamped to the range of the type, and rounded for
+// integer types, which would otherwise truncate it.
+pub fn from_f64_clamped<T>(v: f64) -> T
+where
+    T: Primitive,
+{
+    let min = cast::<T, f64>(T::min_value()).unwrap_or(::std::f64::MIN);
+    let max = cast::<T, f64>(T::max_value()).unwrap_or(::std::f64::MAX);
+    let v = v.max(min).min(max);
+    cast(if is_integer::<T>() { v.round() } else { v }).unwrap_or_else(T::zero)
+}
+
+// Composite the `top` channels over the `bottom` ones with the Porter-Duff "source over" operator, writing the result
+// to `out`. Channels are normalized to `[0, 1]`, the last one being alpha, and color channels are not premultiplied.
+// The color channels of fully transparent results are set to 0.
+pub fn source_over(top: &[f64], bottom: &[f64], out: &mut [f64]) {
+    let alpha_idx = out.len() - 1;
+    let (ta, ba) = (top[alpha_idx], bottom[alpha_idx]);
+    let out_a = ta + ba * (1. - ta);
+    for c in 0..alpha_idx {
+        out[c] = if out_a <= 0. {
+            0.
+        } else {
+            (top[c] * ta + bottom[c] * ba * (1. - ta)) / out_a
+        };
+    }
+    out[alpha_idx] = out_a;
+}
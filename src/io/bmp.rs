@@ -0,0 +1,542 @@
+//! BMP codec.
+
+use core::{
+    BitDepth, DynamicImage, Image2D, ImageBuffer2D, ImageType, Luma, LumaA, Pixel, PixelType, Rgb,
+    RgbA,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use failure::Error;
+
+use io::traits::{ImageDecoder, ImageEncoder};
+
+use std::io::{self, Read, Write};
+
+// Size of the BITMAPFILEHEADER structure.
+const FILE_HEADER_SIZE: u32 = 14;
+// Size of the BITMAPINFOHEADER structure, the smallest DIB header we support.
+const INFO_HEADER_SIZE: u32 = 40;
+// Value of the compression field for uncompressed images.
+const BI_RGB: u32 = 0;
+// Largest number of pixels accepted by the decoder, the equivalent of a 16384x16384 image.
+const MAX_PIXELS: u64 = 1 << 28;
+
+/// BMP decoder type
+pub struct Decoder<R>
+where
+    R: Read,
+{
+    reader: R,
+    channels: PixelType,
+    width: u32,
+    height: u32,
+    top_down: bool,
+    bit_count: u16,
+    palette: Vec<[u8; 3]>,
+}
+
+#[derive(Fail, Debug)]
+/// Represent the errors than can occur when decoding a BMP.
+pub enum DecodingError {
+    #[fail(display = "Internal decoder error")]
+    /// Internal decoder error. These should not actually occur, please report them if you encounter any.
+    Internal,
+    #[fail(display = "Incorrect pixel type, image type is {:?}({:?})", _0, _1)]
+    /// The requested type is not the actual type of the image
+    IncorrectPixelType(PixelType, BitDepth),
+    #[fail(display = "Not a BMP file")]
+    /// The file signature is not the one of a BMP file.
+    InvalidSignature,
+    #[fail(display = "Invalid BMP header")]
+    /// The image header is malformed.
+    InvalidHeader,
+    #[fail(display = "Unsupported BMP bit count {} with compression {}", _0, _1)]
+    /// The image type is not supported (yet) by the library.
+    UnsupportedType(u16, u32),
+    #[fail(display = "BMP image too large")]
+    /// The image data would exceed the 4GB size limit of the BMP format, or the image has more pixels than the
+    /// decoder accepts.
+    TooLarge,
+    #[fail(display = "Palette index {} is out of range", _0)]
+    /// The image data references a palette entry which does not exist.
+    InvalidPaletteIndex(u8),
+}
+
+// Return the size in bytes of a row of pixels, including the padding to a multiple of 4 bytes.
+fn row_stride(width: u32, bit_count: u16) -> usize {
+    ((width as usize * bit_count as usize + 31) / 32) * 4
+}
+
+// Discard `n` bytes from the reader.
+fn skip<R: Read>(reader: &mut R, n: u64) -> Result<(), Error> {
+    let skipped = io::copy(&mut reader.take(n), &mut io::sink())?;
+    ensure!(skipped == n, DecodingError::InvalidHeader);
+    Ok(())
+}
+
+impl<R> Decoder<R>
+where
+    R: Read,
+{
+    /// Create a new BMP decoder object.
+    pub fn new(mut buffer: R) -> Result<Decoder<R>, Error> {
+        // BITMAPFILEHEADER
+        let mut signature = [0u8; 2];
+        buffer.read_exact(&mut signature)?;
+        if &signature != b"BM" {
+            return Err(DecodingError::InvalidSignature.into());
+        }
+        let _file_size = buffer.read_u32::<LittleEndian>()?;
+        let _reserved = buffer.read_u32::<LittleEndian>()?;
+        let data_offset = buffer.read_u32::<LittleEndian>()?;
+
+        // BITMAPINFOHEADER, later versions of the header only append fields to it.
+        let header_size = buffer.read_u32::<LittleEndian>()?;
+        ensure!(
+            header_size >= INFO_HEADER_SIZE,
+            DecodingError::InvalidHeader
+        );
+        let width = buffer.read_i32::<LittleEndian>()?;
+        let height = buffer.read_i32::<LittleEndian>()?;
+        let _planes = buffer.read_u16::<LittleEndian>()?;
+        let bit_count = buffer.read_u16::<LittleEndian>()?;
+        let compression = buffer.read_u32::<LittleEndian>()?;
+        let _image_size = buffer.read_u32::<LittleEndian>()?;
+        let _x_ppm = buffer.read_i32::<LittleEndian>()?;
+        let _y_ppm = buffer.read_i32::<LittleEndian>()?;
+        let colors_used = buffer.read_u32::<LittleEndian>()?;
+        let _colors_important = buffer.read_u32::<LittleEndian>()?;
+        skip(&mut buffer, u64::from(header_size - INFO_HEADER_SIZE))?;
+
+        ensure!(
+            width > 0 && height != 0 && height != i32::min_value(),
+            DecodingError::InvalidHeader
+        );
+        if compression != BI_RGB || (bit_count != 8 && bit_count != 24) {
+            return Err(DecodingError::UnsupportedType(bit_count, compression).into());
+        }
+        // Reject images that can't fit in a BMP file or have too many pixels before allocating anything for them.
+        let data_size = row_stride(width as u32, bit_count) as u64 * u64::from(height.abs() as u32);
+        let n_pixels = u64::from(width as u32) * u64::from(height.abs() as u32);
+        if data_size > u64::from(u32::max_value()) || n_pixels > MAX_PIXELS {
+            return Err(DecodingError::TooLarge.into());
+        }
+
+        let mut palette = Vec::new();
+        if bit_count == 8 {
+            let n_colors = if colors_used == 0 { 256 } else { colors_used };
+            ensure!(n_colors <= 256, DecodingError::InvalidHeader);
+            for _ in 0..n_colors {
+                let mut entry = [0u8; 4];
+                buffer.read_exact(&mut entry)?;
+                palette.push([entry[2], entry[1], entry[0]]);
+            }
+        }
+
+        let consumed = FILE_HEADER_SIZE + header_size + 4 * palette.len() as u32;
+        ensure!(data_offset >= consumed, DecodingError::InvalidHeader);
+        skip(&mut buffer, u64::from(data_offset - consumed))?;
+
+        let channels = if bit_count == 8 && palette.iter().all(|c| c[0] == c[1] && c[1] == c[2]) {
+            PixelType::Luma
+        } else {
+            PixelType::Rgb
+        };
+        Ok(Decoder {
+            reader: buffer,
+            channels,
+            width: width as u32,
+            height: height.abs() as u32,
+            top_down: height < 0,
+            bit_count,
+            palette,
+        })
+    }
+
+    // Read the pixel rows and return them in top-down order, without padding. The buffer grows as rows are
+    // read, so that a truncated file can't cause a large allocation.
+    fn read_rows(&mut self) -> Result<Vec<u8>, Error> {
+        let stride = row_stride(self.width, self.bit_count);
+        let row_len = self.width as usize * self.bit_count as usize / 8;
+        let mut row = vec![0; stride];
+        let mut data = Vec::new();
+        for _ in 0..self.height {
+            self.reader.read_exact(&mut row)?;
+            data.extend_from_slice(&row[..row_len]);
+        }
+        if !self.top_down {
+            data = data
+                .chunks(row_len)
+                .rev()
+                .flat_map(|r| r.iter().cloned())
+                .collect();
+        }
+        Ok(data)
+    }
+
+    // Return the palette entry for the given index.
+    fn palette_entry(&self, i: u8) -> Result<[u8; 3], Error> {
+        match self.palette.get(i as usize) {
+            Some(c) => Ok(*c),
+            None => Err(DecodingError::InvalidPaletteIndex(i).into()),
+        }
+    }
+
+    /// Try reading the image as 8bit grayscale.
+    pub fn read_luma_u8(mut self) -> Result<ImageBuffer2D<Luma<u8>>, Error> {
+        match self.channels {
+            PixelType::Luma => {
+                let data = self.read_rows()?;
+                let mut luma_buffer = Vec::with_capacity(data.len());
+                for &i in &data {
+                    luma_buffer.push(Luma {
+                        data: [self.palette_entry(i)?[0]],
+                    });
+                }
+                Ok(ImageBuffer2D::from_vec(
+                    self.width,
+                    self.height,
+                    luma_buffer,
+                )?)
+            }
+            _ => Err(DecodingError::IncorrectPixelType(self.channels, BitDepth::_8).into()),
+        }
+    }
+
+    /// Try reading the image as RGB 8bit.
+    pub fn read_rgb_u8(mut self) -> Result<ImageBuffer2D<Rgb<u8>>, Error> {
+        match self.channels {
+            PixelType::Rgb => {
+                let data = self.read_rows()?;
+                let mut rgb_buffer = Vec::with_capacity(self.width as usize * self.height as usize);
+                if self.bit_count == 8 {
+                    for &i in &data {
+                        rgb_buffer.push(Rgb {
+                            data: self.palette_entry(i)?,
+                        });
+                    }
+                } else {
+                    for s in data.chunks(3) {
+                        rgb_buffer.push(Rgb {
+                            data: [s[2], s[1], s[0]],
+                        });
+                    }
+                }
+                Ok(ImageBuffer2D::from_vec(
+                    self.width,
+                    self.height,
+                    rgb_buffer,
+                )?)
+            }
+            _ => Err(DecodingError::IncorrectPixelType(self.channels, BitDepth::_8).into()),
+        }
+    }
+
+    /// Return the number of channels in the image.
+    pub fn image_channels(&self) -> PixelType {
+        self.channels
+    }
+
+    /// Return the image bit depth.
+    pub fn depth(&self) -> BitDepth {
+        BitDepth::_8
+    }
+
+    /// Return the image dimensions.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl<R> ImageDecoder for Decoder<R>
+where
+    R: Read,
+{
+    fn read_header(&mut self) -> Result<ImageType, Error> {
+        Ok((self.image_channels(), self.depth()))
+    }
+
+    fn read_image(mut self) -> Result<DynamicImage, Error> {
+        match self.read_header()? {
+            (PixelType::Luma, BitDepth::_8) => {
+                Ok(DynamicImage::LumaU8(Box::new(self.read_luma_u8()?)))
+            }
+            (PixelType::Rgb, BitDepth::_8) => {
+                Ok(DynamicImage::RgbU8(Box::new(self.read_rgb_u8()?)))
+            }
+            _ => Err(DecodingError::Internal.into()),
+        }
+    }
+}
+
+#[derive(Fail, Debug)]
+/// Represent the errors than can occur when encoding to a BMP.
+pub enum EncodingError {
+    #[fail(display = "Unsupported pixel type")]
+    /// The image type is not supported (yet) by the library or by the BMP format.
+    UnsupportedType(),
+    #[fail(display = "Image is too large to be encoded as a BMP")]
+    /// The image dimensions do not fit in the BMP header fields.
+    TooLarge,
+}
+
+#[derive(Debug, Clone, Default)]
+/// BMP encoder type
+pub struct Encoder;
+
+impl Encoder {
+    /// Create a new BMP encoder object.
+    pub fn new() -> Encoder {
+        Encoder::default()
+    }
+
+    /// Write to the output buffer. Grayscale images are written as 8bit images with a grayscale
+    /// palette, RGB images as 24bit images.
+    pub fn write<W, P>(&self, mut out: W, img: &Image2D<P>) -> Result<(), Error>
+    where
+        W: Write,
+        P: Pixel<Subpixel = u8>,
+    {
+        let (w, h) = img.dimensions();
+        let bit_count: u16 = match P::N_CHANNELS {
+            1 => 8,
+            3 => 24,
+            _ => return Err(EncodingError::UnsupportedType().into()),
+        };
+        let palette_size = if bit_count == 8 { 256 * 4 } else { 0 };
+        let stride = row_stride(w, bit_count);
+        let data_offset = u64::from(FILE_HEADER_SIZE + INFO_HEADER_SIZE + palette_size);
+        let image_size = stride as u64 * u64::from(h);
+        let file_size = data_offset + image_size;
+        if file_size > u64::from(u32::max_value())
+            || w > i32::max_value() as u32
+            || h > i32::max_value() as u32
+        {
+            return Err(EncodingError::TooLarge.into());
+        }
+
+        // BITMAPFILEHEADER
+        out.write_all(b"BM")?;
+        out.write_u32::<LittleEndian>(file_size as u32)?;
+        out.write_u32::<LittleEndian>(0)?;
+        out.write_u32::<LittleEndian>(data_offset as u32)?;
+
+        // BITMAPINFOHEADER
+        out.write_u32::<LittleEndian>(INFO_HEADER_SIZE)?;
+        out.write_i32::<LittleEndian>(w as i32)?;
+        out.write_i32::<LittleEndian>(h as i32)?;
+        out.write_u16::<LittleEndian>(1)?;
+        out.write_u16::<LittleEndian>(bit_count)?;
+        out.write_u32::<LittleEndian>(BI_RGB)?;
+        out.write_u32::<LittleEndian>(image_size as u32)?;
+        out.write_i32::<LittleEndian>(0)?;
+        out.write_i32::<LittleEndian>(0)?;
+        out.write_u32::<LittleEndian>(0)?;
+        out.write_u32::<LittleEndian>(0)?;
+
+        if bit_count == 8 {
+            for i in 0..=255u8 {
+                out.write_all(&[i, i, i, 0])?;
+            }
+        }
+
+        // Rows are stored bottom-up, in BGR order and padded to a multiple of 4 bytes.
+        let mut row_buffer = Vec::with_capacity(stride);
+        for y in (0..h).rev() {
+            row_buffer.clear();
+            for x in 0..w {
+                let c = img.get_pixel(x, y).channels();
+                if bit_count == 8 {
+                    row_buffer.push(c[0]);
+                } else {
+                    row_buffer.extend_from_slice(&[c[2], c[1], c[0]]);
+                }
+            }
+            row_buffer.resize(stride, 0);
+            out.write_all(&row_buffer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W, P> ImageEncoder<W, P> for Encoder
+where
+    W: Write,
+    P: Pixel<Subpixel = u8>,
+{
+    fn write_image(self, out: W, img: &Image2D<P>) -> Result<(), Error> {
+        self.write(out, img)
+    }
+}
+
+io_encodable_trait!(
+    /// Trait implemented for image types encodable into the BMP format.
+    BmpEncodable,
+    f32;
+    f64;
+    u16;
+    u32;
+    u64;
+    i8;
+    i16;
+    i32;
+    i64;
+    u8 => {
+        |out, img| {
+            let enc = Encoder::new();
+            enc.write(out, img)
+        }
+    };
+);
+
+#[cfg(test)]
+mod tests {
+    use core::{Image2DMut, ImageBuffer2D, Pixel};
+    use io::bmp::*;
+
+    use std::fmt::Debug;
+    use std::io::Cursor;
+
+    fn mk_test_img<P>() -> ImageBuffer2D<P>
+    where
+        P: Pixel<Subpixel = u8>,
+    {
+        let mut img = ImageBuffer2D::new(31, 17);
+        for y in 0..17 {
+            for x in 0..31 {
+                let pix: Vec<u8> = (0..P::N_CHANNELS)
+                    .map(|c| (x * 7 + y * 3 + c * 50) as u8)
+                    .collect();
+                img.put_pixel(x, y, P::from_slice(&pix));
+            }
+        }
+        img
+    }
+
+    fn helper_test_write_roundtrip<F, P>(img: ImageBuffer2D<P>, fn_decode: F)
+    where
+        F: FnOnce(Decoder<Cursor<Vec<u8>>>) -> Result<ImageBuffer2D<P>, Error>,
+        P: Pixel<Subpixel = u8> + Debug,
+    {
+        let mut buf = Vec::new();
+        Encoder::new().write(&mut buf, &img).unwrap();
+        let decoder = Decoder::new(Cursor::new(buf)).unwrap();
+        assert_eq!(decoder.dimensions(), img.dimensions());
+        let img2 = fn_decode(decoder).unwrap();
+        assert_eq!(img, img2);
+    }
+
+    // Build an 8bit paletted BMP file with the given palette and header dimensions, stored top-down if the height is
+    // negative. The pixel data is the one of a 2x2 image.
+    fn mk_paletted_bmp(palette: &[[u8; 3]], width: i32, height: i32) -> Vec<u8> {
+        let offset = 14 + 40 + 4 * palette.len() as u32;
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"BM");
+        buf.write_u32::<LittleEndian>(offset + 8).unwrap();
+        buf.write_u32::<LittleEndian>(0).unwrap();
+        buf.write_u32::<LittleEndian>(offset).unwrap();
+        buf.write_u32::<LittleEndian>(40).unwrap();
+        buf.write_i32::<LittleEndian>(width).unwrap();
+        buf.write_i32::<LittleEndian>(height).unwrap();
+        buf.write_u16::<LittleEndian>(1).unwrap();
+        buf.write_u16::<LittleEndian>(8).unwrap();
+        for _ in 0..3 {
+            buf.write_u32::<LittleEndian>(0).unwrap();
+        }
+        buf.write_u32::<LittleEndian>(0).unwrap();
+        buf.write_u32::<LittleEndian>(palette.len() as u32).unwrap();
+        buf.write_u32::<LittleEndian>(0).unwrap();
+        for c in palette {
+            buf.extend_from_slice(&[c[2], c[1], c[0], 0]);
+        }
+        buf.extend_from_slice(&[0, 1, 0, 0, 1, 0, 0, 0]);
+        buf
+    }
+
+    #[test]
+    fn test_write_luma_u8() {
+        helper_test_write_roundtrip(mk_test_img::<Luma<u8>>(), |d| d.read_luma_u8());
+    }
+
+    #[test]
+    fn test_write_rgb_u8() {
+        helper_test_write_roundtrip(mk_test_img::<Rgb<u8>>(), |d| d.read_rgb_u8());
+    }
+
+    #[test]
+    fn test_write_unsupported() {
+        let img = ImageBuffer2D::<RgbA<u8>>::new(4, 4);
+        assert!(Encoder::new().write(Vec::new(), &img).is_err());
+    }
+
+    #[test]
+    fn test_read_palette() {
+        let palette = [[255, 0, 0], [0, 0, 255]];
+        let bottom_up = Decoder::new(Cursor::new(mk_paletted_bmp(&palette, 2, 2))).unwrap();
+        assert_eq!(bottom_up.image_channels(), PixelType::Rgb);
+        let img = bottom_up.read_rgb_u8().unwrap();
+        assert_eq!(img[(0, 0)], Rgb::new([0, 0, 255]));
+        assert_eq!(img[(0, 1)], Rgb::new([255, 0, 0]));
+        assert_eq!(img[(1, 1)], Rgb::new([0, 0, 255]));
+
+        let top_down = Decoder::new(Cursor::new(mk_paletted_bmp(&palette, 2, -2))).unwrap();
+        let img = top_down.read_rgb_u8().unwrap();
+        assert_eq!(img[(0, 0)], Rgb::new([255, 0, 0]));
+        assert_eq!(img[(1, 0)], Rgb::new([0, 0, 255]));
+        assert_eq!(img[(0, 1)], Rgb::new([0, 0, 255]));
+    }
+
+    #[test]
+    fn test_read_gray_palette() {
+        let palette = [[10, 10, 10], [200, 200, 200]];
+        let dec = Decoder::new(Cursor::new(mk_paletted_bmp(&palette, 2, -2))).unwrap();
+        assert_eq!(dec.image_channels(), PixelType::Luma);
+        assert!(Decoder::new(Cursor::new(mk_paletted_bmp(&palette, 2, -2)))
+            .unwrap()
+            .read_rgb_u8()
+            .is_err());
+        let img = dec.read_luma_u8().unwrap();
+        assert_eq!(img[(0, 0)], Luma::new([10]));
+        assert_eq!(img[(1, 0)], Luma::new([200]));
+    }
+
+    #[test]
+    fn test_too_large() {
+        let palette = [[0, 0, 0]];
+        for &(w, h) in &[
+            (70000, 70000),
+            (70000, -70000),
+            (1, i32::max_value()),
+            (1, 1_000_000_000),
+        ] {
+            let err = Decoder::new(Cursor::new(mk_paletted_bmp(&palette, w, h)))
+                .err()
+                .unwrap();
+            match err.downcast_ref::<DecodingError>() {
+                Some(&DecodingError::TooLarge) => (),
+                _ => panic!("Unexpected error: {}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        // The header is accepted, but the rows must not be allocated before they are actually read.
+        let palette = [[0, 0, 0]];
+        let dec = Decoder::new(Cursor::new(mk_paletted_bmp(&palette, 1, 100_000_000))).unwrap();
+        assert!(dec.read_image().is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        let palette = [[0, 0, 0]];
+        for &(w, h) in &[(0, 2), (2, 0), (0, i32::max_value())] {
+            assert!(Decoder::new(Cursor::new(mk_paletted_bmp(&palette, w, h))).is_err());
+        }
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        assert!(Decoder::new(Cursor::new(b"PN\0\0\0\0\0\0\0\0\0\0\0\0".to_vec())).is_err());
+    }
+}
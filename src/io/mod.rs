@@ -2,27 +2,51 @@
 
 #[macro_use]
 mod macros;
+pub mod bmp;
 pub mod png;
+pub mod pnm;
 pub mod tiff;
 pub mod traits;
 
-use self::{png::PngEncodable, traits::ImageDecoder};
-use core::{DynamicImage, Image2D, Pixel};
+use self::{bmp::BmpEncodable, png::PngEncodable, pnm::PnmEncodable, traits::ImageDecoder};
+use core::{
+    BitDepth, DynamicImage, Image2D, Image2DMut, ImageBuffer2D, Pixel, PixelCast, PixelType,
+};
 
 use failure::Error;
+use num_traits::cast;
+use tiff::TiffError;
 
 use std::fs::File;
+use std::io::{self as stdio, BufReader, BufWriter, Read, Seek, SeekFrom};
 use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Enumerate the image formats supported by the library.
 pub enum Format {
+    /// BMP format.
+    Bmp,
     /// PNG format.
     Png,
+    /// Netpbm PGM/PPM formats.
+    Pnm,
     /// TIFF format.
     Tiff,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// Image metadata that is not part of the pixel data, as stored in the image file.
+pub struct Metadata {
+    /// Horizontal and vertical resolution in dots per inch, if the file specifies a physical resolution.
+    pub dpi: Option<(f64, f64)>,
+    /// Embedded ICC color profile, if any.
+    pub icc_profile: Option<Vec<u8>>,
+    /// Pixel type of the image, as stored in the file.
+    pub color_type: PixelType,
+    /// Bit depth of the image, as stored in the file.
+    pub bit_depth: BitDepth,
+}
+
 fn parse_extension<P>(filepath: &P) -> Option<Format>
 where
     P: AsRef<Path>,
@@ -35,31 +59,136 @@ where
     match ext.as_str() {
         "tiff" => Some(Format::Tiff),
         "png" => Some(Format::Png),
+        "bmp" => Some(Format::Bmp),
+        "pgm" | "ppm" | "pnm" => Some(Format::Pnm),
         _ => None,
     }
 }
 
-/// Open an image on the filesystem. Try to guess the image format from the file extension.
-pub fn open<P>(filepath: P) -> Result<DynamicImage, Error>
+#[derive(Fail, Debug)]
+/// Error type returned by `open`.
+pub enum OpenError {
+    #[fail(display = "Could not infer image format from file extension")]
+    /// The file extension does not match any supported format.
+    UnknownFormat,
+    #[fail(display = "I/O error")]
+    /// The file could not be read, e.g. because it does not exist.
+    Io(#[cause] stdio::Error),
+    #[fail(display = "Corrupt image data: {}", _0)]
+    /// The file contents are malformed or truncated. Stores the underlying decoder error.
+    Corrupt(Error),
+    #[fail(display = "Unsupported image type: {}", _0)]
+    /// The file is valid but its pixel type is not supported (yet) by the library. Stores the underlying decoder
+    /// error.
+    UnsupportedPixelType(Error),
+}
+
+impl From<Error> for OpenError {
+    /// Sort a decoder error into the corresponding `OpenError` variant.
+    fn from(err: Error) -> OpenError {
+        let unsupported = match err.downcast_ref::<png::DecodingError>() {
+            Some(&png::DecodingError::UnsupportedType(_)) => true,
+            _ => false,
+        } || match err.downcast_ref::<tiff::DecodingError>() {
+            Some(&tiff::DecodingError::UnsupportedType(_))
+            | Some(&tiff::DecodingError::Decoder(TiffError::UnsupportedError(_))) => true,
+            _ => false,
+        } || match err.downcast_ref::<bmp::DecodingError>() {
+            Some(&bmp::DecodingError::UnsupportedType(_, _)) => true,
+            _ => false,
+        } || match err.downcast_ref::<pnm::DecodingError>() {
+            Some(&pnm::DecodingError::UnsupportedType(_)) => true,
+            _ => false,
+        };
+        if unsupported {
+            return OpenError::UnsupportedPixelType(err);
+        }
+        // Unexpected ends of file and invalid data mean the file is corrupt, other I/O errors are reported as such.
+        match err.downcast::<stdio::Error>() {
+            Ok(e) => match e.kind() {
+                stdio::ErrorKind::UnexpectedEof | stdio::ErrorKind::InvalidData => {
+                    OpenError::Corrupt(e.into())
+                }
+                _ => OpenError::Io(e),
+            },
+            Err(err) => OpenError::Corrupt(err),
+        }
+    }
+}
+
+/// Open an image on the filesystem. Try to guess the image format from the file extension, or from the file contents
+/// if the extension is unknown (see `detect_format`).
+///
+/// **Error**: an `OpenError` describing whether the format is unknown, the file could not be read, its contents are
+/// corrupt or its pixel type is not supported.
+pub fn open<P>(filepath: P) -> Result<DynamicImage, OpenError>
 where
     P: AsRef<Path>,
 {
-    if let Some(format) = parse_extension(&filepath) {
-        let file = File::open(filepath)?;
-        match format {
-            Format::Png => png::Decoder::new(file)?.read_image(),
-            Format::Tiff => tiff::Decoder::new(file)?.read_image(),
+    let mut file = File::open(&filepath).map_err(OpenError::Io)?;
+    let format = match parse_extension(&filepath) {
+        Some(format) => format,
+        None => detect_format(&mut file).ok_or(OpenError::UnknownFormat)?,
+    };
+    open_from_reader(file, format)
+}
+
+/// Try to detect the format of an image from the magic bytes at the current position of a reader. The reader is
+/// seeked back to its initial position afterwards. Return `None` if the format is not recognized or the reader can't
+/// be read.
+pub fn detect_format<R>(reader: &mut R) -> Option<Format>
+where
+    R: Read + Seek,
+{
+    let start = reader.seek(SeekFrom::Current(0)).ok()?;
+    let mut magic = [0u8; 8];
+    let mut len = 0;
+    while len < magic.len() {
+        match reader.read(&mut magic[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(ref e) if e.kind() == stdio::ErrorKind::Interrupted => (),
+            Err(_) => return None,
         }
+    }
+    reader.seek(SeekFrom::Start(start)).ok()?;
+    let magic = &magic[..len];
+    if magic.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(Format::Png)
+    } else if magic.starts_with(b"II*\0") || magic.starts_with(b"MM\0*") {
+        Some(Format::Tiff)
+    } else if magic.starts_with(b"BM") {
+        Some(Format::Bmp)
+    } else if magic.len() >= 2 && magic[0] == b'P' && b"2356".contains(&magic[1]) {
+        Some(Format::Pnm)
     } else {
-        bail!("Could not infer image format from file extension!")
+        None
     }
 }
 
+/// Decode an image in the given format from any reader, e.g. an in-memory buffer or a network stream. Only the TIFF
+/// decoder needs to seek, the other decoders read the stream sequentially.
+///
+/// **Error**: an `OpenError` describing whether the stream could not be read, its contents are corrupt or its pixel
+/// type is not supported.
+pub fn open_from_reader<R>(reader: R, format: Format) -> Result<DynamicImage, OpenError>
+where
+    R: Read + Seek,
+{
+    let img = match format {
+        Format::Bmp => bmp::Decoder::new(BufReader::new(reader)).and_then(|d| d.read_image()),
+        Format::Png => png::Decoder::new(reader).and_then(|d| d.read_image()),
+        Format::Pnm => pnm::Decoder::new(BufReader::new(reader)).and_then(|d| d.read_image()),
+        Format::Tiff => tiff::Decoder::new(reader).and_then(|d| d.read_image()),
+    };
+    Ok(img?)
+}
+
 /// Save an image to the disk. Try to guess the image format from the file extension.
 pub fn save<I, P, P2>(filepath: P2, img: &I) -> Result<(), Error>
 where
     I: Image2D<P>,
-    P: Pixel + PngEncodable<P>,
+    P: Pixel + BmpEncodable<P> + PngEncodable<P> + PnmEncodable<P>,
     P2: AsRef<Path>,
 {
     if let Some(format) = parse_extension(&filepath) {
@@ -71,12 +200,50 @@ where
                 let out = File::create(filepath)?;
                 <P as PngEncodable<P>>::write_image(out, img)
             }
+            Format::Bmp => {
+                let out = BufWriter::new(File::create(filepath)?);
+                <P as BmpEncodable<P>>::write_image(out, img)
+            }
+            Format::Pnm => {
+                let out = BufWriter::new(File::create(filepath)?);
+                <P as PnmEncodable<P>>::write_image(out, img)
+            }
         }
     } else {
         bail!("Could not infer image format from file extension!")
     }
 }
 
+/// Save an image to the disk after linearly mapping its values to the 8-bit range, the lowest value of the image
+/// being mapped to 0 and the highest to 255. This is mostly useful to visualize floating point images. Uniform
+/// images are saved as black images. Try to guess the image format from the file extension.
+pub fn save_normalized<I, P, P2>(filepath: P2, img: &I) -> Result<(), Error>
+where
+    I: Image2D<P>,
+    P: PixelCast<u8>,
+    <P as PixelCast<u8>>::Output: BmpEncodable<<P as PixelCast<u8>>::Output>
+        + PngEncodable<<P as PixelCast<u8>>::Output>
+        + PnmEncodable<<P as PixelCast<u8>>::Output>,
+    P2: AsRef<Path>,
+{
+    let to_f64 = |v: &P::Subpixel| cast::<P::Subpixel, f64>(*v).unwrap_or(0.);
+    let (mut min, mut max) = (::std::f64::INFINITY, ::std::f64::NEG_INFINITY);
+    for v in img.iter().flat_map(|p| p.channels().iter().map(to_f64)) {
+        min = min.min(v);
+        max = max.max(v);
+    }
+    let (w, h) = img.dimensions();
+    let mut normalized = ImageBuffer2D::<<P as PixelCast<u8>>::Output>::new(w, h);
+    if max > min {
+        for (src, dst) in img.iter().zip(normalized.iter_mut()) {
+            for (s, d) in src.channels().iter().zip(dst.channels_mut()) {
+                *d = ((to_f64(s) - min) * 255. / (max - min)).round() as u8;
+            }
+        }
+    }
+    save(filepath, &normalized)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,12 +254,17 @@ mod tests {
     use tempfile::tempdir;
 
     use std::fmt::Debug;
+    use std::io::Cursor;
 
     #[test]
     fn test_parse_extension() {
         assert_eq!(parse_extension(&"img.tiff".to_owned()), Some(Format::Tiff));
         assert_eq!(parse_extension(&"img.png".to_owned()), Some(Format::Png));
         assert_eq!(parse_extension(&"img.TIFF".to_owned()), Some(Format::Tiff));
+        assert_eq!(parse_extension(&"img.bmp".to_owned()), Some(Format::Bmp));
+        assert_eq!(parse_extension(&"img.pgm".to_owned()), Some(Format::Pnm));
+        assert_eq!(parse_extension(&"img.PPM".to_owned()), Some(Format::Pnm));
+        assert_eq!(parse_extension(&"img.pnm".to_owned()), Some(Format::Pnm));
     }
 
     #[test]
@@ -141,6 +313,63 @@ mod tests {
         );
         assert!(img_rgb_alpha_u16.is_rgb_alpha());
         assert!(img_rgb_alpha_u16.into_rgb_alpha_u16().is_ok());
+        let img_indexed = open("./test_data/io/png/indexed_8bit.png").unwrap();
+        assert_eq!(img_indexed.image_type(), (PixelType::Rgb, BitDepth::_8));
+        let img_indexed_alpha = open("./test_data/io/png/indexed_alpha_8bit.png").unwrap();
+        assert_eq!(
+            img_indexed_alpha.image_type(),
+            (PixelType::RgbA, BitDepth::_8)
+        );
+    }
+
+    #[test]
+    fn test_open_from_reader() {
+        let path = "./test_data/io/png/rgb_8bit.png";
+        let mut buf = Vec::new();
+        File::open(path).unwrap().read_to_end(&mut buf).unwrap();
+        let img = open_from_reader(Cursor::new(buf), Format::Png).unwrap();
+        assert_eq!(img, open(path).unwrap());
+
+        let mut buf = Vec::new();
+        File::open("./test_data/io/tiff/rgba_16bit.tiff")
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        let img = open_from_reader(Cursor::new(&buf), Format::Tiff).unwrap();
+        assert_eq!(img.image_type(), (PixelType::RgbA, BitDepth::_16));
+        match open_from_reader(Cursor::new(&buf), Format::Png) {
+            Err(OpenError::Corrupt(_)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_detect_format() {
+        let files = [
+            ("./test_data/io/png/rgb_8bit.png", Format::Png),
+            ("./test_data/io/tiff/rgb_8bit.tiff", Format::Tiff),
+        ];
+        for &(path, format) in &files {
+            let mut file = File::open(path).unwrap();
+            assert_eq!(detect_format(&mut file), Some(format));
+            assert_eq!(file.seek(SeekFrom::Current(0)).unwrap(), 0);
+        }
+        let mut cursor = Cursor::new(b"xxP6\n1 1\n255\n\0\0\0".to_vec());
+        cursor.seek(SeekFrom::Start(2)).unwrap();
+        assert_eq!(detect_format(&mut cursor), Some(Format::Pnm));
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(detect_format(&mut Cursor::new(b"BM")), Some(Format::Bmp));
+        assert_eq!(detect_format(&mut Cursor::new(b"")), None);
+        assert_eq!(detect_format(&mut Cursor::new(b"P1\n1 1")), None);
+
+        // A PNG image with a misleading extension is decoded from its contents.
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("image.bin");
+        ::std::fs::copy("./test_data/io/png/rgb_8bit.png", &path).unwrap();
+        assert_eq!(
+            open(&path).unwrap(),
+            open("./test_data/io/png/rgb_8bit.png").unwrap()
+        );
     }
 
     #[test]
@@ -204,7 +433,7 @@ mod tests {
     fn helper_test_write_roundtrip_u8<F, P, P2>(path: P2, img: ImageBuffer2D<P>, fn_decode: F)
     where
         F: FnOnce(P2) -> Result<Box<ImageBuffer2D<P>>, Error>,
-        P: Pixel<Subpixel = u8> + Debug + PngEncodable<P>,
+        P: Pixel<Subpixel = u8> + Debug + BmpEncodable<P> + PngEncodable<P> + PnmEncodable<P>,
         P2: AsRef<Path>,
     {
         {
@@ -217,7 +446,7 @@ mod tests {
     fn helper_test_write_roundtrip_u16<F, P, P2>(path: P2, img: ImageBuffer2D<P>, fn_decode: F)
     where
         F: FnOnce(P2) -> Result<Box<ImageBuffer2D<P>>, Error>,
-        P: Pixel<Subpixel = u16> + Debug + PngEncodable<P>,
+        P: Pixel<Subpixel = u16> + Debug + BmpEncodable<P> + PngEncodable<P> + PnmEncodable<P>,
         P2: AsRef<Path>,
     {
         {
@@ -279,4 +508,103 @@ mod tests {
             |p| open(p)?.into_rgb_u16(),
         );
     }
+
+    #[test]
+    fn test_save_bmp() {
+        let dir = tempdir().unwrap();
+        let img_luma_u8 = mk_test_img::<Luma<u8>, u8>();
+        let img_rgb_u8 = mk_test_img::<Rgb<u8>, u8>();
+        let path = dir.path().join("test_save_bmp_luma_u8.bmp");
+        save(&path, &img_luma_u8).unwrap();
+        assert_eq!(
+            &img_luma_u8,
+            open(&path).unwrap().into_luma_u8().unwrap().as_ref()
+        );
+        let path = dir.path().join("test_save_bmp_rgb_u8.bmp");
+        save(&path, &img_rgb_u8).unwrap();
+        assert_eq!(
+            &img_rgb_u8,
+            open(&path).unwrap().into_rgb_u8().unwrap().as_ref()
+        );
+        let img_luma_u16 = mk_test_img::<Luma<u16>, u16>();
+        assert!(save(dir.path().join("test_save_bmp_luma_u16.bmp"), &img_luma_u16).is_err());
+    }
+
+    #[test]
+    fn test_save_pnm() {
+        let dir = tempdir().unwrap();
+        helper_test_write_roundtrip_u8(
+            dir.path().join("test_save_pnm_luma_u8.pgm"),
+            mk_test_img::<Luma<u8>, u8>(),
+            |p| open(p)?.into_luma_u8(),
+        );
+        helper_test_write_roundtrip_u16(
+            dir.path().join("test_save_pnm_luma_u16.pgm"),
+            mk_test_img::<Luma<u16>, u16>(),
+            |p| open(p)?.into_luma_u16(),
+        );
+        helper_test_write_roundtrip_u8(
+            dir.path().join("test_save_pnm_rgb_u8.ppm"),
+            mk_test_img::<Rgb<u8>, u8>(),
+            |p| open(p)?.into_rgb_u8(),
+        );
+        helper_test_write_roundtrip_u16(
+            dir.path().join("test_save_pnm_rgb_u16.ppm"),
+            mk_test_img::<Rgb<u16>, u16>(),
+            |p| open(p)?.into_rgb_u16(),
+        );
+    }
+
+    #[test]
+    fn test_save_normalized() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_save_normalized.png");
+        let ramp = ImageBuffer2D::generate(16, 4, |(x, _)| Luma::new([-1.5 + x as f32 * 0.25]));
+        save_normalized(&path, &ramp).unwrap();
+        let img = open(&path).unwrap().into_luma_u8().unwrap();
+        for row in img.rows() {
+            let row: Vec<u8> = row.iter().map(|p| p[0]).collect();
+            assert_eq!(row[0], 0);
+            assert_eq!(row[15], 255);
+            assert!(row.windows(2).all(|w| w[0] < w[1]));
+        }
+
+        let path = dir.path().join("test_save_normalized_flat.png");
+        let flat = ImageBuffer2D::generate(4, 4, |_| Rgb::new([0.5f64, 0.5, 0.5]));
+        save_normalized(&path, &flat).unwrap();
+        let img = open(&path).unwrap().into_rgb_u8().unwrap();
+        assert!(img.iter().all(|p| *p == Rgb::new([0, 0, 0])));
+    }
+
+    #[test]
+    fn test_open_errors() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("img.xyz");
+        ::std::fs::write(&path, b"not an image").unwrap();
+        match open(&path) {
+            Err(OpenError::UnknownFormat) => (),
+            r => panic!("Unexpected result {:?}", r.map(|img| img.image_type())),
+        }
+        match open("./test_data/io/png/does_not_exist.png") {
+            Err(OpenError::Io(ref e)) if e.kind() == stdio::ErrorKind::NotFound => (),
+            r => panic!("Unexpected result {:?}", r.map(|img| img.image_type())),
+        }
+
+        let data = ::std::fs::read("./test_data/io/png/rgb_8bit.png").unwrap();
+        for &len in &[4, 40, data.len() / 2] {
+            let path = dir.path().join(format!("truncated_{}.png", len));
+            ::std::fs::write(&path, &data[..len]).unwrap();
+            match open(&path) {
+                Err(OpenError::Corrupt(_)) => (),
+                r => panic!("Unexpected result {:?}", r.map(|img| img.image_type())),
+            }
+        }
+
+        let path = dir.path().join("unsupported.pgm");
+        ::std::fs::write(&path, b"P1\n1 1\n0\n").unwrap();
+        match open(&path) {
+            Err(OpenError::UnsupportedPixelType(_)) => (),
+            r => panic!("Unexpected result {:?}", r.map(|img| img.image_type())),
+        }
+    }
 }
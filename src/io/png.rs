@@ -6,9 +6,12 @@ use core::{
 };
 
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
+use deflate;
+use deflate::write::ZlibEncoder;
 use failure::Error;
 
 use io::traits::{ImageDecoder, ImageEncoder};
+use io::Metadata;
 use png;
 use png::HasParameters;
 
@@ -22,6 +25,147 @@ where
     reader: png::Reader<R>,
     channels: PixelType,
     depth: BitDepth,
+    // Raw frame data already read by `read_image_with_progress`.
+    frame: Option<Vec<u8>>,
+}
+
+// Number of inches in a meter, PNG physical dimensions being stored in pixels per meter.
+const INCHES_PER_METER: f64 = 1. / 0.0254;
+
+// Write the pHYs chunk describing the resolution stored in the metadata, if any.
+fn write_metadata<W: Write>(
+    writer: &mut png::Writer<W>,
+    metadata: &Option<Metadata>,
+) -> Result<(), Error> {
+    if let Some((x_dpi, y_dpi)) = metadata.as_ref().and_then(|m| m.dpi) {
+        let mut phys = [0u8; 9];
+        BigEndian::write_u32(&mut phys[0..4], (x_dpi * INCHES_PER_METER).round() as u32);
+        BigEndian::write_u32(&mut phys[4..8], (y_dpi * INCHES_PER_METER).round() as u32);
+        phys[8] = png::Unit::Meter as u8;
+        try!(writer.write_chunk(*b"pHYs", &phys));
+    }
+    Ok(())
+}
+
+// Offset and spacing `(x0, y0, dx, dy)` of the pixels of each Adam7 pass.
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+// Copy the pixel at index `src_idx` of a packed row of `pixel_bits` bits per pixel to index `dst_idx` of another.
+fn copy_pixel(src: &[u8], src_idx: usize, dst: &mut [u8], dst_idx: usize, pixel_bits: usize) {
+    if pixel_bits % 8 == 0 {
+        let n = pixel_bits / 8;
+        dst[dst_idx * n..(dst_idx + 1) * n].copy_from_slice(&src[src_idx * n..(src_idx + 1) * n]);
+    } else {
+        // Sub-byte pixels, the leftmost pixel being stored in the most significant bits.
+        let mask = ((1u16 << pixel_bits) - 1) as u8;
+        let (src_bit, dst_bit) = (src_idx * pixel_bits, dst_idx * pixel_bits);
+        let v = (src[src_bit / 8] >> (8 - pixel_bits - src_bit % 8)) & mask;
+        let shift = 8 - pixel_bits - dst_bit % 8;
+        dst[dst_bit / 8] = (dst[dst_bit / 8] & !(mask << shift)) | (v << shift);
+    }
+}
+
+// Return the sample at index `idx` of a packed row of `bits` bits per sample, for bit depths of 8 bits or less.
+fn unpack_sample(row: &[u8], idx: usize, bits: usize) -> u8 {
+    let mask = ((1u16 << bits) - 1) as u8;
+    let bit = idx * bits;
+    (row[bit / 8] >> (8 - bits - bit % 8)) & mask
+}
+
+// Filter a row of image data in place with the given filter type. `bpp` is the number of bytes per complete pixel,
+// and `previous` is the unfiltered previous row, or zeros for the first row.
+fn filter_row(filter: FilterType, bpp: usize, previous: &[u8], current: &mut [u8]) {
+    let len = current.len();
+    match filter {
+        FilterType::NoFilter => (),
+        FilterType::Sub => {
+            for i in (bpp..len).rev() {
+                current[i] = current[i].wrapping_sub(current[i - bpp]);
+            }
+        }
+        FilterType::Up => {
+            for i in 0..len {
+                current[i] = current[i].wrapping_sub(previous[i]);
+            }
+        }
+        FilterType::Avg => {
+            for i in (0..len).rev() {
+                let left = if i >= bpp {
+                    u16::from(current[i - bpp])
+                } else {
+                    0
+                };
+                let avg = (left + u16::from(previous[i])) / 2;
+                current[i] = current[i].wrapping_sub(avg as u8);
+            }
+        }
+        FilterType::Paeth => {
+            for i in (0..len).rev() {
+                let (a, c) = if i >= bpp {
+                    (current[i - bpp], previous[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                let b = previous[i];
+                let p = i16::from(a) + i16::from(b) - i16::from(c);
+                let (pa, pb, pc) = (
+                    (p - i16::from(a)).abs(),
+                    (p - i16::from(b)).abs(),
+                    (p - i16::from(c)).abs(),
+                );
+                let predictor = if pa <= pb && pa <= pc {
+                    a
+                } else if pb <= pc {
+                    b
+                } else {
+                    c
+                };
+                current[i] = current[i].wrapping_sub(predictor);
+            }
+        }
+    }
+}
+
+// Write the IDAT chunk containing the given image data, filtered and compressed with the specified settings. The png
+// crate always filters with `Sub` and compresses at the fastest level without exposing its filters, hence the image
+// data being encoded here.
+fn write_image_data<W: Write>(
+    writer: &mut png::Writer<W>,
+    data: &[u8],
+    row_len: usize,
+    bpp: usize,
+    compression: Compression,
+    filter: FilterType,
+) -> Result<(), Error> {
+    ensure!(
+        row_len != 0 && data.len() >= row_len,
+        "Cannot encode an empty image"
+    );
+    let level = match compression {
+        Compression::Fast => deflate::Compression::Fast,
+        Compression::Default => deflate::Compression::Default,
+        Compression::Best => deflate::Compression::Best,
+    };
+    let mut zlib = ZlibEncoder::new(Vec::new(), level);
+    let mut previous = vec![0; row_len];
+    let mut current = vec![0; row_len];
+    for row in data.chunks(row_len) {
+        current.copy_from_slice(row);
+        filter_row(filter, bpp, &previous, &mut current);
+        try!(zlib.write_all(&[filter as u8]));
+        try!(zlib.write_all(&current));
+        previous.copy_from_slice(row);
+    }
+    try!(writer.write_chunk(*b"IDAT", &try!(zlib.finish())));
+    Ok(())
 }
 
 // Convert a slice of bytes in the specified byte order into a Vec of u16 values.
@@ -74,29 +218,146 @@ where
             png::ColorType::GrayscaleAlpha => PixelType::LumaA,
             png::ColorType::RGB => PixelType::Rgb,
             png::ColorType::RGBA => PixelType::RgbA,
-            // TODO: support other types
-            _ => return Err(DecodingError::UnsupportedType(info.color_type).into()),
+            // Paletted images are expanded to RGB, or RGBA if they contain transparency information.
+            png::ColorType::Indexed if reader.info().trns.is_some() => PixelType::RgbA,
+            png::ColorType::Indexed => PixelType::Rgb,
         };
-        let depth = match info.bit_depth {
-            png::BitDepth::Eight => BitDepth::_8,
-            png::BitDepth::Sixteen => BitDepth::_16,
-            // TODO: what to do for other pixel types ?
+        let depth = match (info.color_type, info.bit_depth) {
+            (png::ColorType::Indexed, _) => BitDepth::_8,
+            (_, png::BitDepth::Eight) => BitDepth::_8,
+            (_, png::BitDepth::Sixteen) => BitDepth::_16,
+            // 1, 2 and 4 bit grayscale images are promoted to 8 bit.
+            (png::ColorType::Grayscale, _) => BitDepth::_8,
             _ => return Err(DecodingError::Internal.into()),
         };
         Ok(Decoder {
             reader,
             channels,
             depth,
+            frame: None,
         })
     }
 
+    // Read the raw frame data, rows being packed like in a non interlaced image. The png crate deinterlaces Adam7
+    // images assuming 8 bit samples, so interlaced images are deinterlaced here instead. `progress` is called with the
+    // fraction of the rows read after each row.
+    fn read_frame_with_progress(&mut self, progress: &mut FnMut(f32)) -> Result<Vec<u8>, Error> {
+        if let Some(buffer) = self.frame.take() {
+            return Ok(buffer);
+        }
+        let mut buffer = vec![0; self.reader.output_buffer_size()];
+        let (width, height, interlaced, pixel_bits) = {
+            let info = self.reader.info();
+            let pixel_bits = info.color_type.samples() * info.bit_depth as usize;
+            (
+                info.width as usize,
+                info.height as usize,
+                info.interlaced,
+                pixel_bits,
+            )
+        };
+        let n_rows = if interlaced {
+            ADAM7_PASSES
+                .iter()
+                .filter(|&&(x0, _, _, _)| x0 < width)
+                .map(|&(_, y0, _, dy)| (height + dy - 1).saturating_sub(y0) / dy)
+                .sum()
+        } else {
+            height
+        };
+
+        let row_len = (width * pixel_bits + 7) / 8;
+        let mut rows_read = 0;
+        while let Some((row, pass)) = try!(self.reader.next_interlaced_row()) {
+            match pass {
+                Some((pass, line, pass_width)) => {
+                    let (x0, y0, dx, dy) = ADAM7_PASSES[pass as usize - 1];
+                    let y = y0 + line as usize * dy;
+                    let dst = &mut buffer[y * row_len..(y + 1) * row_len];
+                    for i in 0..pass_width as usize {
+                        copy_pixel(row, i, dst, x0 + i * dx, pixel_bits);
+                    }
+                }
+                None => {
+                    let dst = &mut buffer[rows_read * row_len..(rows_read + 1) * row_len];
+                    dst.copy_from_slice(&row[..row_len]);
+                }
+            }
+            rows_read += 1;
+            progress(rows_read as f32 / n_rows as f32);
+        }
+        Ok(buffer)
+    }
+
+    // Read the raw frame data.
+    fn read_frame(&mut self) -> Result<Vec<u8>, Error> {
+        self.read_frame_with_progress(&mut |_| ())
+    }
+
+    // Read the frame as 8 bit samples, expanding the palette of indexed images and scaling the samples of sub-byte
+    // grayscale images to the full 8 bit range.
+    fn read_frame_u8(&mut self) -> Result<Vec<u8>, Error> {
+        let buffer = try!(self.read_frame());
+
+        let info = self.reader.info();
+        let bits = info.bit_depth as usize;
+        let grayscale = info.color_type == png::ColorType::Grayscale && bits < 8;
+        if info.color_type != png::ColorType::Indexed && !grayscale {
+            return Ok(buffer);
+        }
+        let (w, h) = (info.width as usize, info.height as usize);
+        let mask = ((1u16 << bits) - 1) as u8;
+        let row_len = (w * bits + 7) / 8;
+        if grayscale {
+            let scale = 255 / mask;
+            let mut expanded = Vec::with_capacity(w * h);
+            for row in buffer.chunks(row_len).take(h) {
+                expanded.extend((0..w).map(|x| unpack_sample(row, x, bits) * scale));
+            }
+            return Ok(expanded);
+        }
+
+        let palette = try!(info.palette.as_ref().ok_or(DecodingError::Internal));
+        let n_channels = if info.trns.is_some() { 4 } else { 3 };
+        let mut expanded = Vec::with_capacity(w * h * n_channels);
+        for row in buffer.chunks(row_len).take(h) {
+            for x in 0..w {
+                let i = unpack_sample(row, x, bits);
+                let rgb = palette
+                    .get(3 * i as usize..3 * i as usize + 3)
+                    .unwrap_or(&[0, 0, 0]);
+                expanded.extend_from_slice(rgb);
+                if let Some(ref trns) = info.trns {
+                    expanded.push(*trns.get(i as usize).unwrap_or(&255));
+                }
+            }
+        }
+        Ok(expanded)
+    }
+
+    // Read the frame as 16 bit samples. 8 bit samples are promoted to 16 bit by replicating their bits, so that the
+    // full 16 bit range is covered.
+    fn read_frame_u16(&mut self) -> Result<Vec<u16>, Error> {
+        match self.depth {
+            BitDepth::_8 => Ok(try!(self.read_frame_u8())
+                .into_iter()
+                .map(|i| (u16::from(i) << 8) | u16::from(i))
+                .collect()),
+            BitDepth::_16 => {
+                // Read the frame into a byte buffer
+                let buffer = try!(self.read_frame());
+
+                // Convert the buffer to a u16 buffer
+                bytes_to_vec_u16::<BigEndian>(&buffer)
+            }
+        }
+    }
+
     /// Try reading the image as 8bit grayscale.
     pub fn read_luma_u8(mut self) -> Result<ImageBuffer2D<Luma<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::Luma, BitDepth::_8) => {
-                let buf_size = self.reader.output_buffer_size();
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
+                let buffer = try!(self.read_frame_u8());
                 let luma_buffer = buffer
                     .into_iter()
                     .map(|i| Luma { data: [i] })
@@ -115,9 +376,7 @@ where
     pub fn read_luma_alpha_u8(mut self) -> Result<ImageBuffer2D<LumaA<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::LumaA, BitDepth::_8) => {
-                let buf_size = self.reader.output_buffer_size();
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
+                let buffer = try!(self.read_frame_u8());
                 let luma_buffer = (&buffer)
                     .chunks(2)
                     .map(|s| LumaA { data: [s[0], s[1]] })
@@ -132,18 +391,11 @@ where
         }
     }
 
-    /// Try reading the image as 16bit grayscale.
+    /// Try reading the image as 16bit grayscale. 8bit images are promoted to 16bit.
     pub fn read_luma_u16(mut self) -> Result<ImageBuffer2D<Luma<u16>>, Error> {
-        match (self.channels, self.depth) {
-            (PixelType::Luma, BitDepth::_16) => {
-                let buf_size = self.reader.output_buffer_size();
-
-                // Read the frame into a byte buffer
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
-
-                // Convert the buffer to a u16 buffer
-                let u16_buffer = try!(bytes_to_vec_u16::<BigEndian>(&buffer));
+        match self.channels {
+            PixelType::Luma => {
+                let u16_buffer = try!(self.read_frame_u16());
                 let luma_buffer = u16_buffer
                     .into_iter()
                     .map(|i| Luma { data: [i as u16] })
@@ -154,22 +406,15 @@ where
                     luma_buffer
                 )))
             }
-            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+            _ => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
         }
     }
 
-    /// Try reading the image as 16bit grayscale with alpha.
+    /// Try reading the image as 16bit grayscale with alpha. 8bit images are promoted to 16bit.
     pub fn read_luma_alpha_u16(mut self) -> Result<ImageBuffer2D<LumaA<u16>>, Error> {
-        match (self.channels, self.depth) {
-            (PixelType::LumaA, BitDepth::_16) => {
-                let buf_size = self.reader.output_buffer_size();
-
-                // Read the frame into a byte buffer
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
-
-                // Convert the buffer to a u16 buffer
-                let u16_buffer = try!(bytes_to_vec_u16::<BigEndian>(&buffer));
+        match self.channels {
+            PixelType::LumaA => {
+                let u16_buffer = try!(self.read_frame_u16());
                 let luma_buffer = (&u16_buffer)
                     .chunks(2)
                     .map(|s| LumaA { data: [s[0], s[1]] })
@@ -180,7 +425,7 @@ where
                     luma_buffer
                 )))
             }
-            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+            _ => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
         }
     }
 
@@ -188,9 +433,7 @@ where
     pub fn read_rgb_u8(mut self) -> Result<ImageBuffer2D<Rgb<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::Rgb, BitDepth::_8) => {
-                let buf_size = self.reader.output_buffer_size();
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
+                let buffer = try!(self.read_frame_u8());
                 let rgb_buffer = (&buffer)
                     .chunks(3)
                     .map(|s| Rgb {
@@ -211,9 +454,7 @@ where
     pub fn read_rgb_alpha_u8(mut self) -> Result<ImageBuffer2D<RgbA<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::RgbA, BitDepth::_8) => {
-                let buf_size = self.reader.output_buffer_size();
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
+                let buffer = try!(self.read_frame_u8());
                 let rgb_buffer = (&buffer)
                     .chunks(4)
                     .map(|s| RgbA {
@@ -230,16 +471,11 @@ where
         }
     }
 
-    /// Try reading the image as RGB 16bit.
+    /// Try reading the image as RGB 16bit. 8bit images are promoted to 16bit.
     pub fn read_rgb_u16(mut self) -> Result<ImageBuffer2D<Rgb<u16>>, Error> {
-        match (self.channels, self.depth) {
-            (PixelType::Rgb, BitDepth::_16) => {
-                let buf_size = self.reader.output_buffer_size();
-
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
-                // Convert the buffer to a u16 buffer
-                let u16_buffer = try!(bytes_to_vec_u16::<BigEndian>(&buffer));
+        match self.channels {
+            PixelType::Rgb => {
+                let u16_buffer = try!(self.read_frame_u16());
                 let rgb_buffer = (&u16_buffer)
                     .chunks(3)
                     .map(|s| Rgb {
@@ -252,20 +488,15 @@ where
                     rgb_buffer
                 )))
             }
-            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+            _ => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
         }
     }
 
-    /// Try reading the image as RGB 16bit with alpha.
+    /// Try reading the image as RGB 16bit with alpha. 8bit images are promoted to 16bit.
     pub fn read_rgb_alpha_u16(mut self) -> Result<ImageBuffer2D<RgbA<u16>>, Error> {
-        match (self.channels, self.depth) {
-            (PixelType::RgbA, BitDepth::_16) => {
-                let buf_size = self.reader.output_buffer_size();
-
-                let mut buffer = vec![0; buf_size];
-                try!(self.reader.next_frame(&mut buffer));
-                // Convert the buffer to a u16 buffer
-                let u16_buffer = try!(bytes_to_vec_u16::<BigEndian>(&buffer));
+        match self.channels {
+            PixelType::RgbA => {
+                let u16_buffer = try!(self.read_frame_u16());
                 let rgb_buffer = (&u16_buffer)
                     .chunks(4)
                     .map(|s| RgbA {
@@ -278,7 +509,7 @@ where
                     rgb_buffer
                 )))
             }
-            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+            _ => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
         }
     }
 
@@ -301,6 +532,38 @@ where
         Ok((self.image_channels(), self.depth()))
     }
 
+    fn read_image_with_metadata(mut self) -> Result<(DynamicImage, Metadata), Error> {
+        let (color_type, bit_depth) = self.read_header()?;
+        let dpi = match self.reader.info().pixel_dims {
+            Some(png::PixelDimensions {
+                xppu,
+                yppu,
+                unit: png::Unit::Meter,
+            }) => Some((
+                f64::from(xppu) / INCHES_PER_METER,
+                f64::from(yppu) / INCHES_PER_METER,
+            )),
+            _ => None,
+        };
+        let metadata = Metadata {
+            dpi,
+            // ICC profiles are not exposed by the png crate.
+            icc_profile: None,
+            color_type,
+            bit_depth,
+        };
+        Ok((self.read_image()?, metadata))
+    }
+
+    fn read_image_with_progress<F>(mut self, mut progress: F) -> Result<DynamicImage, Error>
+    where
+        F: FnMut(f32),
+    {
+        let frame = try!(self.read_frame_with_progress(&mut progress));
+        self.frame = Some(frame);
+        self.read_image()
+    }
+
     fn read_image(mut self) -> Result<DynamicImage, Error> {
         match self.read_header()? {
             (PixelType::Luma, BitDepth::_8) => {
@@ -345,13 +608,60 @@ pub enum EncodingError {
     Encoder(#[cause] png::EncodingError),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Compression level of the PNG image data, trading encoding speed for file size.
+pub enum Compression {
+    /// Fast compression. This is the default.
+    Fast,
+    /// Balanced compression.
+    Default,
+    /// Slowest compression, giving the smallest files.
+    Best,
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression::Fast
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Filter applied to the rows of the PNG image data before compression, each pixel being predicted from its
+/// neighbors.
+pub enum FilterType {
+    /// No filtering.
+    NoFilter = 0,
+    /// Predict from the pixel on the left. This is the default.
+    Sub = 1,
+    /// Predict from the pixel above.
+    Up = 2,
+    /// Predict from the average of the pixels on the left and above.
+    Avg = 3,
+    /// Predict with the Paeth predictor, from the pixels on the left, above and above left.
+    Paeth = 4,
+}
+
+impl Default for FilterType {
+    fn default() -> FilterType {
+        FilterType::Sub
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 /// 8bit PNG encoder type
-pub struct Encoder8;
+pub struct Encoder8 {
+    metadata: Option<Metadata>,
+    compression: Compression,
+    filter: FilterType,
+}
 
 #[derive(Debug, Clone, Default)]
 /// 16bit PNG encoder type
-pub struct Encoder16;
+pub struct Encoder16 {
+    metadata: Option<Metadata>,
+    compression: Compression,
+    filter: FilterType,
+}
 
 impl Encoder8 {
     /// Create a new PNG encoder object.
@@ -359,6 +669,24 @@ impl Encoder8 {
         Encoder8::default()
     }
 
+    /// Write the given metadata along with the image. Only the resolution is currently written.
+    pub fn with_metadata(mut self, metadata: Option<Metadata>) -> Encoder8 {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set the compression level of the image data.
+    pub fn with_compression(mut self, compression: Compression) -> Encoder8 {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the filter applied to the image data before compression.
+    pub fn with_filter(mut self, filter: FilterType) -> Encoder8 {
+        self.filter = filter;
+        self
+    }
+
     /// Write to the output buffer.
     pub fn write<W, P>(&self, out: W, img: &Image2D<P>) -> Result<(), Error>
     where
@@ -379,7 +707,15 @@ impl Encoder8 {
             u8_buffer.extend_from_slice(pix.channels());
         }
         let mut writer = try!(enc.write_header());
-        try!(writer.write_image_data(u8_buffer.as_slice()));
+        try!(write_metadata(&mut writer, &self.metadata));
+        try!(write_image_data(
+            &mut writer,
+            &u8_buffer,
+            (w * P::N_CHANNELS) as usize,
+            P::N_CHANNELS as usize,
+            self.compression,
+            self.filter
+        ));
         Ok(())
     }
 }
@@ -390,6 +726,24 @@ impl Encoder16 {
         Encoder16::default()
     }
 
+    /// Write the given metadata along with the image. Only the resolution is currently written.
+    pub fn with_metadata(mut self, metadata: Option<Metadata>) -> Encoder16 {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set the compression level of the image data.
+    pub fn with_compression(mut self, compression: Compression) -> Encoder16 {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the filter applied to the image data before compression.
+    pub fn with_filter(mut self, filter: FilterType) -> Encoder16 {
+        self.filter = filter;
+        self
+    }
+
     /// Write to the output buffer.
     pub fn write<W, P>(self, out: W, img: &Image2D<P>) -> Result<(), Error>
     where
@@ -411,7 +765,15 @@ impl Encoder16 {
         }
         let u8_buffer = vec_u16_to_bytes::<BigEndian>(&u16_buffer);
         let mut writer = try!(enc.write_header());
-        try!(writer.write_image_data(u8_buffer.as_slice()));
+        try!(write_metadata(&mut writer, &self.metadata));
+        try!(write_image_data(
+            &mut writer,
+            &u8_buffer,
+            (2 * w * P::N_CHANNELS) as usize,
+            2 * P::N_CHANNELS as usize,
+            self.compression,
+            self.filter
+        ));
         Ok(())
     }
 }
@@ -587,6 +949,111 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_read_luma_u8_as_u16() {
+        let img8 = helper_test_read(
+            "test_data/io/png/grayscale_8bit.png",
+            |d| d.read_luma_u8(),
+            32,
+            32,
+        )
+        .unwrap();
+        let img16 = helper_test_read(
+            "test_data/io/png/grayscale_8bit.png",
+            |d| d.read_luma_u16(),
+            32,
+            32,
+        )
+        .unwrap();
+        for (p8, p16) in img8.iter().zip(img16.iter()) {
+            assert_eq!(p16[0] as u32, p8[0] as u32 * 257);
+        }
+        assert!(img16.iter().any(|p| p[0] > 255));
+    }
+
+    #[test]
+    fn test_read_rgb_alpha_u8_as_u16() {
+        let img8 = helper_test_read(
+            "test_data/io/png/rgba_8bit.png",
+            |d| d.read_rgb_alpha_u8(),
+            32,
+            32,
+        )
+        .unwrap();
+        let img16 = helper_test_read(
+            "test_data/io/png/rgba_8bit.png",
+            |d| d.read_rgb_alpha_u16(),
+            32,
+            32,
+        )
+        .unwrap();
+        for (p8, p16) in img8.iter().zip(img16.iter()) {
+            for c in 0..4 {
+                assert_eq!(p16[c] as u32, p8[c] as u32 * 257);
+            }
+        }
+        assert!(helper_test_read(
+            "test_data/io/png/rgba_8bit.png",
+            |d| d.read_rgb_u16(),
+            32,
+            32
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_read_indexed() {
+        let palette = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+        let alpha = [255, 128, 0, 255];
+        let img = helper_test_read(
+            "test_data/io/png/indexed_8bit.png",
+            |d| d.read_rgb_u8(),
+            32,
+            32,
+        )
+        .unwrap();
+        for ((x, y), pix) in img.enumerate_pixels() {
+            assert_eq!(pix.data, palette[((x / 8 + y / 8) % 4) as usize]);
+        }
+        let img = helper_test_read(
+            "test_data/io/png/indexed_alpha_8bit.png",
+            |d| d.read_rgb_alpha_u8(),
+            32,
+            32,
+        )
+        .unwrap();
+        for ((x, y), pix) in img.enumerate_pixels() {
+            let i = ((x / 8 + y / 8) % 4) as usize;
+            assert_eq!(pix.data[0..3], palette[i][..]);
+            assert_eq!(pix[3], alpha[i]);
+        }
+    }
+
+    #[test]
+    fn test_read_low_bit_depth() {
+        let open = |name: &str| {
+            let mut path = current_dir().unwrap();
+            path.push(format!("test_data/io/png/{}.png", name));
+            Decoder::new(File::open(path).unwrap())
+                .unwrap()
+                .read_image()
+                .unwrap()
+        };
+        let img = open("grayscale_1bit");
+        let img = img.as_luma_u8_ref().unwrap();
+        assert_eq!(img.dimensions(), (32, 32));
+        for ((x, y), pix) in img.enumerate_pixels() {
+            let expected = if (x / 8 + y / 8) % 2 == 0 { 0 } else { 255 };
+            assert_eq!(pix[0], expected);
+        }
+
+        let img = open("grayscale_4bit");
+        let img = img.as_luma_u8_ref().unwrap();
+        for ((x, y), pix) in img.enumerate_pixels() {
+            assert_eq!(pix[0], ((x + y) % 16 * 17) as u8);
+        }
+    }
+
     #[test]
     fn test_read_rgb_u8() {
         helper_test_read("test_data/io/png/rgb_8bit.png", |d| d.read_rgb_u8(), 32, 32).unwrap();
@@ -648,4 +1115,195 @@ mod tests {
         let img = mk_test_img::<Rgb<u16>, u16>();
         helper_test_write_roundtrip_u16(img, |d| d.read_rgb_u16());
     }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let img: ImageBuffer2D<Luma<u8>> = mk_test_img();
+        let metadata = Metadata {
+            dpi: Some((300., 72.)),
+            icc_profile: None,
+            color_type: PixelType::Luma,
+            bit_depth: BitDepth::_8,
+        };
+        let mut buf = Vec::new();
+        Encoder8::new()
+            .with_metadata(Some(metadata))
+            .write(&mut buf, &img)
+            .unwrap();
+        let decoder = Decoder::new(Cursor::new(buf.as_slice())).unwrap();
+        let (img2, metadata2) = decoder.read_image_with_metadata().unwrap();
+        assert_eq!(*img2.into_luma_u8().unwrap(), img);
+        let (x_dpi, y_dpi) = metadata2.dpi.unwrap();
+        assert!((x_dpi - 300.).abs() < 0.01);
+        assert!((y_dpi - 72.).abs() < 0.01);
+        assert_eq!(metadata2.color_type, PixelType::Luma);
+        assert_eq!(metadata2.bit_depth, BitDepth::_8);
+
+        // Images written without metadata have no resolution.
+        let mut buf = Vec::new();
+        Encoder8::new().write(&mut buf, &img).unwrap();
+        let decoder = Decoder::new(Cursor::new(buf.as_slice())).unwrap();
+        let (_, metadata) = decoder.read_image_with_metadata().unwrap();
+        assert_eq!(metadata.dpi, None);
+    }
+
+    #[test]
+    fn test_filters_roundtrip() {
+        // Odd widths, so that rows don't align with any power of 2, and 1 to 6 bytes per pixel.
+        let luma8 = ImageBuffer2D::generate(13, 7, |(x, y)| Luma::new([(x * 97 + y * 61) as u8]));
+        let rgb8 = ImageBuffer2D::generate(11, 5, |(x, y)| {
+            Rgb::new([
+                (x * 97 + y * 61) as u8,
+                (x * y * 31) as u8,
+                255 - (x * 13) as u8,
+            ])
+        });
+        let luma16 = ImageBuffer2D::generate(13, 7, |(x, y)| {
+            Luma::new([(x * 40_009 + y * 17_011) as u16])
+        });
+        let rgb16 = ImageBuffer2D::generate(11, 5, |(x, y)| {
+            Rgb::new([
+                (x * 40_009 + y * 17_011) as u16,
+                (x * y * 3_001) as u16,
+                65_535 - (x * 257) as u16,
+            ])
+        });
+        for &filter in &[
+            FilterType::NoFilter,
+            FilterType::Sub,
+            FilterType::Up,
+            FilterType::Avg,
+            FilterType::Paeth,
+        ] {
+            let decoder = |buf: &Vec<u8>| Decoder::new(Cursor::new(buf.clone())).unwrap();
+            let mut buf = Vec::new();
+            Encoder8::new()
+                .with_filter(filter)
+                .write(&mut buf, &luma8)
+                .unwrap();
+            assert_eq!(decoder(&buf).read_luma_u8().unwrap(), luma8, "{:?}", filter);
+            let mut buf = Vec::new();
+            Encoder8::new()
+                .with_filter(filter)
+                .write(&mut buf, &rgb8)
+                .unwrap();
+            assert_eq!(decoder(&buf).read_rgb_u8().unwrap(), rgb8, "{:?}", filter);
+            let mut buf = Vec::new();
+            Encoder16::new()
+                .with_filter(filter)
+                .write(&mut buf, &luma16)
+                .unwrap();
+            assert_eq!(
+                decoder(&buf).read_luma_u16().unwrap(),
+                luma16,
+                "{:?}",
+                filter
+            );
+            let mut buf = Vec::new();
+            Encoder16::new()
+                .with_filter(filter)
+                .write(&mut buf, &rgb16)
+                .unwrap();
+            assert_eq!(decoder(&buf).read_rgb_u16().unwrap(), rgb16, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn test_compression_filters() {
+        let img = ImageBuffer2D::generate(64, 48, |(x, y)| {
+            Rgb::new([(x * y % 256) as u8, (x * 4) as u8, (y * 5 + x / 3) as u8])
+        });
+        let encode = |encoder: Encoder8| {
+            let mut buf = Vec::new();
+            encoder.write(&mut buf, &img).unwrap();
+            let decoded = Decoder::new(Cursor::new(buf.as_slice()))
+                .unwrap()
+                .read_rgb_u8()
+                .unwrap();
+            assert_eq!(decoded, img);
+            buf.len()
+        };
+        let fast = encode(Encoder8::new().with_compression(Compression::Fast));
+        let best = encode(Encoder8::new().with_compression(Compression::Best));
+        assert!(best != fast);
+        for &filter in &[
+            FilterType::NoFilter,
+            FilterType::Sub,
+            FilterType::Up,
+            FilterType::Avg,
+            FilterType::Paeth,
+        ] {
+            encode(Encoder8::new().with_filter(filter));
+        }
+
+        let img16 = ImageBuffer2D::generate(17, 9, |(x, y)| Luma::new([(x * 3000 + y * 7) as u16]));
+        let mut buf = Vec::new();
+        Encoder16::new()
+            .with_filter(FilterType::Paeth)
+            .with_compression(Compression::Best)
+            .write(&mut buf, &img16)
+            .unwrap();
+        let decoded = Decoder::new(Cursor::new(buf.as_slice()))
+            .unwrap()
+            .read_luma_u16()
+            .unwrap();
+        assert_eq!(decoded, img16);
+    }
+
+    #[test]
+    fn test_read_image_with_progress() {
+        for name in &[
+            "rgb_16bit",
+            "rgb_16bit_interlaced",
+            "indexed_2bit_interlaced",
+        ] {
+            let mut path = current_dir().unwrap();
+            path.push(format!("test_data/io/png/{}.png", name));
+            let mut fractions = Vec::new();
+            let img = Decoder::new(File::open(&path).unwrap())
+                .unwrap()
+                .read_image_with_progress(|f| fractions.push(f))
+                .unwrap();
+            assert!(fractions.len() > 1);
+            assert!(fractions.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(fractions.last(), Some(&1.));
+            let expected = Decoder::new(File::open(&path).unwrap())
+                .unwrap()
+                .read_image()
+                .unwrap();
+            assert!(img == expected, "{} differs", name);
+        }
+    }
+
+    #[test]
+    fn test_read_interlaced() {
+        let open = |name: &str| {
+            let mut path = current_dir().unwrap();
+            path.push(format!("test_data/io/png/{}.png", name));
+            Decoder::new(File::open(path).unwrap())
+                .unwrap()
+                .read_image()
+                .unwrap()
+        };
+        for name in &[
+            "grayscale_8bit",
+            "grayscale_alpha_16bit",
+            "rgb_16bit",
+            "rgba_8bit",
+            "indexed_alpha_8bit",
+            "indexed_2bit",
+        ] {
+            let interlaced = open(&format!("{}_interlaced", name));
+            assert!(interlaced == open(name), "{} differs", name);
+        }
+    }
+
+    #[test]
+    fn test_read_header_twice() {
+        let mut dec = Decoder::new(File::open("test_data/io/png/rgb_16bit.png").unwrap()).unwrap();
+        assert_eq!(dec.read_header().unwrap(), (PixelType::Rgb, BitDepth::_16));
+        assert_eq!(dec.read_header().unwrap(), (PixelType::Rgb, BitDepth::_16));
+        let img = dec.read_image().unwrap().into_rgb_u16().unwrap();
+        assert_eq!(img.dimensions(), (32, 32));
+    }
 }
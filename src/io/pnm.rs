@@ -0,0 +1,580 @@
+//! Netpbm (PGM/PPM) codec.
+//!
+//! Binary (P5/P6) and ASCII (P2/P3) images can be decoded, images are always encoded in the binary
+//! formats. Sample values are not rescaled according to the maxval of the image.
+
+use core::{
+    BitDepth, DynamicImage, Image2D, ImageBuffer2D, ImageType, Luma, LumaA, Pixel, PixelType, Rgb,
+    RgbA,
+};
+
+use byteorder::{BigEndian, ByteOrder};
+use failure::Error;
+
+use io::traits::{ImageDecoder, ImageEncoder};
+
+use std::io::{Read, Write};
+
+/// PNM decoder type
+pub struct Decoder<R>
+where
+    R: Read,
+{
+    reader: R,
+    channels: PixelType,
+    depth: BitDepth,
+    width: u32,
+    height: u32,
+    maxval: u16,
+    ascii: bool,
+}
+
+#[derive(Fail, Debug)]
+/// Represent the errors than can occur when decoding a PNM image.
+pub enum DecodingError {
+    #[fail(display = "Incorrect pixel type, image type is {:?}({:?})", _0, _1)]
+    /// The requested type is not the actual type of the image
+    IncorrectPixelType(PixelType, BitDepth),
+    #[fail(display = "Unsupported PNM magic number: {}", _0)]
+    /// The image type is not supported (yet) by the library.
+    UnsupportedType(String),
+    #[fail(display = "Invalid PNM header")]
+    /// The image header is malformed.
+    InvalidHeader,
+    #[fail(display = "Sample value {} is greater than maxval {}", _0, _1)]
+    /// A sample value is out of the range declared in the header.
+    InvalidSample(u32, u16),
+    #[fail(display = "Unexpected end of file")]
+    /// The image data is truncated.
+    UnexpectedEof,
+    #[fail(display = "PNM image too large")]
+    /// The image data would not fit in memory.
+    TooLarge,
+}
+
+impl<R> Decoder<R>
+where
+    R: Read,
+{
+    /// Create a new PNM decoder object.
+    pub fn new(buffer: R) -> Result<Decoder<R>, Error> {
+        let mut dec = Decoder {
+            reader: buffer,
+            channels: PixelType::Luma,
+            depth: BitDepth::_8,
+            width: 0,
+            height: 0,
+            maxval: 0,
+            ascii: false,
+        };
+        let magic = dec.read_token()?;
+        let (channels, ascii) = match magic.as_str() {
+            "P2" => (PixelType::Luma, true),
+            "P3" => (PixelType::Rgb, true),
+            "P5" => (PixelType::Luma, false),
+            "P6" => (PixelType::Rgb, false),
+            _ => return Err(DecodingError::UnsupportedType(magic).into()),
+        };
+        dec.channels = channels;
+        dec.ascii = ascii;
+        dec.width = dec.read_header_value()?;
+        dec.height = dec.read_header_value()?;
+        let maxval = dec.read_header_value()?;
+        ensure!(maxval > 0 && maxval <= 65535, DecodingError::InvalidHeader);
+        dec.maxval = maxval as u16;
+        dec.depth = if maxval < 256 {
+            BitDepth::_8
+        } else {
+            BitDepth::_16
+        };
+        // Reject images whose data can't be addressed before allocating anything for them.
+        let n_bytes = u64::from(dec.width)
+            .checked_mul(u64::from(dec.height))
+            .and_then(|n| n.checked_mul((dec.n_channels() * dec.sample_size()) as u64));
+        match n_bytes {
+            Some(n) if n <= isize::max_value() as u64 => Ok(dec),
+            _ => Err(DecodingError::TooLarge.into()),
+        }
+    }
+
+    // Number of samples per pixel.
+    fn n_channels(&self) -> usize {
+        match self.channels {
+            PixelType::Rgb => 3,
+            _ => 1,
+        }
+    }
+
+    // Size in bytes of a sample in the binary formats.
+    fn sample_size(&self) -> usize {
+        match self.depth {
+            BitDepth::_8 => 1,
+            _ => 2,
+        }
+    }
+
+    // Read a single byte, or `None` at the end of the stream.
+    fn read_byte(&mut self) -> Result<Option<u8>, Error> {
+        let mut b = [0u8];
+        match self.reader.read(&mut b)? {
+            0 => Ok(None),
+            _ => Ok(Some(b[0])),
+        }
+    }
+
+    // Read a whitespace delimited token, skipping comments. The delimiting whitespace character is
+    // consumed.
+    fn read_token(&mut self) -> Result<String, Error> {
+        let mut token = String::new();
+        loop {
+            match self.read_byte()? {
+                Some(b'#') if token.is_empty() => loop {
+                    match self.read_byte()? {
+                        Some(b'\n') | Some(b'\r') | None => break,
+                        _ => (),
+                    }
+                },
+                Some(b) if (b as char).is_ascii_whitespace() => {
+                    if !token.is_empty() {
+                        return Ok(token);
+                    }
+                }
+                Some(b) => token.push(b as char),
+                None => {
+                    if token.is_empty() {
+                        return Err(DecodingError::UnexpectedEof.into());
+                    }
+                    return Ok(token);
+                }
+            }
+        }
+    }
+
+    // Read a numeric header field.
+    fn read_header_value(&mut self) -> Result<u32, Error> {
+        self.read_token()?
+            .parse()
+            .map_err(|_| DecodingError::InvalidHeader.into())
+    }
+
+    // Read all the samples of the image, widened to u16. The buffers grow as data is read, so that a
+    // truncated file can't cause a large allocation.
+    fn read_samples(&mut self) -> Result<Vec<u16>, Error> {
+        let n_samples = self.width as usize * self.height as usize * self.n_channels();
+        let maxval = self.maxval;
+        let samples = if self.ascii {
+            let mut samples = Vec::new();
+            for _ in 0..n_samples {
+                let v: u32 = self
+                    .read_token()?
+                    .parse()
+                    .map_err(|_| DecodingError::InvalidSample(u32::max_value(), maxval))?;
+                ensure!(
+                    v <= u32::from(maxval),
+                    DecodingError::InvalidSample(v, maxval)
+                );
+                samples.push(v as u16);
+            }
+            samples
+        } else {
+            let sample_size = self.sample_size();
+            let n_bytes = n_samples * sample_size;
+            let mut buffer = Vec::new();
+            self.reader
+                .by_ref()
+                .take(n_bytes as u64)
+                .read_to_end(&mut buffer)?;
+            if buffer.len() != n_bytes {
+                return Err(DecodingError::UnexpectedEof.into());
+            }
+            let samples: Vec<u16> = if sample_size == 1 {
+                buffer.into_iter().map(u16::from).collect()
+            } else {
+                buffer.chunks(2).map(BigEndian::read_u16).collect()
+            };
+            if let Some(&v) = samples.iter().find(|&&v| v > maxval) {
+                return Err(DecodingError::InvalidSample(u32::from(v), maxval).into());
+            }
+            samples
+        };
+        Ok(samples)
+    }
+
+    /// Try reading the image as 8bit grayscale.
+    pub fn read_luma_u8(mut self) -> Result<ImageBuffer2D<Luma<u8>>, Error> {
+        match (self.channels, self.depth) {
+            (PixelType::Luma, BitDepth::_8) => {
+                let luma_buffer = self
+                    .read_samples()?
+                    .into_iter()
+                    .map(|s| Luma { data: [s as u8] })
+                    .collect();
+                Ok(ImageBuffer2D::from_vec(
+                    self.width,
+                    self.height,
+                    luma_buffer,
+                )?)
+            }
+            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+        }
+    }
+
+    /// Try reading the image as 16bit grayscale.
+    pub fn read_luma_u16(mut self) -> Result<ImageBuffer2D<Luma<u16>>, Error> {
+        match (self.channels, self.depth) {
+            (PixelType::Luma, BitDepth::_16) => {
+                let luma_buffer = self
+                    .read_samples()?
+                    .into_iter()
+                    .map(|s| Luma { data: [s] })
+                    .collect();
+                Ok(ImageBuffer2D::from_vec(
+                    self.width,
+                    self.height,
+                    luma_buffer,
+                )?)
+            }
+            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+        }
+    }
+
+    /// Try reading the image as RGB 8bit.
+    pub fn read_rgb_u8(mut self) -> Result<ImageBuffer2D<Rgb<u8>>, Error> {
+        match (self.channels, self.depth) {
+            (PixelType::Rgb, BitDepth::_8) => {
+                let rgb_buffer = self
+                    .read_samples()?
+                    .chunks(3)
+                    .map(|s| Rgb {
+                        data: [s[0] as u8, s[1] as u8, s[2] as u8],
+                    })
+                    .collect();
+                Ok(ImageBuffer2D::from_vec(
+                    self.width,
+                    self.height,
+                    rgb_buffer,
+                )?)
+            }
+            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+        }
+    }
+
+    /// Try reading the image as RGB 16bit.
+    pub fn read_rgb_u16(mut self) -> Result<ImageBuffer2D<Rgb<u16>>, Error> {
+        match (self.channels, self.depth) {
+            (PixelType::Rgb, BitDepth::_16) => {
+                let rgb_buffer = self
+                    .read_samples()?
+                    .chunks(3)
+                    .map(|s| Rgb {
+                        data: [s[0], s[1], s[2]],
+                    })
+                    .collect();
+                Ok(ImageBuffer2D::from_vec(
+                    self.width,
+                    self.height,
+                    rgb_buffer,
+                )?)
+            }
+            (_, _) => Err(DecodingError::IncorrectPixelType(self.channels, self.depth).into()),
+        }
+    }
+
+    /// Return the number of channels in the image.
+    pub fn image_channels(&self) -> PixelType {
+        self.channels
+    }
+
+    /// Return the image bit depth.
+    pub fn depth(&self) -> BitDepth {
+        self.depth
+    }
+
+    /// Return the maximum sample value declared in the image header.
+    pub fn maxval(&self) -> u16 {
+        self.maxval
+    }
+}
+
+impl<R> ImageDecoder for Decoder<R>
+where
+    R: Read,
+{
+    fn read_header(&mut self) -> Result<ImageType, Error> {
+        Ok((self.image_channels(), self.depth()))
+    }
+
+    fn read_image(mut self) -> Result<DynamicImage, Error> {
+        match self.read_header()? {
+            (PixelType::Luma, BitDepth::_8) => {
+                Ok(DynamicImage::LumaU8(Box::new(self.read_luma_u8()?)))
+            }
+            (PixelType::Luma, BitDepth::_16) => {
+                Ok(DynamicImage::LumaU16(Box::new(self.read_luma_u16()?)))
+            }
+            (PixelType::Rgb, BitDepth::_8) => {
+                Ok(DynamicImage::RgbU8(Box::new(self.read_rgb_u8()?)))
+            }
+            (PixelType::Rgb, BitDepth::_16) => {
+                Ok(DynamicImage::RgbU16(Box::new(self.read_rgb_u16()?)))
+            }
+            (channels, depth) => Err(DecodingError::IncorrectPixelType(channels, depth).into()),
+        }
+    }
+}
+
+#[derive(Fail, Debug)]
+/// Represent the errors than can occur when encoding to a PNM image.
+pub enum EncodingError {
+    #[fail(display = "Unsupported pixel type")]
+    /// The image type is not supported (yet) by the library or by the PNM format.
+    UnsupportedType(),
+}
+
+#[derive(Debug, Clone, Default)]
+/// 8bit PNM encoder type
+pub struct Encoder8;
+
+#[derive(Debug, Clone, Default)]
+/// 16bit PNM encoder type
+pub struct Encoder16;
+
+// Write the header of a binary PNM image.
+fn write_header<W>(out: &mut W, n_channels: u32, w: u32, h: u32, maxval: u16) -> Result<(), Error>
+where
+    W: Write,
+{
+    let magic = match n_channels {
+        1 => "P5",
+        3 => "P6",
+        _ => return Err(EncodingError::UnsupportedType().into()),
+    };
+    write!(out, "{}\n{} {}\n{}\n", magic, w, h, maxval)?;
+    Ok(())
+}
+
+impl Encoder8 {
+    /// Create a new PNM encoder object.
+    pub fn new() -> Encoder8 {
+        Encoder8::default()
+    }
+
+    /// Write to the output buffer. Grayscale images are written as PGM, RGB images as PPM.
+    pub fn write<W, P>(&self, mut out: W, img: &Image2D<P>) -> Result<(), Error>
+    where
+        W: Write,
+        P: Pixel<Subpixel = u8>,
+    {
+        let (w, h) = img.dimensions();
+        write_header(&mut out, P::N_CHANNELS, w, h, 255)?;
+        let mut u8_buffer = Vec::with_capacity((w * h * P::N_CHANNELS) as usize);
+        for pix in img.iter() {
+            u8_buffer.extend_from_slice(pix.channels());
+        }
+        out.write_all(&u8_buffer)?;
+        Ok(())
+    }
+}
+
+impl Encoder16 {
+    /// Create a new PNM encoder object.
+    pub fn new() -> Encoder16 {
+        Encoder16::default()
+    }
+
+    /// Write to the output buffer. Grayscale images are written as PGM, RGB images as PPM.
+    pub fn write<W, P>(&self, mut out: W, img: &Image2D<P>) -> Result<(), Error>
+    where
+        W: Write,
+        P: Pixel<Subpixel = u16>,
+    {
+        let (w, h) = img.dimensions();
+        write_header(&mut out, P::N_CHANNELS, w, h, 65535)?;
+        let mut u8_buffer = vec![0; (w * h * P::N_CHANNELS * 2) as usize];
+        for (pix, bytes) in img
+            .iter()
+            .zip(u8_buffer.chunks_mut(2 * P::N_CHANNELS as usize))
+        {
+            BigEndian::write_u16_into(pix.channels(), bytes);
+        }
+        out.write_all(&u8_buffer)?;
+        Ok(())
+    }
+}
+
+impl<W, P> ImageEncoder<W, P> for Encoder8
+where
+    W: Write,
+    P: Pixel<Subpixel = u8>,
+{
+    fn write_image(self, out: W, img: &Image2D<P>) -> Result<(), Error> {
+        self.write(out, img)
+    }
+}
+
+impl<W, P> ImageEncoder<W, P> for Encoder16
+where
+    W: Write,
+    P: Pixel<Subpixel = u16>,
+{
+    fn write_image(self, out: W, img: &Image2D<P>) -> Result<(), Error> {
+        self.write(out, img)
+    }
+}
+
+io_encodable_trait!(
+    /// Trait implemented for image types encodable into the PNM format.
+    PnmEncodable,
+    f32;
+    f64;
+    u32;
+    u64;
+    i8;
+    i16;
+    i32;
+    i64;
+    u8 => {
+        |out, img| {
+            let enc = Encoder8::new();
+            enc.write(out, img)
+        }
+    };
+    u16 => {
+        |out, img| {
+            let enc = Encoder16::new();
+            enc.write(out, img)
+        }
+    };
+);
+
+#[cfg(test)]
+mod tests {
+    use core::{Image2DMut, ImageBuffer2D, Pixel, Primitive};
+    use io::pnm::*;
+
+    use num_traits::NumCast;
+
+    use std::io::Cursor;
+
+    // Build a test image whose samples take values in `[0, modulus)`.
+    fn mk_test_img<P, S>(modulus: u32) -> ImageBuffer2D<P>
+    where
+        P: Pixel<Subpixel = S>,
+        S: Primitive,
+    {
+        let mut img = ImageBuffer2D::new(32, 32);
+        for y in 0..32 {
+            for x in 0..32 {
+                let n = <S as NumCast>::from::<u32>((x + 257 * y) % modulus).unwrap();
+                let pix = vec![n; P::N_CHANNELS as usize];
+                img.put_pixel(x, y, P::from_slice(&pix));
+            }
+        }
+        img
+    }
+
+    #[test]
+    fn test_write_luma_u8() {
+        let img = mk_test_img::<Luma<u8>, u8>(256);
+        let mut buf = Vec::new();
+        Encoder8::new().write(&mut buf, &img).unwrap();
+        assert!(buf.starts_with(b"P5\n32 32\n255\n"));
+        let img2 = Decoder::new(Cursor::new(buf))
+            .unwrap()
+            .read_luma_u8()
+            .unwrap();
+        assert_eq!(img, img2);
+    }
+
+    #[test]
+    fn test_write_luma_u16() {
+        let mut img = mk_test_img::<Luma<u16>, u16>(65536);
+        img.put_pixel(3, 4, Luma::new([0xABCD]));
+        let mut buf = Vec::new();
+        Encoder16::new().write(&mut buf, &img).unwrap();
+        let dec = Decoder::new(Cursor::new(buf)).unwrap();
+        assert_eq!(dec.depth(), BitDepth::_16);
+        let img2 = dec.read_luma_u16().unwrap();
+        assert_eq!(img2.get_pixel(3, 4), &Luma::new([0xABCD]));
+        assert_eq!(img, img2);
+    }
+
+    #[test]
+    fn test_write_rgb_u8() {
+        let img = mk_test_img::<Rgb<u8>, u8>(256);
+        let mut buf = Vec::new();
+        Encoder8::new().write(&mut buf, &img).unwrap();
+        let img2 = Decoder::new(Cursor::new(buf))
+            .unwrap()
+            .read_rgb_u8()
+            .unwrap();
+        assert_eq!(img, img2);
+    }
+
+    #[test]
+    fn test_write_rgb_u16() {
+        let img = mk_test_img::<Rgb<u16>, u16>(65536);
+        let mut buf = Vec::new();
+        Encoder16::new().write(&mut buf, &img).unwrap();
+        let img2 = Decoder::new(Cursor::new(buf))
+            .unwrap()
+            .read_rgb_u16()
+            .unwrap();
+        assert_eq!(img, img2);
+    }
+
+    #[test]
+    fn test_write_unsupported() {
+        let img = ImageBuffer2D::<LumaA<u8>>::new(4, 4);
+        assert!(Encoder8::new().write(Vec::new(), &img).is_err());
+    }
+
+    #[test]
+    fn test_read_ascii() {
+        let pgm = b"P2\n# a comment\n3 2\n# another one\n15\n0 1 2\n 3  4\n15\n";
+        let img = Decoder::new(Cursor::new(&pgm[..]))
+            .unwrap()
+            .read_luma_u8()
+            .unwrap();
+        assert_eq!(img.dimensions(), (3, 2));
+        assert_eq!(img.get_pixel(2, 0), &Luma::new([2]));
+        assert_eq!(img.get_pixel(2, 1), &Luma::new([15]));
+
+        let ppm = b"P3 2 1 1000 1000 0 0 0 999 1";
+        let img = Decoder::new(Cursor::new(&ppm[..]))
+            .unwrap()
+            .read_rgb_u16()
+            .unwrap();
+        assert_eq!(img.get_pixel(0, 0), &Rgb::new([1000, 0, 0]));
+        assert_eq!(img.get_pixel(1, 0), &Rgb::new([0, 999, 1]));
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        assert!(Decoder::new(Cursor::new(&b"P7\n1 1\n255\n\0"[..])).is_err());
+        assert!(Decoder::new(Cursor::new(&b"P5\n1\n"[..])).is_err());
+        let dec = Decoder::new(Cursor::new(&b"P2\n1 1\n10\n11\n"[..])).unwrap();
+        assert!(dec.read_luma_u8().is_err());
+        let dec = Decoder::new(Cursor::new(&b"P5\n2 2\n255\n\0\0"[..])).unwrap();
+        assert!(dec.read_luma_u8().is_err());
+    }
+
+    #[test]
+    fn test_read_too_large() {
+        let err = Decoder::new(Cursor::new(&b"P5 4000000000 4000000000 255\n\0"[..]))
+            .err()
+            .unwrap();
+        match err.downcast_ref::<DecodingError>() {
+            Some(&DecodingError::TooLarge) => (),
+            _ => panic!("Unexpected error: {}", err),
+        }
+        // Dimensions which can be addressed but are not backed by actual data must not be allocated up front.
+        for pnm in &[
+            &b"P5 100000 100000 65535\n\0"[..],
+            &b"P2 100000 100000 255\n0"[..],
+        ] {
+            let dec = Decoder::new(Cursor::new(*pnm)).unwrap();
+            assert!(dec.read_image().is_err());
+        }
+    }
+}
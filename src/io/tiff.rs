@@ -1,25 +1,244 @@
 //! TIFF codec.
 
-use core::{BitDepth, DynamicImage, ImageBuffer2D, ImageType, Luma, LumaA, PixelType, Rgb, RgbA};
+use core::{
+    BitDepth, DynamicImage, Endianness, Image2D, ImageBuffer2D, ImageType, Luma, LumaA, Pixel,
+    PixelType, Rect, Rgb, RgbA,
+};
+use helper::generic::is_integer;
 use io::traits::ImageDecoder;
+use io::Metadata;
 
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
 use failure::Error;
-
+use num_traits::{Bounded, Zero};
 use tiff::{
     decoder::{Decoder as TiffDecoder, DecodingResult}, ColorType, TiffError,
 };
 
-use std::io::{Read, Seek};
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// TIFF field types.
+const BYTE: u16 = 1;
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+const UNDEFINED: u16 = 7;
+
+// Tags read by the decoder in addition to the ones the tiff crate handles.
+const STRIP_OFFSETS: u16 = 273;
+const X_RESOLUTION: u16 = 282;
+const Y_RESOLUTION: u16 = 283;
+const RESOLUTION_UNIT: u16 = 296;
+const ICC_PROFILE: u16 = 34675;
+
+// Values of the ResolutionUnit tag.
+const RESOLUTION_INCH: u64 = 2;
+const RESOLUTION_CENTIMETER: u64 = 3;
+// Denominator of the resolutions written by the encoder.
+const RESOLUTION_DENOMINATOR: u32 = 1000;
+
+// Handle on a stream shared between a decoder and the TIFF decoders it creates to read the pages of the file. A mutex
+// keeps decoders `Send` when their stream is.
+struct SharedReader<R>(Arc<Mutex<R>>);
+
+impl<R> SharedReader<R> {
+    fn lock(&self) -> MutexGuard<R> {
+        // The stream is only locked for single reads and seeks, a panic can't leave it in an inconsistent state.
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<R> Clone for SharedReader<R> {
+    fn clone(&self) -> SharedReader<R> {
+        SharedReader(Arc::clone(&self.0))
+    }
+}
+
+impl<R> Read for SharedReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.lock().read(buf)
+    }
+}
+
+impl<R> Seek for SharedReader<R>
+where
+    R: Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.lock().seek(pos)
+    }
+}
+
+// Stream wrapper calling a progress callback with the fraction of the strips read each time the TIFF decoder seeks to
+// the beginning of a new strip, the tiff crate reading the strips in order.
+struct StripProgress<'a, R> {
+    stream: SharedReader<R>,
+    strip_offsets: Vec<u64>,
+    strips_started: usize,
+    progress: &'a mut FnMut(f32),
+}
+
+impl<'a, R> Read for StripProgress<'a, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl<'a, R> Seek for StripProgress<'a, R>
+where
+    R: Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = self.stream.seek(pos)?;
+        if self.strip_offsets.get(self.strips_started) == Some(&pos) {
+            if self.strips_started > 0 {
+                (self.progress)(self.strips_started as f32 / self.strip_offsets.len() as f32);
+            }
+            self.strips_started += 1;
+        }
+        Ok(pos)
+    }
+}
+
+// Create a TIFF decoder positioned on the page `n` of the file.
+fn open_page<S>(mut stream: S, n: usize) -> Result<TiffDecoder<S>, Error>
+where
+    S: Read + Seek,
+{
+    stream.seek(SeekFrom::Start(0))?;
+    let mut dec = TiffDecoder::new(stream)?;
+    for i in 0..n {
+        ensure!(
+            dec.more_images(),
+            "Page {} out of range, the file only has {} pages",
+            n,
+            i + 1
+        );
+        dec = dec.next_image()?;
+    }
+    Ok(dec)
+}
+
+// Read the values of the given tags from the IFD of the page `page` of the file. BYTE, SHORT, LONG, RATIONAL and
+// UNDEFINED values are supported, RATIONAL values being returned as numerator and denominator pairs. Tags missing from
+// the IFD are missing from the result.
+fn read_ifd_fields<S>(
+    stream: &mut S,
+    page: usize,
+    tags: &[u16],
+) -> Result<HashMap<u16, Vec<u64>>, Error>
+where
+    S: Read + Seek,
+{
+    let mut order = [0u8; 2];
+    stream.seek(SeekFrom::Start(0))?;
+    stream.read_exact(&mut order)?;
+    match &order {
+        b"II" => read_ifd_fields_with_order::<_, LittleEndian>(stream, page, tags),
+        b"MM" => read_ifd_fields_with_order::<_, BigEndian>(stream, page, tags),
+        _ => bail!("Invalid TIFF byte order"),
+    }
+}
+
+fn read_ifd_fields_with_order<S, B>(
+    stream: &mut S,
+    page: usize,
+    tags: &[u16],
+) -> Result<HashMap<u16, Vec<u64>>, Error>
+where
+    S: Read + Seek,
+    B: ByteOrder,
+{
+    stream.seek(SeekFrom::Start(4))?;
+    let mut ifd_offset = u64::from(stream.read_u32::<B>()?);
+    for _ in 0..page {
+        stream.seek(SeekFrom::Start(ifd_offset))?;
+        let n_entries = stream.read_u16::<B>()?;
+        stream.seek(SeekFrom::Current(12 * i64::from(n_entries)))?;
+        ifd_offset = u64::from(stream.read_u32::<B>()?);
+    }
+
+    stream.seek(SeekFrom::Start(ifd_offset))?;
+    let n_entries = stream.read_u16::<B>()?;
+    let mut entries = Vec::new();
+    for i in 0..u64::from(n_entries) {
+        let tag = stream.read_u16::<B>()?;
+        let field_type = stream.read_u16::<B>()?;
+        let count = stream.read_u32::<B>()?;
+        if tags.contains(&tag) {
+            entries.push((tag, field_type, count, ifd_offset + 2 + 12 * i + 8));
+        }
+        stream.seek(SeekFrom::Current(4))?;
+    }
+
+    let mut fields = HashMap::new();
+    for (tag, field_type, count, value_pos) in entries {
+        let size = match field_type {
+            BYTE | UNDEFINED => 1,
+            SHORT => 2,
+            LONG => 4,
+            RATIONAL => 8,
+            _ => bail!("Unsupported type {} for TIFF tag {}", field_type, tag),
+        };
+        // Values that don't fit in the entry are stored elsewhere, the entry holding their offset.
+        stream.seek(SeekFrom::Start(value_pos))?;
+        if u64::from(count) * size > 4 {
+            let offset = stream.read_u32::<B>()?;
+            stream.seek(SeekFrom::Start(u64::from(offset)))?;
+        }
+        let mut values = Vec::new();
+        if size == 1 {
+            let mut bytes = Vec::new();
+            (&mut *stream)
+                .take(u64::from(count))
+                .read_to_end(&mut bytes)?;
+            ensure!(
+                bytes.len() == count as usize,
+                "Unexpected end of file in TIFF tag {}",
+                tag
+            );
+            values.extend(bytes.into_iter().map(u64::from));
+        } else {
+            for _ in 0..count {
+                match field_type {
+                    SHORT => values.push(u64::from(stream.read_u16::<B>()?)),
+                    LONG => values.push(u64::from(stream.read_u32::<B>()?)),
+                    _ => {
+                        values.push(u64::from(stream.read_u32::<B>()?));
+                        values.push(u64::from(stream.read_u32::<B>()?));
+                    }
+                }
+            }
+        }
+        fields.insert(tag, values);
+    }
+    Ok(fields)
+}
 
 /// TIFF decoder type
 pub struct Decoder<R>
 where
     R: Read + Seek,
 {
-    reader: TiffDecoder<R>,
+    reader: TiffDecoder<SharedReader<R>>,
+    stream: SharedReader<R>,
+    // Index of the page the decoder reads.
+    page: usize,
     channels: PixelType,
     depth: BitDepth,
     dimensions: (u32, u32),
+    // Samples already decoded by `read_image_with_progress`.
+    samples: Option<DecodingResult>,
 }
 
 #[derive(Fail, Debug)]
@@ -43,9 +262,19 @@ impl<R> Decoder<R>
 where
     R: Read + Seek,
 {
-    /// Create a new TIFF decoder object.
+    /// Create a new TIFF decoder object, reading the first page of the file.
     pub fn new(buffer: R) -> Result<Decoder<R>, Error> {
-        let mut dec = TiffDecoder::new(buffer)?;
+        let stream = SharedReader(Arc::new(Mutex::new(buffer)));
+        let dec = TiffDecoder::new(stream.clone())?;
+        Decoder::from_page(dec, 0, stream)
+    }
+
+    // Create a decoder for the page the TIFF decoder is positioned on, which is the page `page` of the file.
+    fn from_page(
+        mut dec: TiffDecoder<SharedReader<R>>,
+        page: usize,
+        stream: SharedReader<R>,
+    ) -> Result<Decoder<R>, Error> {
         let color_type = dec.colortype()?;
         let (channels, depth) = match &color_type {
             ColorType::Gray(8u8) => (PixelType::Luma, BitDepth::_8),
@@ -62,20 +291,109 @@ where
         let dimensions = dec.dimensions()?;
         Ok(Decoder {
             reader: dec,
+            page,
+            stream,
             channels,
             depth,
             dimensions,
+            samples: None,
         })
     }
 
+    // Create a TIFF decoder positioned on the page `n` of the file.
+    fn seek_page(&self, n: usize) -> Result<TiffDecoder<SharedReader<R>>, Error> {
+        open_page(self.stream.clone(), n)
+    }
+
+    // Decode the samples of the page. `progress` is called with the fraction of the strips read each time a new strip
+    // is started.
+    fn read_samples_with_progress(
+        &mut self,
+        progress: &mut FnMut(f32),
+    ) -> Result<DecodingResult, Error> {
+        if let Some(samples) = self.samples.take() {
+            return Ok(samples);
+        }
+        let mut stream = self.stream.clone();
+        let strip_offsets = read_ifd_fields(&mut stream, self.page, &[STRIP_OFFSETS])?
+            .remove(&STRIP_OFFSETS)
+            .unwrap_or_default();
+        let reader = StripProgress {
+            stream,
+            strip_offsets,
+            strips_started: 0,
+            progress,
+        };
+        Ok(open_page(reader, self.page)?.read_image()?)
+    }
+
+    // Read the resolution in dots per inch and the ICC profile of the page.
+    fn read_metadata_fields(&self) -> Result<(Option<(f64, f64)>, Option<Vec<u8>>), Error> {
+        let mut fields = read_ifd_fields(
+            &mut self.stream.clone(),
+            self.page,
+            &[X_RESOLUTION, Y_RESOLUTION, RESOLUTION_UNIT, ICC_PROFILE],
+        )?;
+        let rational = |values: Option<&Vec<u64>>| match values.map(|v| v.as_slice()) {
+            Some(&[num, den]) if den != 0 => Some(num as f64 / den as f64),
+            _ => None,
+        };
+        // The resolution unit defaults to inches, it can also be unspecified in which case there is no physical
+        // resolution.
+        let inches_per_unit = match fields.get(&RESOLUTION_UNIT).map(|v| v.as_slice()) {
+            None | Some(&[RESOLUTION_INCH]) => Some(1.),
+            Some(&[RESOLUTION_CENTIMETER]) => Some(2.54),
+            _ => None,
+        };
+        let dpi = match (
+            rational(fields.get(&X_RESOLUTION)),
+            rational(fields.get(&Y_RESOLUTION)),
+            inches_per_unit,
+        ) {
+            (Some(x), Some(y), Some(k)) => Some((x * k, y * k)),
+            _ => None,
+        };
+        let icc_profile = fields
+            .remove(&ICC_PROFILE)
+            .map(|v| v.into_iter().map(|b| b as u8).collect());
+        Ok((dpi, icc_profile))
+    }
+
+    // Decode the samples of the page.
+    fn read_samples(&mut self) -> Result<DecodingResult, Error> {
+        match self.samples.take() {
+            Some(samples) => Ok(samples),
+            None => Ok(self.reader.read_image()?),
+        }
+    }
+
+    /// Return the number of pages of the file.
+    pub fn num_pages(&mut self) -> Result<usize, Error> {
+        let mut dec = self.seek_page(0)?;
+        let mut n = 1;
+        while dec.more_images() {
+            dec = dec.next_image()?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// Read the page `n` of the file. Pages can be read in any order, and may have different dimensions and types.
+    ///
+    /// **Error**: if the file has fewer than `n + 1` pages, or if the page can't be decoded.
+    pub fn read_page(&mut self, n: usize) -> Result<DynamicImage, Error> {
+        let dec = self.seek_page(n)?;
+        Decoder::from_page(dec, n, self.stream.clone())?.read_image()
+    }
+
     /// Try reading the image as 8bit grayscale.
     pub fn read_luma_u8(mut self) -> Result<ImageBuffer2D<Luma<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::Luma, BitDepth::_8) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U8(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1) as usize {
+                        if buffer.len() != self.n_pixels() {
                             return Err(DecodingError::Internal.into());
                         }
                         let luma_buffer = buffer
@@ -99,10 +417,10 @@ where
     pub fn read_luma_alpha_u8(mut self) -> Result<ImageBuffer2D<LumaA<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::LumaA, BitDepth::_8) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U8(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1 * 2) as usize {
+                        if buffer.len() != self.n_pixels() * 2 {
                             return Err(DecodingError::Internal.into());
                         }
                         let luma_buffer = buffer
@@ -126,10 +444,10 @@ where
     pub fn read_luma_u16(mut self) -> Result<ImageBuffer2D<Luma<u16>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::Luma, BitDepth::_16) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U16(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1) as usize {
+                        if buffer.len() != self.n_pixels() {
                             return Err(DecodingError::Internal.into());
                         }
                         let luma_buffer = buffer
@@ -153,10 +471,10 @@ where
     pub fn read_luma_alpha_u16(mut self) -> Result<ImageBuffer2D<LumaA<u16>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::LumaA, BitDepth::_16) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U16(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1 * 2) as usize {
+                        if buffer.len() != self.n_pixels() * 2 {
                             return Err(DecodingError::Internal.into());
                         }
                         let luma_buffer = buffer
@@ -180,10 +498,10 @@ where
     pub fn read_rgb_u8(mut self) -> Result<ImageBuffer2D<Rgb<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::Rgb, BitDepth::_8) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U8(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1 * 3) as usize {
+                        if buffer.len() != self.n_pixels() * 3 {
                             return Err(DecodingError::Internal.into());
                         }
                         let rgb_buffer = buffer
@@ -209,10 +527,10 @@ where
     pub fn read_rgb_alpha_u8(mut self) -> Result<ImageBuffer2D<RgbA<u8>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::RgbA, BitDepth::_8) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U8(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1 * 4) as usize {
+                        if buffer.len() != self.n_pixels() * 4 {
                             return Err(DecodingError::Internal.into());
                         }
                         let rgb_buffer = buffer
@@ -238,10 +556,10 @@ where
     pub fn read_rgb_u16(mut self) -> Result<ImageBuffer2D<Rgb<u16>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::Rgb, BitDepth::_16) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U16(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1 * 3) as usize {
+                        if buffer.len() != self.n_pixels() * 3 {
                             return Err(DecodingError::Internal.into());
                         }
                         let rgb_buffer = buffer
@@ -267,10 +585,10 @@ where
     pub fn read_rgb_alpha_u16(mut self) -> Result<ImageBuffer2D<RgbA<u16>>, Error> {
         match (self.channels, self.depth) {
             (PixelType::RgbA, BitDepth::_16) => {
-                let decoded = self.reader.read_image()?;
+                let decoded = self.read_samples()?;
                 match decoded {
                     DecodingResult::U16(buffer) => {
-                        if buffer.len() != (self.dimensions.0 * self.dimensions.1 * 4) as usize {
+                        if buffer.len() != self.n_pixels() * 4 {
                             return Err(DecodingError::Internal.into());
                         }
                         let rgb_buffer = buffer
@@ -292,6 +610,36 @@ where
         }
     }
 
+    /// Read a rectangular region of the image.
+    ///
+    /// The TIFF backend has no support for partial decoding yet, so the whole image is decoded and cropped. The
+    /// decoder can be reused to read several tiles.
+    ///
+    /// **Error**: if the region does not fit inside of the image, or decoding fails.
+    pub fn read_tile(&mut self, tile: Rect) -> Result<DynamicImage, Error> {
+        let (w, h) = self.dimensions;
+        ensure!(
+            u64::from(tile.left()) + u64::from(tile.width()) <= u64::from(w)
+                && u64::from(tile.top()) + u64::from(tile.height()) <= u64::from(h),
+            "Tile {:?} does not fit in a {}x{} image",
+            tile,
+            w,
+            h
+        );
+        let page = Decoder::from_page(self.seek_page(self.page)?, self.page, self.stream.clone())?;
+        Ok(page.read_image()?.crop(tile))
+    }
+
+    /// Return the image dimensions.
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    // Return the number of pixels of the image. Computed on usize, very large images overflow u32.
+    fn n_pixels(&self) -> usize {
+        self.dimensions.0 as usize * self.dimensions.1 as usize
+    }
+
     /// Return the number of channels in the image.
     pub fn image_channels(&self) -> PixelType {
         self.channels
@@ -311,6 +659,29 @@ where
         Ok((self.image_channels(), self.depth()))
     }
 
+    fn read_image_with_metadata(mut self) -> Result<(DynamicImage, Metadata), Error> {
+        let (color_type, bit_depth) = self.read_header()?;
+        let (dpi, icc_profile) = self.read_metadata_fields()?;
+        let metadata = Metadata {
+            dpi,
+            icc_profile,
+            color_type,
+            bit_depth,
+        };
+        Ok((self.read_image()?, metadata))
+    }
+
+    fn read_image_with_progress<F>(mut self, mut progress: F) -> Result<DynamicImage, Error>
+    where
+        F: FnMut(f32),
+    {
+        let samples = self.read_samples_with_progress(&mut progress)?;
+        self.samples = Some(samples);
+        let img = self.read_image()?;
+        progress(1.);
+        Ok(img)
+    }
+
     fn read_image(mut self) -> Result<DynamicImage, Error> {
         match self.read_header()? {
             (PixelType::Luma, BitDepth::_8) => {
@@ -340,3 +711,427 @@ where
         }
     }
 }
+
+#[derive(Fail, Debug)]
+/// Represent the errors than can occur when encoding to a TIFF.
+pub enum EncodingError {
+    #[fail(display = "Unsupported pixel type")]
+    /// The image type is not supported (yet) by the library.
+    UnsupportedType(),
+    #[fail(display = "TIFF file too large")]
+    /// The file would exceed the 4GB size limit of the TIFF format.
+    TooLarge,
+}
+
+// Size the encoder aims for when splitting images in strips, the TIFF specification recommending about 8KB.
+const STRIP_SIZE: u64 = 8192;
+
+// Append LONG values to the page being assembled and return their offset in the file, `base` being the offset of the
+// page.
+fn push_longs(page: &mut Vec<u8>, base: u64, values: &[u64]) -> Result<u64, Error> {
+    let offset = base + page.len() as u64;
+    for &v in values {
+        page.write_u32::<LittleEndian>(v as u32)?;
+    }
+    Ok(offset)
+}
+
+/// TIFF encoder type. Images are appended to the file as separate pages with `add_page`, each page being written to
+/// the output as soon as it is added, so only one page at a time is held in memory. Images are stored uncompressed,
+/// in strips of about 8KB.
+pub struct Encoder<W>
+where
+    W: Write + Seek,
+{
+    out: W,
+    metadata: Option<Metadata>,
+    // Position of the beginning of the file in the output, set when writing the first page.
+    start: Option<u64>,
+    // Length of the file written so far.
+    len: u64,
+    // Position in the file of the offset of the IFD of the next page.
+    next_ifd_pos: u64,
+}
+
+impl<W> Encoder<W>
+where
+    W: Write + Seek,
+{
+    /// Create a new TIFF encoder object. The file is written from the current position of `out`.
+    pub fn new(out: W) -> Encoder<W> {
+        Encoder {
+            out,
+            metadata: None,
+            start: None,
+            len: 0,
+            next_ifd_pos: 4,
+        }
+    }
+
+    /// Write the given metadata along with the images of all the pages added after this call. The resolution and the
+    /// ICC profile are written.
+    pub fn with_metadata(mut self, metadata: Option<Metadata>) -> Encoder<W> {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Append an image to the file as a new page. 8 and 16 bit grayscale and RGB images, with or without alpha, are
+    /// supported.
+    pub fn add_page<P>(&mut self, img: &Image2D<P>) -> Result<(), Error>
+    where
+        P: Pixel,
+    {
+        let bits = 8 * mem::size_of::<P::Subpixel>() as u32;
+        let unsigned = P::Subpixel::min_value() == P::Subpixel::zero();
+        if !is_integer::<P::Subpixel>() || !unsigned || (bits != 8 && bits != 16) {
+            return Err(EncodingError::UnsupportedType().into());
+        }
+        let n_channels = P::N_CHANNELS;
+        let photometric = match n_channels {
+            1 | 2 => 1,
+            3 | 4 => 2,
+            _ => return Err(EncodingError::UnsupportedType().into()),
+        };
+        let (w, h) = img.dimensions();
+        ensure!(w != 0 && h != 0, "Cannot encode an empty image");
+
+        let start = match self.start {
+            Some(start) => start,
+            None => {
+                // Little endian header, the offset of the first IFD is filled when adding the first page.
+                let start = self.out.seek(SeekFrom::Current(0))?;
+                self.out.write_all(&[b'I', b'I', 42, 0, 0, 0, 0, 0])?;
+                self.start = Some(start);
+                self.len = 8;
+                start
+            }
+        };
+
+        // The page is assembled in memory, `base` being its offset in the file.
+        let base = self.len;
+        let mut page = img.to_bytes(Endianness::Little);
+        let data_len = page.len() as u64;
+        // IFDs and values stored out of them must begin on a word boundary.
+        if page.len() % 2 == 1 {
+            page.push(0);
+        }
+        let row_len = data_len / u64::from(h);
+        let rows_per_strip = min(max(STRIP_SIZE / row_len, 1), u64::from(h));
+        let strip_len = rows_per_strip * row_len;
+        let n_strips = (u64::from(h) + rows_per_strip - 1) / rows_per_strip;
+        let strip_offsets: Vec<u64> = (0..n_strips).map(|i| base + i * strip_len).collect();
+        let strip_byte_counts: Vec<u64> = (0..n_strips)
+            .map(|i| min(strip_len, data_len - i * strip_len))
+            .collect();
+
+        // Values that don't fit in the 4 bytes of an IFD entry are stored before the IFD.
+        let bits_per_sample = if n_channels > 2 {
+            let offset = base + page.len() as u64;
+            for _ in 0..n_channels {
+                page.write_u16::<LittleEndian>(bits as u16)?;
+            }
+            offset
+        } else {
+            u64::from(bits | (bits << 16))
+        };
+        let (strip_offsets, strip_byte_counts) = if n_strips > 1 {
+            (
+                push_longs(&mut page, base, &strip_offsets)?,
+                push_longs(&mut page, base, &strip_byte_counts)?,
+            )
+        } else {
+            (strip_offsets[0], strip_byte_counts[0])
+        };
+        let mut metadata_entries = Vec::new();
+        if let Some((x_dpi, y_dpi)) = self.metadata.as_ref().and_then(|m| m.dpi) {
+            for &(tag, dpi) in &[(X_RESOLUTION, x_dpi), (Y_RESOLUTION, y_dpi)] {
+                let num = (dpi * f64::from(RESOLUTION_DENOMINATOR)).round() as u64;
+                let offset =
+                    push_longs(&mut page, base, &[num, u64::from(RESOLUTION_DENOMINATOR)])?;
+                metadata_entries.push((tag, RATIONAL, 1, offset));
+            }
+            metadata_entries.push((RESOLUTION_UNIT, SHORT, 1, RESOLUTION_INCH));
+        }
+        if let Some(icc_profile) = self.metadata.as_ref().and_then(|m| m.icc_profile.as_ref()) {
+            let value = if icc_profile.len() > 4 {
+                let offset = base + page.len() as u64;
+                page.extend_from_slice(icc_profile);
+                if page.len() % 2 == 1 {
+                    page.push(0);
+                }
+                offset
+            } else {
+                let mut bytes = [0; 4];
+                bytes[..icc_profile.len()].copy_from_slice(icc_profile);
+                u64::from(LittleEndian::read_u32(&bytes))
+            };
+            metadata_entries.push((ICC_PROFILE, UNDEFINED, icc_profile.len() as u32, value));
+        }
+
+        let mut entries = vec![
+            (256, LONG, 1, u64::from(w)),
+            (257, LONG, 1, u64::from(h)),
+            (258, SHORT, n_channels, bits_per_sample),
+            (259, SHORT, 1, 1),
+            (262, SHORT, 1, photometric),
+            (STRIP_OFFSETS, LONG, n_strips as u32, strip_offsets),
+            (277, SHORT, 1, u64::from(n_channels)),
+            (278, LONG, 1, rows_per_strip),
+            (279, LONG, n_strips as u32, strip_byte_counts),
+            (284, SHORT, 1, 1),
+        ];
+        if n_channels % 2 == 0 {
+            // Unassociated alpha
+            entries.push((338, SHORT, 1, 2));
+        }
+        // IFD entries are sorted by tag.
+        entries.extend(metadata_entries);
+        entries.sort_by_key(|e| e.0);
+        let ifd_offset = base + page.len() as u64;
+        let end = ifd_offset + 6 + 12 * entries.len() as u64;
+        if end > u64::from(u32::max_value()) {
+            return Err(EncodingError::TooLarge.into());
+        }
+        page.write_u16::<LittleEndian>(entries.len() as u16)?;
+        for (tag, field_type, count, value) in entries {
+            page.write_u16::<LittleEndian>(tag)?;
+            page.write_u16::<LittleEndian>(field_type)?;
+            page.write_u32::<LittleEndian>(count)?;
+            page.write_u32::<LittleEndian>(value as u32)?;
+        }
+        page.write_u32::<LittleEndian>(0)?;
+
+        self.out.write_all(&page)?;
+        // Link the page to the previous one.
+        self.out.seek(SeekFrom::Start(start + self.next_ifd_pos))?;
+        self.out.write_u32::<LittleEndian>(ifd_offset as u32)?;
+        self.out.seek(SeekFrom::Start(start + end))?;
+        self.len = end;
+        self.next_ifd_pos = end - 4;
+        Ok(())
+    }
+
+    /// Finish writing the file and flush the output buffer.
+    ///
+    /// **Error**: if no page was added to the file.
+    pub fn finish(mut self) -> Result<(), Error> {
+        ensure!(
+            self.start.is_some(),
+            "Cannot write a TIFF file without pages"
+        );
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_tile() {
+        for name in &[
+            "grayscale_8bit",
+            "grayscale_16bit",
+            "rgb_8bit",
+            "rgba_16bit",
+        ] {
+            let path = format!("./test_data/io/tiff/{}.tiff", name);
+            let full = Decoder::new(File::open(&path).unwrap())
+                .unwrap()
+                .read_image()
+                .unwrap();
+            let mut dec = Decoder::new(File::open(&path).unwrap()).unwrap();
+            let (w, h) = dec.dimensions();
+            for &tile in &[
+                Rect::new(0, 0, w, h),
+                Rect::new(3, 5, 7, 4),
+                Rect::new(w - 1, h - 1, 1, 1),
+            ] {
+                let cropped = dec.read_tile(tile).unwrap();
+                assert_eq!(cropped.image_type(), full.image_type());
+                match (&full, &cropped) {
+                    (DynamicImage::LumaU8(f), DynamicImage::LumaU8(c)) => {
+                        assert!(f.sub_image(tile) == c.get_view())
+                    }
+                    (DynamicImage::LumaU16(f), DynamicImage::LumaU16(c)) => {
+                        assert!(f.sub_image(tile) == c.get_view())
+                    }
+                    (DynamicImage::RgbU8(f), DynamicImage::RgbU8(c)) => {
+                        assert!(f.sub_image(tile) == c.get_view())
+                    }
+                    (DynamicImage::RgbAU16(f), DynamicImage::RgbAU16(c)) => {
+                        assert!(f.sub_image(tile) == c.get_view())
+                    }
+                    _ => panic!("Unexpected image type"),
+                }
+            }
+            assert!(dec.read_tile(Rect::new(1, 0, w, h)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_read_image_with_progress() {
+        // 768 bytes rows, written in 10 strips of 10 rows
+        let img = ImageBuffer2D::generate(256, 100, |(x, y)| Rgb::new([x as u8, y as u8, 7]));
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut enc = Encoder::new(&mut buf);
+            enc.add_page(&img).unwrap();
+            enc.finish().unwrap();
+        }
+        let mut fractions = Vec::new();
+        let read = Decoder::new(Cursor::new(buf.into_inner()))
+            .unwrap()
+            .read_image_with_progress(|f| fractions.push(f))
+            .unwrap();
+        let expected: Vec<f32> = (1..11).map(|i| i as f32 / 10.).collect();
+        assert_eq!(fractions, expected);
+        assert_eq!(read.into_rgb_u8().unwrap().as_ref(), &img);
+
+        // Single strip image
+        let small = ImageBuffer2D::generate(6, 5, |(x, y)| Luma::new([(x * 40 + y) as u8]));
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut enc = Encoder::new(&mut buf);
+            enc.add_page(&small).unwrap();
+            enc.finish().unwrap();
+        }
+        let mut fractions = Vec::new();
+        let read = Decoder::new(Cursor::new(buf.into_inner()))
+            .unwrap()
+            .read_image_with_progress(|f| fractions.push(f))
+            .unwrap();
+        assert_eq!(fractions, vec![1.]);
+        assert_eq!(read.into_luma_u8().unwrap().as_ref(), &small);
+    }
+
+    #[test]
+    fn test_multipage() {
+        let luma = ImageBuffer2D::generate(5, 4, |(x, y)| Luma::new([(x * 50 + y) as u8]));
+        let rgb = ImageBuffer2D::generate(3, 7, |(x, y)| {
+            Rgb::new([x as u16 * 20000, y as u16 * 9000, 65535])
+        });
+        let rgba =
+            ImageBuffer2D::generate(8, 1, |(x, _)| RgbA::new([x as u8, 1, 2, 255 - x as u8]));
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut enc = Encoder::new(&mut buf);
+            enc.add_page(&luma).unwrap();
+            enc.add_page(&rgb).unwrap();
+            enc.add_page(&rgba).unwrap();
+            assert!(enc
+                .add_page(&ImageBuffer2D::solid(2, 2, Luma::new([0.5f32])))
+                .is_err());
+            enc.finish().unwrap();
+        }
+
+        let mut dec = Decoder::new(Cursor::new(buf.into_inner())).unwrap();
+        assert_eq!(dec.dimensions(), (5, 4));
+        assert_eq!(dec.num_pages().unwrap(), 3);
+        assert_eq!(
+            dec.read_page(2)
+                .unwrap()
+                .into_rgb_alpha_u8()
+                .unwrap()
+                .as_ref(),
+            &rgba
+        );
+        assert_eq!(
+            dec.read_page(0).unwrap().into_luma_u8().unwrap().as_ref(),
+            &luma
+        );
+        assert_eq!(
+            dec.read_page(1).unwrap().into_rgb_u16().unwrap().as_ref(),
+            &rgb
+        );
+        assert!(dec.read_page(3).is_err());
+        assert_eq!(
+            dec.read_image().unwrap().into_luma_u8().unwrap().as_ref(),
+            &luma
+        );
+
+        assert!(Encoder::new(Cursor::new(Vec::new())).finish().is_err());
+    }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let img = ImageBuffer2D::generate(40, 30, |(x, y)| Rgb::new([x as u8, y as u8, 7]));
+        let metadata = Metadata {
+            dpi: Some((300., 72.5)),
+            icc_profile: Some(vec![1, 2, 3, 4, 5]),
+            color_type: PixelType::Rgb,
+            bit_depth: BitDepth::_8,
+        };
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut enc = Encoder::new(&mut buf).with_metadata(Some(metadata.clone()));
+            enc.add_page(&img).unwrap();
+            enc.finish().unwrap();
+        }
+        let (img2, metadata2) = Decoder::new(Cursor::new(buf.into_inner()))
+            .unwrap()
+            .read_image_with_metadata()
+            .unwrap();
+        assert_eq!(img2.into_rgb_u8().unwrap().as_ref(), &img);
+        assert_eq!(metadata2, metadata);
+
+        // Images written without metadata have no resolution.
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut enc = Encoder::new(&mut buf);
+            enc.add_page(&img).unwrap();
+            enc.finish().unwrap();
+        }
+        let (_, metadata) = Decoder::new(Cursor::new(buf.into_inner()))
+            .unwrap()
+            .read_image_with_metadata()
+            .unwrap();
+        assert_eq!(metadata.dpi, None);
+        assert_eq!(metadata.icc_profile, None);
+    }
+
+    #[test]
+    fn test_read_header_twice() {
+        let mut dec =
+            Decoder::new(File::open("./test_data/io/tiff/rgba_16bit.tiff").unwrap()).unwrap();
+        assert_eq!(dec.read_header().unwrap(), (PixelType::RgbA, BitDepth::_16));
+        assert_eq!(dec.read_header().unwrap(), (PixelType::RgbA, BitDepth::_16));
+        let img = dec.read_image().unwrap().into_rgb_alpha_u16().unwrap();
+        assert_eq!(img.dimensions(), (32, 32));
+    }
+
+    #[test]
+    fn test_write_at_offset() {
+        // Offsets in the file are relative to the position the encoder started writing at.
+        let img = ImageBuffer2D::generate(3, 2, |(x, y)| Rgb::new([x as u8, y as u8, 7]));
+        let mut buf = Cursor::new(vec![1, 2, 3]);
+        buf.set_position(3);
+        {
+            let mut enc = Encoder::new(&mut buf);
+            enc.add_page(&img).unwrap();
+            enc.add_page(&img).unwrap();
+            enc.finish().unwrap();
+        }
+        let mut dec = Decoder::new(Cursor::new(buf.into_inner().split_off(3))).unwrap();
+        assert_eq!(dec.num_pages().unwrap(), 2);
+        assert_eq!(
+            dec.read_page(1).unwrap().into_rgb_u8().unwrap().as_ref(),
+            &img
+        );
+    }
+
+    #[test]
+    fn test_decoder_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let dec = Decoder::new(File::open("./test_data/io/tiff/rgb_8bit.tiff").unwrap()).unwrap();
+        assert_send(&dec);
+        let img = ::std::thread::spawn(move || dec.read_image().unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(img.image_type(), (PixelType::Rgb, BitDepth::_8));
+    }
+}
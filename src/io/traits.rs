@@ -3,17 +3,45 @@
 use core::{DynamicImage, Image2D, ImageBuffer2D, ImageType, Luma, LumaA, Pixel, Rgb, RgbA};
 
 use failure::Error;
+use io::Metadata;
 
 use std::io::Write;
 
 /// Trait implemented by all image decoders.
 pub trait ImageDecoder: Sized {
-    /// Read the image header and return the image information.
+    /// Read the image header and return the image information. This can be called any number of times before the image
+    /// is read with `read_image` or one of the other reading methods: it always returns the same information and does
+    /// not advance the decoder past the image data.
     fn read_header(&mut self) -> Result<ImageType, Error>;
 
     /// Read the image.
     fn read_image(self) -> Result<DynamicImage, Error>;
 
+    /// Read the image, calling `progress` with the fraction of the image decoded so far, between 0 and 1, as it is
+    /// being read. Decoders that can't report intermediate progress only call it once the image is read. The result
+    /// is the same as with `read_image`.
+    fn read_image_with_progress<F>(self, mut progress: F) -> Result<DynamicImage, Error>
+    where
+        F: FnMut(f32),
+    {
+        let img = self.read_image()?;
+        progress(1.);
+        Ok(img)
+    }
+
+    /// Read the image along with its metadata. Decoders that do not support reading metadata only fill in the
+    /// fields provided by the image header.
+    fn read_image_with_metadata(mut self) -> Result<(DynamicImage, Metadata), Error> {
+        let (color_type, bit_depth) = self.read_header()?;
+        let metadata = Metadata {
+            dpi: None,
+            icc_profile: None,
+            color_type,
+            bit_depth,
+        };
+        Ok((self.read_image()?, metadata))
+    }
+
     /// Try reading the image as 8bit grayscale.
     fn read_luma_u8(self) -> Result<Box<ImageBuffer2D<Luma<u8>>>, Error> {
         self.read_image()?.into_luma_u8()
@@ -2,20 +2,27 @@
 #![deny(missing_docs)]
 
 extern crate byteorder;
+extern crate deflate;
 #[macro_use]
 extern crate failure;
 #[macro_use]
 pub extern crate ndarray;
+#[cfg(feature = "fft")]
+extern crate rustfft;
 extern crate num_traits;
 extern crate png;
 #[cfg(feature = "rand_integration")]
 extern crate rand;
+#[cfg(feature = "rayon_integration")]
+extern crate rayon;
 #[cfg(test)]
 extern crate tempfile;
 extern crate tiff;
 
 pub mod core;
+pub mod draw;
 mod helper;
 pub mod io;
 mod math;
+pub mod prelude;
 pub mod processing;
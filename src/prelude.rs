@@ -0,0 +1,7 @@
+//! Re-exports the most commonly used items of the crate, to be glob imported with `use ndimage::prelude::*;`.
+
+pub use core::padding::Padding;
+pub use core::{
+    DynamicImage, Image2D, Image2DMut, ImageBuffer2D, Luma, LumaA, Pixel, Rect, Rgb, RgbA,
+};
+pub use io::{open, save};
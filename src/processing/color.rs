@@ -0,0 +1,368 @@
+//! Contains color and tone adjustment functions.
+
+use core::{Image2D, ImageBuffer2D, Luma, Pixel, Primitive, Rgb, YCbCr};
+use helper::generic::{from_f64_clamped, full_intensity, is_integer};
+
+use num_traits::{cast, Bounded};
+
+/// Apply gamma correction to an image, raising the normalized value of every channel to the power `1 / gamma`, so that
+/// a gamma greater than 1 brightens the mid-tones and a gamma lower than 1 darkens them. Values are normalized from the
+/// `[0, max]` range of the subpixel type for integer types, and are expected in the `[0, 1]` range for floating point
+/// types. The results are clamped to that range.
+pub fn gamma_correct<P>(img: &Image2D<P>, gamma: f64) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let max = full_intensity::<P::Subpixel>();
+    let exponent = gamma.recip();
+    map_channels_clamped(img, |v| (v / max).max(0.).min(1.).powf(exponent) * max)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Enumerate the methods used to convert color images to grayscale.
+pub enum GrayscaleMethod {
+    /// Mean of the red, green and blue channels.
+    Average,
+    /// Weighted sum with the Rec. 709 luma coefficients `(0.2126, 0.7152, 0.0722)`.
+    Luminosity709,
+    /// Weighted sum with the Rec. 601 luma coefficients `(0.299, 0.587, 0.114)`.
+    Luminosity601,
+    /// Maximum of the red, green and blue channels, i.e. the value of the HSV color model.
+    Max,
+    /// Weighted sum with custom red, green and blue coefficients.
+    Custom([f64; 3]),
+}
+
+/// Convert a color image to grayscale with the given method. Results are rounded for integer types and clamped to the
+/// range of the subpixel type.
+pub fn to_grayscale<P>(img: &Image2D<Rgb<P>>, method: GrayscaleMethod) -> ImageBuffer2D<Luma<P>>
+where
+    P: Primitive,
+{
+    let weights = match method {
+        GrayscaleMethod::Average => Some([1. / 3.; 3]),
+        GrayscaleMethod::Luminosity709 => Some([0.2126, 0.7152, 0.0722]),
+        GrayscaleMethod::Luminosity601 => Some([0.299, 0.587, 0.114]),
+        GrayscaleMethod::Custom(weights) => Some(weights),
+        GrayscaleMethod::Max => None,
+    };
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        let p = img.get_pixel(x, y);
+        let weights = match weights {
+            Some(weights) => weights,
+            None => {
+                let v = p.data.iter().fold(p[0], |m, &c| if c > m { c } else { m });
+                return Luma::new([v]);
+            }
+        };
+        let v = p
+            .data
+            .iter()
+            .zip(weights.iter())
+            .map(|(c, w)| cast::<P, f64>(*c).unwrap_or(0.) * w)
+            .sum::<f64>();
+        Luma::new([from_f64_clamped(v)])
+    })
+}
+
+// Apply a function to every channel of an image, computed on `f64`. The results are rounded for integer types and
+// clamped to the range of the subpixel type.
+fn map_channels_clamped<P, F>(img: &Image2D<P>, f: F) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+    F: Fn(f64) -> f64,
+{
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        img.get_pixel(x, y)
+            .map(|c| from_f64_clamped(f(cast::<P::Subpixel, f64>(c).unwrap_or(0.))))
+    })
+}
+
+/// Invert the intensity of an image. Integer channels are mirrored around the middle of the range of the subpixel type,
+/// i.e. `v` becomes `max - v` for unsigned types and `-1 - v` for signed types. Floating point channels, expected in
+/// the `[0, 1]` range, become `1 - v`.
+pub fn invert<P>(img: &Image2D<P>) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let offset = if is_integer::<P::Subpixel>() {
+        cast::<P::Subpixel, f64>(P::Subpixel::min_value()).unwrap_or(0.)
+            + cast::<P::Subpixel, f64>(P::Subpixel::max_value()).unwrap_or(0.)
+    } else {
+        full_intensity::<P::Subpixel>()
+    };
+    map_channels_clamped(img, |v| offset - v)
+}
+
+/// Add `delta` to every channel of an image, saturating at the bounds of the subpixel type. Results are rounded for
+/// integer types.
+pub fn brighten<P>(img: &Image2D<P>, delta: f64) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    map_channels_clamped(img, |v| v + delta)
+}
+
+/// Multiply every channel of an image by `factor`, saturating at the bounds of the subpixel type. Results are rounded
+/// for integer types.
+pub fn scale<P>(img: &Image2D<P>, factor: f64) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    map_channels_clamped(img, |v| v * factor)
+}
+
+// Decode a normalized sRGB value to linear light with the sRGB transfer function.
+fn srgb_decode(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Encode a normalized linear light value with the sRGB transfer function.
+fn srgb_encode(v: f64) -> f64 {
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Convert the channels of an sRGB encoded image to linear light, e.g. before blending or resampling it. Values are
+/// normalized like with `gamma_correct`. Every channel is converted, so alpha channels should be split off first.
+pub fn srgb_to_linear<P>(img: &Image2D<P>) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let max = full_intensity::<P::Subpixel>();
+    map_channels_clamped(img, |v| srgb_decode((v / max).max(0.).min(1.)) * max)
+}
+
+/// Encode the channels of a linear light image with the sRGB transfer function. This is the inverse of
+/// `srgb_to_linear`.
+pub fn linear_to_srgb<P>(img: &Image2D<P>) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let max = full_intensity::<P::Subpixel>();
+    map_channels_clamped(img, |v| srgb_encode((v / max).max(0.).min(1.)) * max)
+}
+
+/// Convert an RGB image to the YCbCr colorspace.
+pub fn rgb_to_ycbcr<P>(img: &Image2D<Rgb<P>>) -> ImageBuffer2D<YCbCr<P>>
+where
+    P: Primitive,
+{
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| YCbCr::from(img.get_pixel(x, y)))
+}
+
+/// Convert a YCbCr image to the RGB colorspace.
+pub fn ycbcr_to_rgb<P>(img: &Image2D<YCbCr<P>>) -> ImageBuffer2D<Rgb<P>>
+where
+    P: Primitive,
+{
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| Rgb::from(img.get_pixel(x, y)))
+}
+
+/// Split a YCbCr image into planes with 4:2:0 chroma subsampling: a full resolution luma plane, and chroma planes
+/// averaged over 2x2 blocks. The chroma planes are `((width + 1) / 2, (height + 1) / 2)` large, blocks on the right
+/// and bottom edges of images with odd dimensions are averaged over the pixels they contain.
+pub fn subsample_420<P>(
+    img: &Image2D<YCbCr<P>>,
+) -> (
+    ImageBuffer2D<Luma<P>>,
+    ImageBuffer2D<Luma<P>>,
+    ImageBuffer2D<Luma<P>>,
+)
+where
+    P: Primitive,
+{
+    let (w, h) = img.dimensions();
+    let (cw, ch) = ((w + 1) / 2, (h + 1) / 2);
+    let chroma = |c: u8| {
+        ImageBuffer2D::generate(cw, ch, |(x, y)| {
+            let (mut sum, mut n) = (0., 0.);
+            for by in 2 * y..(2 * y + 2).min(h) {
+                for bx in 2 * x..(2 * x + 2).min(w) {
+                    sum += cast::<P, f64>(img.get_pixel(bx, by)[c]).unwrap_or(0.);
+                    n += 1.;
+                }
+            }
+            Luma::new([from_f64_clamped(sum / n)])
+        })
+    };
+    let luma = ImageBuffer2D::generate(w, h, |(x, y)| Luma::new([img.get_pixel(x, y)[0]]));
+    (luma, chroma(1), chroma(2))
+}
+
+/// Reassemble a YCbCr image from 4:2:0 subsampled planes, as produced by `subsample_420`. The chroma planes are
+/// upsampled by replicating each of their pixels over a 2x2 block.
+///
+/// # Panics
+///
+/// Panics if the chroma planes are not `((width + 1) / 2, (height + 1) / 2)` large, `(width, height)` being the
+/// dimensions of the luma plane.
+pub fn upsample_420<P>(
+    luma: &Image2D<Luma<P>>,
+    cb: &Image2D<Luma<P>>,
+    cr: &Image2D<Luma<P>>,
+) -> ImageBuffer2D<YCbCr<P>>
+where
+    P: Primitive,
+{
+    let (w, h) = luma.dimensions();
+    let chroma_dims = ((w + 1) / 2, (h + 1) / 2);
+    assert_eq!(cb.dimensions(), chroma_dims);
+    assert_eq!(cr.dimensions(), chroma_dims);
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        YCbCr::new([
+            luma.get_pixel(x, y)[0],
+            cb.get_pixel(x / 2, y / 2)[0],
+            cr.get_pixel(x / 2, y / 2)[0],
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gamma_correct() {
+        let img = ImageBuffer2D::generate(3, 1, |(x, _)| Luma::new([[0u8, 128, 255][x as usize]]));
+        let brighter = gamma_correct(&img, 2.2);
+        let darker = gamma_correct(&img, 1. / 2.2);
+        assert_eq!(brighter.get_pixel(0, 0), &Luma::new([0]));
+        assert_eq!(brighter.get_pixel(2, 0), &Luma::new([255]));
+        assert_eq!(darker.get_pixel(0, 0), &Luma::new([0]));
+        assert_eq!(darker.get_pixel(2, 0), &Luma::new([255]));
+        assert!(brighter.get_pixel(1, 0)[0] > 128);
+        assert!(darker.get_pixel(1, 0)[0] < 128);
+        assert_eq!(darker.get_pixel(1, 0), &Luma::new([56]));
+
+        let img =
+            ImageBuffer2D::generate(4, 4, |(x, y)| Rgb::new([x as u16 * 1000, y as u16, 65535]));
+        assert_eq!(gamma_correct(&img, 1.), img);
+
+        let img =
+            ImageBuffer2D::generate(3, 1, |(x, _)| Luma::new([[-0.5f32, 0.25, 2.][x as usize]]));
+        let corrected = gamma_correct(&img, 0.5);
+        assert_eq!(corrected.get_pixel(0, 0), &Luma::new([0.]));
+        assert_eq!(corrected.get_pixel(1, 0), &Luma::new([0.0625]));
+        assert_eq!(corrected.get_pixel(2, 0), &Luma::new([1.]));
+    }
+
+    #[test]
+    fn test_invert() {
+        let img = ImageBuffer2D::generate(16, 16, |(x, y)| Luma::new([(x + 16 * y) as u8]));
+        let inverted = invert(&img);
+        assert_eq!(inverted.get_pixel(0, 0), &Luma::new([255]));
+        assert_eq!(inverted.get_pixel(15, 15), &Luma::new([0]));
+        assert_eq!(invert(&inverted), img);
+
+        let img =
+            ImageBuffer2D::generate(2, 1, |(x, _)| Rgb::new([[-128i8, 5][x as usize], 0, 127]));
+        assert_eq!(invert(&img).get_pixel(0, 0), &Rgb::new([127, -1, -128]));
+        assert_eq!(invert(&invert(&img)), img);
+
+        let img = ImageBuffer2D::solid(2, 2, Luma::new([0.25f32]));
+        assert_eq!(invert(&img), ImageBuffer2D::solid(2, 2, Luma::new([0.75])));
+    }
+
+    #[test]
+    fn test_brighten_scale() {
+        let img = ImageBuffer2D::generate(3, 1, |(x, _)| Luma::new([[10u8, 128, 250][x as usize]]));
+        let brighter = brighten(&img, 20.);
+        assert!(brighter.iter().map(|p| p[0]).eq(vec![30, 148, 255]));
+        let darker = brighten(&img, -20.);
+        assert!(darker.iter().map(|p| p[0]).eq(vec![0, 108, 230]));
+        let scaled = scale(&img, 1.5);
+        assert!(scaled.iter().map(|p| p[0]).eq(vec![15, 192, 255]));
+        assert_eq!(scale(&img, -1.), ImageBuffer2D::new(3, 1));
+
+        let img = ImageBuffer2D::solid(1, 1, Luma::new([-100i16]));
+        assert_eq!(scale(&img, 1000.).get_pixel(0, 0), &Luma::new([-32768]));
+        let img = ImageBuffer2D::solid(1, 1, Rgb::new([0.5f64, 2., -1.]));
+        assert_eq!(
+            brighten(&img, 0.25).get_pixel(0, 0),
+            &Rgb::new([0.75, 2.25, -0.75])
+        );
+    }
+
+    #[test]
+    fn test_to_grayscale() {
+        let img = ImageBuffer2D::solid(2, 2, Rgb::new([30u8, 60, 90]));
+        let gray = |method| to_grayscale(&img, method).get_pixel(1, 1)[0];
+        assert_eq!(gray(GrayscaleMethod::Average), 60);
+        assert_eq!(gray(GrayscaleMethod::Max), 90);
+        assert_eq!(gray(GrayscaleMethod::Luminosity709), 56);
+        assert_eq!(gray(GrayscaleMethod::Luminosity601), 54);
+        assert_eq!(gray(GrayscaleMethod::Custom([0., 0., 1.])), 90);
+        assert_eq!(gray(GrayscaleMethod::Custom([4., 4., 4.])), 255);
+
+        let img = ImageBuffer2D::generate(3, 1, |(x, _)| Rgb::new([x as f32, 0.5, 0.25]));
+        let gray = to_grayscale(&img, GrayscaleMethod::Max);
+        assert!(gray.iter().map(|p| p[0]).eq(vec![0.5, 1., 2.]));
+    }
+
+    #[test]
+    fn test_srgb_linear() {
+        let gray = ImageBuffer2D::solid(1, 1, Luma::new([188f64 / 255.]));
+        assert!((srgb_to_linear(&gray).get_pixel(0, 0)[0] - 0.5).abs() < 0.01);
+        assert_eq!(
+            srgb_to_linear(&ImageBuffer2D::solid(1, 1, Luma::new([188u8]))).get_pixel(0, 0),
+            &Luma::new([128])
+        );
+        // The linear segment near black.
+        let dark = ImageBuffer2D::solid(1, 1, Luma::new([0.02f64]));
+        assert!((srgb_to_linear(&dark).get_pixel(0, 0)[0] - 0.02 / 12.92).abs() < 1e-12);
+
+        let img = ImageBuffer2D::generate(16, 16, |(x, y)| {
+            Rgb::new([(x + 16 * y) as f64 / 255., x as f64 / 15., y as f64 / 15.])
+        });
+        assert!(linear_to_srgb(&srgb_to_linear(&img)).approx_eq(&img, 1e-9));
+        let img = ImageBuffer2D::generate(16, 16, |(x, y)| Luma::new([(x + 16 * y) as u16 * 257]));
+        // Dark values are quantized more coarsely in linear light, the slope of the linear segment being 1 / 12.92.
+        let back = linear_to_srgb(&srgb_to_linear(&img));
+        for (a, b) in img.iter().zip(back.iter()) {
+            assert!((i32::from(a[0]) - i32::from(b[0])).abs() <= 7);
+        }
+    }
+
+    #[test]
+    fn test_ycbcr() {
+        let img = ImageBuffer2D::generate(16, 16, |(x, y)| {
+            Rgb::new([(x * 16) as u8, (y * 16) as u8, ((x + y) * 8) as u8])
+        });
+        let round_trip = ycbcr_to_rgb(&rgb_to_ycbcr(&img));
+        for (p1, p2) in img.iter().zip(round_trip.iter()) {
+            for (c1, c2) in p1.data.iter().zip(p2.data.iter()) {
+                assert!((i32::from(*c1) - i32::from(*c2)).abs() <= 1);
+            }
+        }
+
+        let gray = ImageBuffer2D::generate(5, 3, |(x, y)| Rgb::new([(x * 50 + y) as u8; 3]));
+        let ycbcr = rgb_to_ycbcr(&gray);
+        assert!(ycbcr.iter().all(|p| p[1] == 128 && p[2] == 128));
+
+        let (luma, cb, cr) = subsample_420(&ycbcr);
+        assert_eq!(luma.dimensions(), (5, 3));
+        assert_eq!(cb.dimensions(), (3, 2));
+        assert_eq!(cr.dimensions(), (3, 2));
+        assert!(cb.iter().chain(cr.iter()).all(|p| p[0] == 128));
+        assert_eq!(upsample_420(&luma, &cb, &cr), ycbcr);
+
+        let img = ImageBuffer2D::generate(3, 1, |(x, _)| {
+            YCbCr::new([0u8, [10, 21, 40][x as usize], 0])
+        });
+        let (_, cb, _) = subsample_420(&img);
+        assert_eq!(cb.get_pixel(0, 0), &Luma::new([16]));
+        assert_eq!(cb.get_pixel(1, 0), &Luma::new([40]));
+    }
+}
@@ -0,0 +1,134 @@
+//! Contains image comparison functions.
+
+use core::{Image2D, ImageBuffer2D, Pixel};
+use helper::generic::full_intensity;
+
+use failure::Error;
+use num_traits::{cast, Bounded, Zero};
+
+// Check that two images have the same dimensions.
+fn check_dimensions<P>(a: &Image2D<P>, b: &Image2D<P>) -> Result<(), Error>
+where
+    P: Pixel,
+{
+    ensure!(
+        a.dimensions() == b.dimensions(),
+        "Image dimensions do not match: {:?} and {:?}",
+        a.dimensions(),
+        b.dimensions()
+    );
+    Ok(())
+}
+
+/// Compute the absolute difference `|a - b|` of two images, channel by channel. The difference is computed on `f64`
+/// so unsigned subpixels don't wrap around, and saturates to the maximum value of signed subpixel types.
+///
+/// **Error**: if the images have different dimensions.
+pub fn abs_diff<P>(a: &Image2D<P>, b: &Image2D<P>) -> Result<ImageBuffer2D<P>, Error>
+where
+    P: Pixel,
+{
+    check_dimensions(a, b)?;
+    let (w, h) = a.dimensions();
+    Ok(ImageBuffer2D::generate(w, h, |(x, y)| {
+        let mut out = P::zero();
+        let channels = a
+            .get_pixel(x, y)
+            .channels()
+            .iter()
+            .zip(b.get_pixel(x, y).channels());
+        for (dst, (ca, cb)) in out.channels_mut().iter_mut().zip(channels) {
+            let d = cast::<P::Subpixel, f64>(*ca).unwrap_or(0.)
+                - cast::<P::Subpixel, f64>(*cb).unwrap_or(0.);
+            *dst = cast(d.abs()).unwrap_or_else(P::Subpixel::max_value);
+        }
+        out
+    }))
+}
+
+/// Compute the mean squared error between two images, averaged over every channel of every pixel. The mean squared
+/// error of empty images is 0.
+///
+/// **Error**: if the images have different dimensions.
+pub fn mse<P>(a: &Image2D<P>, b: &Image2D<P>) -> Result<f64, Error>
+where
+    P: Pixel,
+{
+    check_dimensions(a, b)?;
+    let (w, h) = a.dimensions();
+    let n = f64::from(w) * f64::from(h) * f64::from(P::N_CHANNELS);
+    if n == 0. {
+        return Ok(0.);
+    }
+    let mut sum = 0.;
+    for (pa, pb) in a.iter().zip(b.iter()) {
+        for (ca, cb) in pa.channels().iter().zip(pb.channels()) {
+            let d = cast::<P::Subpixel, f64>(*ca).unwrap_or(0.)
+                - cast::<P::Subpixel, f64>(*cb).unwrap_or(0.);
+            sum += d * d;
+        }
+    }
+    Ok(sum / n)
+}
+
+/// Compute the peak signal to noise ratio between two images in decibels. The peak value is the maximum value of the
+/// subpixel type for integer types, and 1 for floating point types. Identical images have an infinite PSNR.
+///
+/// **Error**: if the images have different dimensions.
+pub fn psnr<P>(a: &Image2D<P>, b: &Image2D<P>) -> Result<f64, Error>
+where
+    P: Pixel,
+{
+    let mse = mse(a, b)?;
+    let peak = full_intensity::<P::Subpixel>();
+    Ok(if mse.is_zero() {
+        ::std::f64::INFINITY
+    } else {
+        10. * (peak * peak / mse).log10()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Luma, Rgb};
+
+    #[test]
+    fn test_abs_diff() {
+        let a =
+            ImageBuffer2D::generate(5, 4, |(x, y)| Rgb::new([(x * 50) as u8, (y * 60) as u8, 3]));
+        assert_eq!(abs_diff(&a, &a).unwrap(), ImageBuffer2D::new(5, 4));
+        let b = ImageBuffer2D::solid(5, 4, Rgb::new([100u8, 100, 100]));
+        let d = abs_diff(&a, &b).unwrap();
+        assert_eq!(d, abs_diff(&b, &a).unwrap());
+        assert_eq!(d.get_pixel(0, 0), &Rgb::new([100, 100, 97]));
+        assert_eq!(d.get_pixel(4, 3), &Rgb::new([100, 80, 97]));
+
+        let a = ImageBuffer2D::solid(2, 2, Luma::new([-100i8]));
+        let b = ImageBuffer2D::solid(2, 2, Luma::new([100i8]));
+        assert_eq!(
+            abs_diff(&a, &b).unwrap(),
+            ImageBuffer2D::solid(2, 2, Luma::new([127]))
+        );
+
+        assert!(abs_diff(&a, &ImageBuffer2D::new(2, 3)).is_err());
+    }
+
+    #[test]
+    fn test_mse_psnr() {
+        let a = ImageBuffer2D::generate(8, 8, |(x, y)| Luma::new([(x * 30 + y) as u8]));
+        assert_eq!(mse(&a, &a).unwrap(), 0.);
+        assert_eq!(psnr(&a, &a).unwrap(), ::std::f64::INFINITY);
+
+        let b = a.map(|p| Luma::new([p[0] + 5]));
+        assert_eq!(mse(&a, &b).unwrap(), 25.);
+        assert!((psnr(&a, &b).unwrap() - 34.151).abs() < 1e-3);
+
+        let a = ImageBuffer2D::solid(3, 3, Rgb::new([0.5f32, 0.5, 0.5]));
+        let b = ImageBuffer2D::solid(3, 3, Rgb::new([0.6f32, 0.4, 0.5]));
+        assert!((psnr(&a, &b).unwrap() - 21.761).abs() < 1e-3);
+
+        assert!(mse(&a, &ImageBuffer2D::new(3, 1)).is_err());
+        assert!(psnr(&a, &ImageBuffer2D::new(3, 1)).is_err());
+    }
+}
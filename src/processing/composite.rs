@@ -0,0 +1,175 @@
+//! Contains image compositing functions.
+
+use core::{Image2D, ImageBuffer2D, LumaA, Pixel, Primitive, RgbA};
+use helper::generic::{from_f64_clamped, full_intensity, source_over};
+
+use failure::Error;
+use num_traits::cast;
+
+/// Composite `top` over `bottom` with the Porter-Duff "source over" operator. Pixel values are normalized to the
+/// `[0, 1]` range from the `[0, max]` range of the subpixel type for integer types, and are expected to be already
+/// normalized for floating point types. Color channels are not premultiplied by alpha.
+///
+/// **Error**: if the images have different dimensions.
+pub fn alpha_over<P>(
+    top: &Image2D<RgbA<P>>,
+    bottom: &Image2D<RgbA<P>>,
+) -> Result<ImageBuffer2D<RgbA<P>>, Error>
+where
+    P: Primitive,
+{
+    ensure!(
+        top.dimensions() == bottom.dimensions(),
+        "Image dimensions do not match: {:?} and {:?}",
+        top.dimensions(),
+        bottom.dimensions()
+    );
+    let max = full_intensity::<P>();
+    let norm = |v: P| cast::<P, f64>(v).unwrap_or(0.) / max;
+    let denorm = |v: f64| from_f64_clamped(v.max(0.).min(1.) * max);
+    let (w, h) = top.dimensions();
+    Ok(ImageBuffer2D::generate(w, h, |(x, y)| {
+        let (t, b) = (top.get_pixel(x, y), bottom.get_pixel(x, y));
+        let (mut tn, mut bn, mut out) = ([0.; 4], [0.; 4], [0.; 4]);
+        for c in 0..4 {
+            tn[c] = norm(t.data[c]);
+            bn[c] = norm(b.data[c]);
+        }
+        source_over(&tn, &bn, &mut out);
+        RgbA::new([
+            denorm(out[0]),
+            denorm(out[1]),
+            denorm(out[2]),
+            denorm(out[3]),
+        ])
+    }))
+}
+
+// Multiply or divide the color channels of an image by its alpha channel, which is the last channel of the pixels.
+fn scale_by_alpha<P>(img: &Image2D<P>, premultiply: bool) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let max = full_intensity::<P::Subpixel>();
+    let alpha_idx = P::N_CHANNELS as usize - 1;
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        let mut p = img.get_pixel(x, y).clone();
+        let alpha = cast::<P::Subpixel, f64>(p.channels()[alpha_idx]).unwrap_or(0.) / max;
+        for c in &mut p.channels_mut()[..alpha_idx] {
+            let v = cast::<P::Subpixel, f64>(*c).unwrap_or(0.);
+            let v = if alpha <= 0. {
+                0.
+            } else if premultiply {
+                v * alpha
+            } else {
+                (v / alpha).min(max)
+            };
+            *c = from_f64_clamped(v);
+        }
+        p
+    })
+}
+
+/// Premultiply the color channels of an image by its alpha channel, normalized to `[0, 1]` from the `[0, max]` range
+/// of the subpixel type for integer types. The color channels of fully transparent pixels become 0.
+pub fn premultiply_alpha<P>(img: &Image2D<RgbA<P>>) -> ImageBuffer2D<RgbA<P>>
+where
+    P: Primitive,
+{
+    scale_by_alpha(img, true)
+}
+
+/// Undo `premultiply_alpha`, dividing the color channels of an image by its alpha channel. The color channels of
+/// fully transparent pixels stay 0. Integer images lose precision in the round trip for small alpha values.
+pub fn unpremultiply_alpha<P>(img: &Image2D<RgbA<P>>) -> ImageBuffer2D<RgbA<P>>
+where
+    P: Primitive,
+{
+    scale_by_alpha(img, false)
+}
+
+/// `LumaA` version of `premultiply_alpha`.
+pub fn premultiply_luma_alpha<P>(img: &Image2D<LumaA<P>>) -> ImageBuffer2D<LumaA<P>>
+where
+    P: Primitive,
+{
+    scale_by_alpha(img, true)
+}
+
+/// `LumaA` version of `unpremultiply_alpha`.
+pub fn unpremultiply_luma_alpha<P>(img: &Image2D<LumaA<P>>) -> ImageBuffer2D<LumaA<P>>
+where
+    P: Primitive,
+{
+    scale_by_alpha(img, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alpha_over() {
+        let top = ImageBuffer2D::solid(3, 2, RgbA::new([255u8, 0, 0, 128]));
+        let bottom = ImageBuffer2D::solid(3, 2, RgbA::new([0u8, 0, 255, 255]));
+        let out = alpha_over(&top, &bottom).unwrap();
+        assert!(out.iter().all(|p| *p == RgbA::new([128, 0, 127, 255])));
+
+        let clear = ImageBuffer2D::generate(3, 2, |(x, y)| RgbA::new([x as u8, y as u8, 9, 0]));
+        assert_eq!(alpha_over(&clear, &bottom).unwrap(), bottom);
+        let opaque = ImageBuffer2D::generate(3, 2, |(x, y)| RgbA::new([x as u8, y as u8, 9, 255]));
+        assert_eq!(alpha_over(&opaque, &bottom).unwrap(), opaque);
+        assert_eq!(
+            alpha_over(&clear, &clear).unwrap(),
+            ImageBuffer2D::new(3, 2)
+        );
+
+        let top = ImageBuffer2D::solid(1, 1, RgbA::new([1f32, 1., 1., 0.5]));
+        let bottom = ImageBuffer2D::solid(1, 1, RgbA::new([0f32, 0., 0., 0.5]));
+        let out = alpha_over(&top, &bottom).unwrap();
+        let p = out.get_pixel(0, 0);
+        assert_eq!(p[3], 0.75);
+        assert!((p[0] - 2. / 3.).abs() < 1e-6);
+
+        assert!(alpha_over(&top, &ImageBuffer2D::new(2, 1)).is_err());
+    }
+
+    #[test]
+    fn test_premultiply_alpha() {
+        let img = ImageBuffer2D::generate(16, 16, |(x, y)| {
+            RgbA::new([
+                (x * 16) as u8,
+                (y * 16) as u8,
+                200,
+                (x * 8 + y * 7 + 15) as u8,
+            ])
+        });
+        let premultiplied = premultiply_alpha(&img);
+        assert_eq!(
+            premultiplied.get_pixel(15, 15),
+            &RgbA::new([226, 226, 188, 240])
+        );
+        let back = unpremultiply_alpha(&premultiplied);
+        // The error introduced by rounding the premultiplied value is amplified by 255 / alpha.
+        for (a, b) in img.iter().zip(back.iter()) {
+            let tolerance = (255. / f64::from(a[3])).ceil() as i32;
+            for c in 0..4 {
+                assert!((i32::from(a.data[c]) - i32::from(b.data[c])).abs() <= tolerance);
+            }
+        }
+
+        let img = ImageBuffer2D::generate(4, 4, |(x, y)| {
+            LumaA::new([x as f64 / 4., 0.1 + y as f64 / 4.])
+        });
+        let back = unpremultiply_luma_alpha(&premultiply_luma_alpha(&img));
+        assert!(back.approx_eq(&img, 1e-12));
+
+        let clear = ImageBuffer2D::solid(2, 2, RgbA::new([10u8, 20, 30, 0]));
+        assert_eq!(premultiply_alpha(&clear), ImageBuffer2D::new(2, 2));
+        assert_eq!(
+            unpremultiply_alpha(&ImageBuffer2D::<RgbA<u8>>::new(2, 2)),
+            ImageBuffer2D::new(2, 2)
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Contains distance transform functions.
+
+use core::{Image2D, ImageBuffer2D, Luma};
+
+use std::f32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Enumerate the metrics used to measure the distance between pixels.
+pub enum DistanceMetric {
+    /// Straight line distance.
+    Euclidean,
+    /// Sum of the absolute coordinate differences, i.e. the length of the shortest 4-connected path.
+    Manhattan,
+    /// Maximum of the absolute coordinate differences, i.e. the length of the shortest 8-connected path.
+    Chebyshev,
+}
+
+// Stand-in for an infinite squared distance, large enough to never be reached in an actual image while keeping the
+// arithmetic of the euclidean transform finite.
+const FAR: f64 = 1e20;
+
+// Compute the exact 1D squared euclidean distance transform of a sampled function, as described by Felzenszwalb and
+// Huttenlocher in "Distance Transforms of Sampled Functions".
+fn edt_1d(f: &[f64], out: &mut [f64]) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+    let mut v = vec![0usize; n];
+    let mut z = vec![0f64; n + 1];
+    let mut k = 0;
+    z[0] = -FAR * FAR;
+    z[1] = FAR * FAR;
+    for q in 1..n {
+        let parabola = |p: usize| f[p] + (p * p) as f64;
+        let mut s;
+        loop {
+            let p = v[k];
+            s = (parabola(q) - parabola(p)) / (2 * (q - p)) as f64;
+            if s <= z[k] {
+                k -= 1;
+            } else {
+                break;
+            }
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = FAR * FAR;
+    }
+    k = 0;
+    for (q, o) in out.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let d = q as f64 - v[k] as f64;
+        *o = d * d + f[v[k]];
+    }
+}
+
+fn euclidean(img: &Image2D<Luma<u8>>) -> Vec<f64> {
+    let (w, h) = img.dimensions();
+    let (w, h) = (w as usize, h as usize);
+    if w == 0 || h == 0 {
+        return Vec::new();
+    }
+    let mut dist: Vec<f64> = img
+        .iter()
+        .map(|p| if p[0] == 0 { 0. } else { FAR })
+        .collect();
+    let (mut f, mut out) = (vec![0.; h], vec![0.; h]);
+    for x in 0..w {
+        for y in 0..h {
+            f[y] = dist[y * w + x];
+        }
+        edt_1d(&f, &mut out);
+        for y in 0..h {
+            dist[y * w + x] = out[y];
+        }
+    }
+    let mut out = vec![0.; w];
+    for row in dist.chunks_mut(w) {
+        edt_1d(row, &mut out);
+        row.copy_from_slice(&out);
+    }
+    dist.iter()
+        .map(|&d| if d >= FAR { f64::INFINITY } else { d.sqrt() })
+        .collect()
+}
+
+// Exact two-pass chamfer transform, the neighbors at distance 1 being either the 4 or 8 adjacent pixels.
+fn chamfer(img: &Image2D<Luma<u8>>, diagonals: bool) -> Vec<f64> {
+    let (w, h) = img.dimensions();
+    let (w, h) = (w as usize, h as usize);
+    let mut dist: Vec<f64> = img
+        .iter()
+        .map(|p| if p[0] == 0 { 0. } else { f64::INFINITY })
+        .collect();
+    // Forward pass looks at the already visited neighbors above and on the left, backward pass at the ones below and
+    // on the right.
+    for y in 0..h {
+        for x in 0..w {
+            let mut d = dist[y * w + x];
+            if x > 0 {
+                d = d.min(dist[y * w + x - 1] + 1.);
+            }
+            if y > 0 {
+                d = d.min(dist[(y - 1) * w + x] + 1.);
+                if diagonals && x > 0 {
+                    d = d.min(dist[(y - 1) * w + x - 1] + 1.);
+                }
+                if diagonals && x + 1 < w {
+                    d = d.min(dist[(y - 1) * w + x + 1] + 1.);
+                }
+            }
+            dist[y * w + x] = d;
+        }
+    }
+    for y in (0..h).rev() {
+        for x in (0..w).rev() {
+            let mut d = dist[y * w + x];
+            if x + 1 < w {
+                d = d.min(dist[y * w + x + 1] + 1.);
+            }
+            if y + 1 < h {
+                d = d.min(dist[(y + 1) * w + x] + 1.);
+                if diagonals && x + 1 < w {
+                    d = d.min(dist[(y + 1) * w + x + 1] + 1.);
+                }
+                if diagonals && x > 0 {
+                    d = d.min(dist[(y + 1) * w + x - 1] + 1.);
+                }
+            }
+            dist[y * w + x] = d;
+        }
+    }
+    dist
+}
+
+/// Compute the distance transform of a binary image, in which non-zero pixels are foreground. Each foreground pixel
+/// is set to its distance to the nearest background pixel, background pixels being set to 0. If the image has no
+/// background pixel, every pixel is set to infinity.
+///
+/// The euclidean transform is computed exactly with the algorithm of Felzenszwalb and Huttenlocher, the Manhattan and
+/// Chebyshev ones with exact two-pass chamfer transforms.
+pub fn distance_transform(
+    img: &Image2D<Luma<u8>>,
+    metric: DistanceMetric,
+) -> ImageBuffer2D<Luma<f32>> {
+    let dist = match metric {
+        DistanceMetric::Euclidean => euclidean(img),
+        DistanceMetric::Manhattan => chamfer(img, false),
+        DistanceMetric::Chebyshev => chamfer(img, true),
+    };
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::from_vec(w, h, dist.iter().map(|&d| Luma::new([d as f32])).collect()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(img: &Image2D<Luma<u8>>, metric: DistanceMetric) -> ImageBuffer2D<Luma<f32>> {
+        let background: Vec<(i64, i64)> = img
+            .enumerate_pixels()
+            .filter(|(_, p)| p[0] == 0)
+            .map(|((x, y), _)| (i64::from(x), i64::from(y)))
+            .collect();
+        let (w, h) = img.dimensions();
+        ImageBuffer2D::generate(w, h, |(x, y)| {
+            let (x, y) = (i64::from(x), i64::from(y));
+            let d = background
+                .iter()
+                .map(|&(bx, by)| {
+                    let (dx, dy) = ((bx - x).abs() as f64, (by - y).abs() as f64);
+                    match metric {
+                        DistanceMetric::Euclidean => dx.hypot(dy),
+                        DistanceMetric::Manhattan => dx + dy,
+                        DistanceMetric::Chebyshev => dx.max(dy),
+                    }
+                })
+                .fold(f64::INFINITY, f64::min);
+            Luma::new([d as f32])
+        })
+    }
+
+    #[test]
+    fn test_single_pixel() {
+        let img = ImageBuffer2D::generate(5, 5, |(x, y)| Luma::new([(x == 2 && y == 2) as u8]));
+        for &metric in &[
+            DistanceMetric::Euclidean,
+            DistanceMetric::Manhattan,
+            DistanceMetric::Chebyshev,
+        ] {
+            let dist = distance_transform(&img, metric);
+            assert_eq!(dist.get_pixel(2, 2), &Luma::new([1.]));
+            assert_eq!(dist.iter().filter(|p| p[0] != 0.).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        let img = ImageBuffer2D::generate(23, 17, |(x, y)| {
+            Luma::new([((x * 7 + y * 13) % 11 != 0 && (x + 2 * y) % 29 != 3) as u8])
+        });
+        for &metric in &[
+            DistanceMetric::Euclidean,
+            DistanceMetric::Manhattan,
+            DistanceMetric::Chebyshev,
+        ] {
+            let expected = brute_force(&img, metric);
+            let dist = distance_transform(&img, metric);
+            for (a, b) in dist.iter().zip(expected.iter()) {
+                assert!(
+                    (a[0] - b[0]).abs() < 1e-5,
+                    "{:?}: {} != {}",
+                    metric,
+                    a[0],
+                    b[0]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_background() {
+        let img = ImageBuffer2D::solid(4, 3, Luma::new([1u8]));
+        for &metric in &[DistanceMetric::Euclidean, DistanceMetric::Manhattan] {
+            let dist = distance_transform(&img, metric);
+            assert!(dist.iter().all(|p| p[0] == f32::INFINITY));
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        for &(w, h) in &[(0, 3), (3, 0), (0, 0)] {
+            let img = ImageBuffer2D::<Luma<u8>>::new(w, h);
+            for &metric in &[
+                DistanceMetric::Euclidean,
+                DistanceMetric::Manhattan,
+                DistanceMetric::Chebyshev,
+            ] {
+                assert_eq!(distance_transform(&img, metric).dimensions(), (w, h));
+            }
+        }
+    }
+}
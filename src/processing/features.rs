@@ -0,0 +1,193 @@
+//! Contains feature detection functions.
+
+use core::padding::Padding;
+use core::{Image2D, ImageBuffer2D, Luma, Primitive, Rect};
+use processing::gradient::sobel_gradients;
+
+use std::cmp::min;
+
+/// Return the coordinates of the pixels whose value is above `threshold` and strictly greater than the value of every
+/// other pixel in the `(2 * radius + 1)` wide square window centered on them. The window is cropped at the image
+/// borders. Coordinates are returned in scanline order.
+pub fn nonmax_suppression<P>(img: &Image2D<Luma<P>>, threshold: P, radius: u32) -> Vec<(u32, u32)>
+where
+    P: Primitive,
+{
+    let (w, h) = img.dimensions();
+    let mut maxima = Vec::new();
+    for y in 0..h {
+        for x in 0..w {
+            let val = img.get_pixel(x, y)[0];
+            if val <= threshold {
+                continue;
+            }
+            let (left, top) = (x.saturating_sub(radius), y.saturating_sub(radius));
+            let right = min(x.saturating_add(radius), w - 1);
+            let bottom = min(y.saturating_add(radius), h - 1);
+            let window = Rect::new(left, top, right - left + 1, bottom - top + 1);
+            let is_max = img.rect_iter(window).enumerate().all(|(i, p)| {
+                let (wx, wy) = (
+                    left + i as u32 % window.width(),
+                    top + i as u32 / window.width(),
+                );
+                (wx == x && wy == y) || p[0] < val
+            });
+            if is_max {
+                maxima.push((x, y));
+            }
+        }
+    }
+    maxima
+}
+
+/// Compute the Harris corner response of an image. The structure tensor is computed from the Sobel derivatives of
+/// the image and summed over a 3x3 window, `k` is the sensitivity parameter of the detector, usually around `0.04`.
+pub fn harris_response<P>(img: &Image2D<Luma<P>>, k: f64) -> ImageBuffer2D<Luma<f64>>
+where
+    P: Primitive,
+{
+    let (w, h) = img.dimensions();
+    let (gx, gy) = sobel_gradients(img, Padding::Replicate);
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        let (left, top) = (x.saturating_sub(1), y.saturating_sub(1));
+        let window = Rect::new(
+            left,
+            top,
+            min(x + 1, w - 1) - left + 1,
+            min(y + 1, h - 1) - top + 1,
+        );
+        let (mut sxx, mut syy, mut sxy) = (0., 0., 0.);
+        for (dx, dy) in gx.rect_iter(window).zip(gy.rect_iter(window)) {
+            sxx += dx[0] * dx[0];
+            syy += dy[0] * dy[0];
+            sxy += dx[0] * dy[0];
+        }
+        let trace = sxx + syy;
+        Luma::new([sxx * syy - sxy * sxy - k * trace * trace])
+    })
+}
+
+/// Detect corners in an image with the Harris corner detector. Return the coordinates of the local maxima of the
+/// Harris response (see `harris_response`, with `k = 0.04`) above `threshold`.
+pub fn harris_corners<P>(img: &Image2D<Luma<P>>, threshold: f64) -> Vec<(u32, u32)>
+where
+    P: Primitive,
+{
+    let response = harris_response(img, 0.04);
+    nonmax_suppression(&response, threshold, 1)
+}
+
+/// Refine the location of corners to sub-pixel precision. A quadratic is fitted to the 3x3 neighborhood of each corner
+/// in the detector `response` (e.g. from `harris_response`), and the corner is moved to the peak of the quadratic.
+/// Corners on the image borders, and corners whose neighborhood isn't well approximated by a peak within one pixel,
+/// keep their integer coordinates.
+pub fn refine_corners(response: &Image2D<Luma<f64>>, corners: &[(u32, u32)]) -> Vec<(f32, f32)> {
+    let (w, h) = response.dimensions();
+    corners
+        .iter()
+        .map(|&(x, y)| {
+            let unrefined = (x as f32, y as f32);
+            if x == 0 || y == 0 || x + 1 >= w || y + 1 >= h {
+                return unrefined;
+            }
+            let f = |dx: i32, dy: i32| {
+                response.get_pixel((x as i32 + dx) as u32, (y as i32 + dy) as u32)[0]
+            };
+            // Gradient and hessian of the quadratic from finite differences
+            let (gx, gy) = ((f(1, 0) - f(-1, 0)) / 2., (f(0, 1) - f(0, -1)) / 2.);
+            let hxx = f(1, 0) - 2. * f(0, 0) + f(-1, 0);
+            let hyy = f(0, 1) - 2. * f(0, 0) + f(0, -1);
+            let hxy = (f(1, 1) - f(1, -1) - f(-1, 1) + f(-1, -1)) / 4.;
+            let det = hxx * hyy - hxy * hxy;
+            if det == 0. {
+                return unrefined;
+            }
+            let ox = -(hyy * gx - hxy * gy) / det;
+            let oy = -(hxx * gy - hxy * gx) / det;
+            if ox.abs() > 1. || oy.abs() > 1. {
+                return unrefined;
+            }
+            ((f64::from(x) + ox) as f32, (f64::from(y) + oy) as f32)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonmax_suppression() {
+        let peaks = [
+            (2, 3, 10u8),
+            (9, 3, 20),
+            (5, 8, 30),
+            (0, 0, 40),
+            (11, 9, 50),
+        ];
+        let img = ImageBuffer2D::generate(12, 10, |(x, y)| {
+            match peaks.iter().find(|&&(px, py, _)| px == x && py == y) {
+                Some(&(_, _, v)) => Luma::new([v]),
+                None => Luma::new([(x + y) as u8 % 3]),
+            }
+        });
+        assert_eq!(
+            nonmax_suppression(&img, 5, 2),
+            vec![(0, 0), (2, 3), (9, 3), (5, 8), (11, 9)]
+        );
+        assert_eq!(
+            nonmax_suppression(&img, 25, 2),
+            vec![(0, 0), (5, 8), (11, 9)]
+        );
+        // Peaks within the radius of a higher one are suppressed
+        assert_eq!(nonmax_suppression(&img, 5, 7), vec![(0, 0), (11, 9)]);
+    }
+
+    #[test]
+    fn test_nonmax_suppression_plateau() {
+        let img = ImageBuffer2D::generate(5, 5, |(x, y)| {
+            Luma::new([if x == 2 && y < 2 { 9u8 } else { 0 }])
+        });
+        assert!(nonmax_suppression(&img, 0, 1).is_empty());
+    }
+
+    #[test]
+    fn test_harris_corners() {
+        // White square on a black background
+        let img = ImageBuffer2D::generate(20, 20, |(x, y)| {
+            Luma::new([if x >= 5 && x < 15 && y >= 5 && y < 15 {
+                255u8
+            } else {
+                0
+            }])
+        });
+        let corners = harris_corners(&img, 1e9);
+        assert_eq!(corners.len(), 4);
+        for &(cx, cy) in &[(5, 5), (14, 5), (5, 14), (14, 14)] {
+            assert!(corners
+                .iter()
+                .any(|&(x, y)| (x as i32 - cx).abs() <= 1 && (y as i32 - cy).abs() <= 1));
+        }
+    }
+
+    #[test]
+    fn test_refine_corners() {
+        let (px, py) = (7.3, 4.8);
+        let response = ImageBuffer2D::generate(12, 10, |(x, y)| {
+            let (dx, dy) = (f64::from(x) - px, f64::from(y) - py);
+            Luma::new([100. - dx * dx - 0.5 * dx * dy - 2. * dy * dy])
+        });
+        let corners = nonmax_suppression(&response, 0., 1);
+        assert_eq!(corners, vec![(7, 5)]);
+        let refined = refine_corners(&response, &corners);
+        assert!((refined[0].0 - 7.3).abs() < 0.05);
+        assert!((refined[0].1 - 4.8).abs() < 0.05);
+
+        // Border corners and flat neighborhoods are not refined
+        let flat = ImageBuffer2D::solid(5, 5, Luma::new([1.]));
+        assert_eq!(
+            refine_corners(&flat, &[(0, 2), (2, 2)]),
+            vec![(0., 2.), (2., 2.)]
+        );
+    }
+}
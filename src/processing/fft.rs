@@ -0,0 +1,146 @@
+//! Contains FFT based image processing functions.
+
+use core::padding::{pad, Padding};
+use core::{Image2D, Image2DMut, ImageBuffer2D, Pixel};
+use helper::generic::from_f64_clamped;
+use processing::kernel::Kernel;
+
+use num_traits::{cast, Zero};
+use rustfft::num_complex::Complex;
+use rustfft::FFTplanner;
+
+// In place 2D FFT of a row-major `w x h` buffer. `scratch` must have the same length as `data`. The inverse
+// transform is not normalized.
+fn fft_2d(
+    planner: &mut FFTplanner<f64>,
+    data: &mut [Complex<f64>],
+    scratch: &mut [Complex<f64>],
+    w: usize,
+    h: usize,
+) {
+    planner.plan_fft(w).process_multi(data, scratch);
+    // Transpose so that the columns are contiguous, transform them and transpose back.
+    for y in 0..h {
+        for x in 0..w {
+            data[x * h + y] = scratch[y * w + x];
+        }
+    }
+    planner.plan_fft(h).process_multi(data, scratch);
+    for x in 0..w {
+        for y in 0..h {
+            data[y * w + x] = scratch[x * h + y];
+        }
+    }
+}
+
+/// Convolve an image with a kernel by multiplication in the frequency domain. The result is the same as with
+/// `Kernel::convolve`, but the cost does not depend on the kernel size, which makes it much faster for large kernels.
+/// Borders are handled with the specified padding method. Results are rounded for integer types and clamped to the
+/// range of the subpixel type.
+pub fn convolve_fft<P>(img: &Image2D<P>, kernel: &Kernel<f64>, padding: Padding) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return ImageBuffer2D::new(w, h);
+    }
+    let r = kernel.radius() as usize;
+    let padded = pad(img, r as u32, padding);
+    // Only the pixels at least `r` away from the padded image borders are kept, so that the wrap around of the
+    // circular convolution does not need any additional padding.
+    let (n, m) = (w as usize + 2 * r, h as usize + 2 * r);
+    let mut forward = FFTplanner::new(false);
+    let mut inverse = FFTplanner::new(true);
+    let mut scratch = vec![Complex::zero(); n * m];
+
+    // Lay out the kernel with its center at the origin, wrapping around the negative offsets.
+    let d = 2 * r + 1;
+    let mut kernel_freq = vec![Complex::zero(); n * m];
+    for (i, e) in kernel.elems().iter().enumerate() {
+        let (x, y) = ((n + i % d - r) % n, (m + i / d - r) % m);
+        kernel_freq[y * n + x] = Complex::new(*e, 0.);
+    }
+    fft_2d(&mut forward, &mut kernel_freq, &mut scratch, n, m);
+
+    let norm = (n * m) as f64;
+    let mut out = ImageBuffer2D::<P>::new(w, h);
+    let mut plane = vec![Complex::zero(); n * m];
+    for c in 0..P::N_CHANNELS as usize {
+        for v in &mut plane {
+            *v = Complex::zero();
+        }
+        for ((x, y), p) in padded.enumerate_pixels() {
+            let v = cast::<P::Subpixel, f64>(p.channels()[c]).unwrap_or(0.);
+            plane[y as usize * n + x as usize] = Complex::new(v, 0.);
+        }
+        fft_2d(&mut forward, &mut plane, &mut scratch, n, m);
+        for (v, k) in plane.iter_mut().zip(kernel_freq.iter()) {
+            *v *= *k;
+        }
+        fft_2d(&mut inverse, &mut plane, &mut scratch, n, m);
+        for ((x, y), p) in out.enumerate_pixels_mut() {
+            let idx = (y as usize + r) * n + x as usize + r;
+            p.channels_mut()[c] = from_f64_clamped(plane[idx].re / norm);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Luma, Rect, Rgb};
+
+    #[test]
+    fn test_fft_2d_roundtrip() {
+        let (w, h) = (6, 5);
+        let v: Vec<_> = (0..w * h)
+            .map(|i| Complex::new((i * 7 % 5) as f64, (i % 3) as f64))
+            .collect();
+        let mut f = v.clone();
+        let mut scratch = vec![Complex::zero(); w * h];
+        fft_2d(&mut FFTplanner::new(false), &mut f, &mut scratch, w, h);
+        // The first coefficient is the sum of the input.
+        let sum = v.iter().fold(Complex::zero(), |acc, c| acc + c);
+        assert!((f[0] - sum).norm() < 1e-9);
+        fft_2d(&mut FFTplanner::new(true), &mut f, &mut scratch, w, h);
+        for (a, b) in f.iter().zip(v.iter()) {
+            assert!((a / (w * h) as f64 - b).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_convolve_fft() {
+        let img = ImageBuffer2D::generate(37, 22, |(x, y)| {
+            Luma::new([((x * 7 + y * 13) % 17) as f64 / 17.])
+        });
+        // Asymmetric kernel, to check that the orientation matches.
+        let elems: Vec<f64> = (0..25).map(|i| f64::from(i % 7) - 2.5).collect();
+        let kernel = Kernel::new(elems, 2).unwrap();
+        let fft = convolve_fft(&img, &kernel, Padding::Zeros);
+        assert_eq!(fft.dimensions(), (37, 22));
+        assert!(fft.approx_eq(&kernel.convolve_raw::<_, f64>(&img), 1e-9));
+
+        let kernel = Kernel::gaussian(3., 9);
+        let inner = img.rect().inset(9).unwrap();
+        let direct = kernel.convolve(&img);
+        for padding in &[Padding::Zeros, Padding::Replicate, Padding::Mirror] {
+            let fft = convolve_fft(&img, &kernel, *padding);
+            assert!(fft
+                .sub_image(inner)
+                .approx_eq(&direct.sub_image(inner), 1e-9));
+        }
+
+        let img = ImageBuffer2D::generate(9, 9, |(x, y)| {
+            Rgb::new([(x * 20) as u8, (y * 30) as u8, 255])
+        });
+        let fft = convolve_fft(&img, &Kernel::box_(1), Padding::Replicate);
+        assert_eq!(fft.get_pixel(4, 4), &Rgb::new([80, 120, 255]));
+        let sub = img.sub_image(Rect::new(2, 2, 3, 3));
+        assert_eq!(
+            convolve_fft(&sub, &Kernel::box_(3), Padding::Replicate).dimensions(),
+            (3, 3)
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Contains image filtering functions.
+
+use core::padding::{pad, Padding};
+use core::{Image2D, Image2DMut, ImageBuffer2D, Pixel, Rect, RectNeighborhood};
+use helper::generic::from_f64_clamped;
+use math;
+
+use num_traits::cast;
+
+use std::cmp::Ordering;
+
+// Return the radius and the normalized weights of a square gaussian window of standard deviation `sigma`, truncated
+// at 3 sigmas.
+fn gaussian_window(sigma: f64) -> (u32, Vec<f64>) {
+    let radius = (3. * sigma).ceil() as u32;
+    let r = i64::from(radius);
+    let mut weights = Vec::with_capacity(((2 * radius + 1) * (2 * radius + 1)) as usize);
+    for y in -r..=r {
+        for x in -r..=r {
+            weights.push(math::gaussian_2d(x as f64, y as f64, sigma));
+        }
+    }
+    let norm: f64 = weights.iter().sum();
+    for w in &mut weights {
+        *w /= norm;
+    }
+    (radius, weights)
+}
+
+// Compute the gaussian blur of every pixel of an image on f64 channels, and let `f` compute the output pixel from
+// the original pixel and its blurred channels.
+fn map_gaussian_blur<P, F>(img: &Image2D<P>, sigma: f64, padding: Padding, f: F) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+    F: Fn(&P, &[f64]) -> P,
+{
+    let (radius, weights) = gaussian_window(sigma);
+    let d = 2 * radius + 1;
+    let padded = pad(img, radius, padding);
+    let mut blurred = vec![0.; P::N_CHANNELS as usize];
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        for b in &mut blurred {
+            *b = 0.;
+        }
+        let window = Rect::new(x, y, d, d);
+        for (p, weight) in padded.rect_iter(window).zip(weights.iter()) {
+            for (b, c) in blurred.iter_mut().zip(p.channels()) {
+                *b += weight * cast::<P::Subpixel, f64>(*c).unwrap_or(0.);
+            }
+        }
+        f(img.get_pixel(x, y), &blurred)
+    })
+}
+
+/// Blur an image with a gaussian of standard deviation `sigma`, truncated at 3 sigmas. Borders are handled with the
+/// specified padding method. A `sigma` of 0 or less returns a copy of the image.
+pub fn gaussian_blur<P>(img: &Image2D<P>, sigma: f64, padding: Padding) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    if sigma <= 0. {
+        return img.to_owned();
+    }
+    map_gaussian_blur(img, sigma, padding, |_, blurred| {
+        let mut out = P::zero();
+        for (c, b) in out.channels_mut().iter_mut().zip(blurred) {
+            *c = from_f64_clamped(*b);
+        }
+        out
+    })
+}
+
+/// Sharpen an image by unsharp masking, computing `img + amount * (img - blur(img))` on every channel, where `blur` is
+/// a gaussian blur of standard deviation `sigma`. The result is clamped to the range of the subpixel type. Borders are
+/// handled with the specified padding method.
+pub fn unsharp_mask<P>(
+    img: &Image2D<P>,
+    sigma: f64,
+    amount: f64,
+    padding: Padding,
+) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    if amount == 0. || sigma <= 0. {
+        return img.to_owned();
+    }
+
+    map_gaussian_blur(img, sigma, padding, |p, blurred| {
+        let mut out = p.clone();
+        for (c, b) in out.channels_mut().iter_mut().zip(blurred) {
+            let v = cast::<P::Subpixel, f64>(*c).unwrap_or(0.);
+            *c = from_f64_clamped(v + amount * (v - b));
+        }
+        out
+    })
+}
+
+/// Apply a rank filter to an image: each channel of every pixel is set to the value at position `rank` in the sorted
+/// values of this channel over the `(2 * radius + 1)` wide square window centered on the pixel. Rank 0 gives a minimum
+/// filter and the last rank a maximum filter. `rank` is clamped to the window size. Borders are handled with the
+/// specified padding method.
+pub fn rank_filter<P>(
+    img: &Image2D<P>,
+    radius: u32,
+    rank: usize,
+    padding: Padding,
+) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let d = 2 * radius + 1;
+    let n = (d * d) as usize;
+    let rank = rank.min(n - 1);
+    let nbh = RectNeighborhood::new((d, d), (radius, radius)).unwrap();
+    let (w, h) = img.dimensions();
+    let mut out = ImageBuffer2D::<P>::new(w, h);
+    let mut values = Vec::with_capacity(n);
+    for ((x, y), window) in nbh.pad(img, padding).iter() {
+        let dst = out.get_pixel_mut(x, y);
+        for c in 0..P::N_CHANNELS as usize {
+            values.clear();
+            values.extend(window.iter().map(|p| p.channels()[c]));
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            dst.channels_mut()[c] = values[rank];
+        }
+    }
+    out
+}
+
+/// Apply a median filter to an image, i.e. a rank filter selecting the middle value of every window. See
+/// `rank_filter`.
+pub fn median_filter<P>(img: &Image2D<P>, radius: u32, padding: Padding) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let d = (2 * radius + 1) as usize;
+    rank_filter(img, radius, d * d / 2, padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Luma, Rgb};
+    use processing::morphology::dilate;
+
+    #[test]
+    fn test_unsharp_mask() {
+        // Blurred step edge
+        let img = ImageBuffer2D::generate(16, 4, |(x, _)| {
+            Luma::new([match x {
+                0..=6 => 50u8,
+                7 => 100,
+                8 => 150,
+                _ => 200,
+            }])
+        });
+        let sharpened = unsharp_mask(&img, 1., 1.5, Padding::Replicate);
+        let contrast = |img: &Image2D<Luma<u8>>| {
+            i32::from(img.get_pixel(8, 2)[0]) - i32::from(img.get_pixel(7, 2)[0])
+        };
+        assert!(contrast(&sharpened) > contrast(&img));
+        // Flat regions are left untouched
+        assert_eq!(sharpened.get_pixel(0, 0), &Luma::new([50]));
+        assert_eq!(sharpened.get_pixel(15, 3), &Luma::new([200]));
+        // Overshoots are clamped
+        let strong = unsharp_mask(&img, 1., 20., Padding::Replicate);
+        assert_eq!(strong.get_pixel(9, 0), &Luma::new([255]));
+        assert_eq!(strong.get_pixel(6, 0), &Luma::new([0]));
+    }
+
+    #[test]
+    fn test_unsharp_mask_zero_amount() {
+        let img = ImageBuffer2D::generate(5, 5, |(x, y)| Rgb::new([x as u8, y as u8, 3]));
+        assert_eq!(unsharp_mask(&img, 2., 0., Padding::Zeros), img);
+    }
+
+    #[test]
+    fn test_gaussian_blur() {
+        let flat = ImageBuffer2D::solid(6, 5, Rgb::new([10u8, 200, 255]));
+        assert_eq!(gaussian_blur(&flat, 1.5, Padding::Replicate), flat);
+
+        let img = ImageBuffer2D::checkerboard(8, 8, 1, Luma::new([0.]), Luma::new([1f64]));
+        let blurred = gaussian_blur(&img, 1., Padding::Wrap);
+        assert!((blurred.mean()[0] - 0.5).abs() < 1e-9);
+        assert!(blurred.std_dev()[0] < 0.1 * img.std_dev()[0]);
+        assert_eq!(gaussian_blur(&img, 0., Padding::Wrap), img);
+    }
+
+    #[test]
+    fn test_rank_filter() {
+        let img =
+            ImageBuffer2D::generate(9, 7, |(x, y)| Luma::new([((x * 37 + y * 71) % 101) as u8]));
+        let min = rank_filter(&img, 1, 0, Padding::Replicate);
+        let max = rank_filter(&img, 1, 1000, Padding::Replicate);
+        for y in 0..7i64 {
+            for x in 0..9i64 {
+                let window: Vec<u8> = (-1..=1)
+                    .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                    .map(|(dx, dy)| {
+                        let (sx, sy) = ((x + dx).max(0).min(8), (y + dy).max(0).min(6));
+                        img.get_pixel(sx as u32, sy as u32)[0]
+                    })
+                    .collect();
+                let (x, y) = (x as u32, y as u32);
+                assert_eq!(min.get_pixel(x, y)[0], *window.iter().min().unwrap());
+                assert_eq!(max.get_pixel(x, y)[0], *window.iter().max().unwrap());
+            }
+        }
+
+        let binary = ImageBuffer2D::generate(12, 10, |(x, y)| {
+            Luma::new([if (x == 3 && y == 4) || (x > 7 && y > 6) {
+                255u8
+            } else {
+                0
+            }])
+        });
+        assert_eq!(
+            rank_filter(&binary, 2, 24, Padding::Zeros),
+            dilate(&binary, 2)
+        );
+    }
+
+    #[test]
+    fn test_median_filter() {
+        // Salt and pepper noise on a uniform color image
+        let img = ImageBuffer2D::generate(8, 8, |(x, y)| match (x * 3 + y * 5) % 17 {
+            0 => Rgb::new([255u8, 255, 255]),
+            1 => Rgb::new([0, 0, 0]),
+            _ => Rgb::new([10, 20, 30]),
+        });
+        let filtered = median_filter(&img, 1, Padding::Mirror);
+        assert!(filtered.iter().all(|p| *p == Rgb::new([10, 20, 30])));
+    }
+}
@@ -1,17 +1,52 @@
 //! Contains the definitions of the Histogram type and Histogram equalization functions.
 
-use core::{Image2D, ImageBuffer2D, Luma, Pixel};
+use core::{Image2D, Image2DMut, ImageBuffer2D, Luma, Pixel, Rect, Rgb};
 
-use num_traits::{NumCast, Zero};
+use num_traits::Zero;
 
 use std::convert::{From, Into};
 
 /// Trait implemented for pixel types for which histogram computation is implemented.
-pub trait HistPixel: Pixel + Zero {}
-impl HistPixel for Luma<u8> {}
-impl HistPixel for Luma<i8> {}
+pub trait HistPixel: Pixel + Zero {
+    /// Return the index of the histogram bin, in the `[0, 255]` range, counting the pixel.
+    fn bin(&self) -> usize;
+    /// Return the pixel whose value is the one represented by a histogram bin.
+    fn from_bin(bin: u8) -> Self;
+}
+
+impl HistPixel for Luma<u8> {
+    fn bin(&self) -> usize {
+        self.data[0] as usize
+    }
+
+    fn from_bin(bin: u8) -> Self {
+        Luma::new([bin])
+    }
+}
+
+impl HistPixel for Luma<i8> {
+    /// Signed values are offset so that -128 maps to the bin 0 and 127 to the bin 255.
+    fn bin(&self) -> usize {
+        (i16::from(self.data[0]) + 128) as usize
+    }
+
+    fn from_bin(bin: u8) -> Self {
+        Luma::new([(i16::from(bin) - 128) as i8])
+    }
+}
+
+impl HistPixel for Luma<u16> {
+    /// Each bin covers 256 consecutive values. Use `equalize_u16` to equalize an image over all the 65536 levels.
+    fn bin(&self) -> usize {
+        (self.data[0] >> 8) as usize
+    }
+
+    fn from_bin(bin: u8) -> Self {
+        Luma::new([u16::from(bin) * 257])
+    }
+}
 
-/// Represent a histogram of a greyscale 8-bit image.
+/// Represent a histogram of a greyscale image with 256 bins. See `HistPixel` for the mapping of pixel values to bins.
 pub struct Histogram {
     v: [u32; 256],
 }
@@ -25,8 +60,8 @@ impl Histogram {
 
     /// Return the number of pixels in the histogram with the given value, treating the histogram as representing a i8
     /// image.
-    pub fn count_i8(&self, val: u8) -> u32 {
-        self.v[val as usize]
+    pub fn count_i8(&self, val: i8) -> u32 {
+        self.v[Luma::new([val]).bin()]
     }
 
     /// Return a reference to the array of histogram bins.
@@ -44,8 +79,73 @@ impl Histogram {
         Histogram { v }
     }
 
-    // TODO
-    // fn draw(&self) -> Image2D<Rgb<u8>>
+    /// Return the total number of pixels counted in the histogram.
+    pub fn total(&self) -> u32 {
+        self.v.iter().sum()
+    }
+
+    /// Render the histogram as a bar chart of the given dimensions, with white bars on a black background. The
+    /// bars are scaled so that the highest one spans the whole height of the image. When the image is narrower than
+    /// 256 pixels, each column displays the highest of the bins it covers.
+    pub fn draw(&self, width: u32, height: u32) -> ImageBuffer2D<Rgb<u8>> {
+        let mut img = ImageBuffer2D::new(width, height);
+        let max = *self.v.iter().max().unwrap() as u64;
+        if max == 0 || height == 0 {
+            return img;
+        }
+        let white = Rgb::new([255, 255, 255]);
+        for x in 0..width {
+            let first = (x as usize * 256) / width as usize;
+            let last = ((x as usize + 1) * 256 / width as usize).max(first + 1);
+            let val = *self.v[first..last].iter().max().unwrap() as u64;
+            let bar_h = ((val * height as u64 + max / 2) / max) as u32;
+            if bar_h > 0 {
+                img.fill_rect(Rect::new(x, height - bar_h, 1, bar_h), &white);
+            }
+        }
+        img
+    }
+}
+
+/// Represent the per-channel histograms of an 8-bit RGB image.
+pub struct RgbHistogram {
+    r: Histogram,
+    g: Histogram,
+    b: Histogram,
+}
+
+impl RgbHistogram {
+    /// Return the histogram of the red channel.
+    pub fn red(&self) -> &Histogram {
+        &self.r
+    }
+
+    /// Return the histogram of the green channel.
+    pub fn green(&self) -> &Histogram {
+        &self.g
+    }
+
+    /// Return the histogram of the blue channel.
+    pub fn blue(&self) -> &Histogram {
+        &self.b
+    }
+}
+
+impl<'a> From<&'a Image2D<Rgb<u8>>> for RgbHistogram {
+    /// Construct the per-channel histograms of an image.
+    fn from(img: &'a Image2D<Rgb<u8>>) -> RgbHistogram {
+        let (mut r, mut g, mut b) = ([0; 256], [0; 256], [0; 256]);
+        for pix in img {
+            r[pix.data[0] as usize] += 1;
+            g[pix.data[1] as usize] += 1;
+            b[pix.data[2] as usize] += 1;
+        }
+        RgbHistogram {
+            r: Histogram { v: r },
+            g: Histogram { v: g },
+            b: Histogram { v: b },
+        }
+    }
 }
 
 impl<'a, P> From<&'a Image2D<P>> for Histogram
@@ -56,8 +156,7 @@ where
     fn from(img: &'a Image2D<P>) -> Histogram {
         let mut v = [0; 256];
         for pix in img {
-            let idx = <u8 as NumCast>::from::<P::Subpixel>(pix.channels()[0]).unwrap();
-            v[idx as usize] += 1;
+            v[pix.bin()] += 1;
         }
         Histogram { v }
     }
@@ -74,13 +173,126 @@ where
     let transfer = cumul
         .bins()
         .iter()
-        .map(|val| ((Into::<f64>::into(*val) * 255.) / (Into::<f64>::into(m))) as u8)
-        .collect::<Vec<u8>>();
+        .map(|val| P::from_bin(((Into::<f64>::into(*val) * 255.) / (Into::<f64>::into(m))) as u8))
+        .collect::<Vec<P>>();
+    let mut equalized = img.to_owned();
+    for pix in &mut equalized {
+        *pix = transfer[pix.bin()].clone();
+    }
+    equalized
+}
+
+/// Adjust the contrast of a 16-bit image by histogram equalization over the 65536 possible levels. The lowest level
+/// present in the image is mapped to 0 and the highest one to 65535.
+pub fn equalize_u16(img: &Image2D<Luma<u16>>) -> ImageBuffer2D<Luma<u16>> {
+    let mut cumul = vec![0u64; 65536];
+    for pix in img {
+        cumul[pix.data[0] as usize] += 1;
+    }
+    for i in 1..cumul.len() {
+        cumul[i] += cumul[i - 1];
+    }
+    let total = cumul[65535];
+    let min = cumul.iter().cloned().find(|&c| c != 0).unwrap_or(0);
     let mut equalized = img.to_owned();
+    if total == min {
+        // Empty or uniform image, nothing to stretch.
+        return equalized;
+    }
     for pix in &mut equalized {
-        let idx = <u8 as NumCast>::from::<P::Subpixel>(pix.channels()[0]).unwrap();
-        pix.channels_mut()[0] =
-            <P::Subpixel as NumCast>::from::<u8>(transfer[idx as usize]).unwrap();
+        let c = cumul[pix.data[0] as usize];
+        pix.data[0] = ((c - min) * 65535 / (total - min)) as u16;
     }
     equalized
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_histogram() {
+        let img = ImageBuffer2D::generate(16, 8, |(x, y)| Rgb::new([x as u8, y as u8, 7]));
+        let hist: RgbHistogram = (&img as &Image2D<Rgb<u8>>).into();
+        for h in &[hist.red(), hist.green(), hist.blue()] {
+            assert_eq!(h.total(), 16 * 8);
+        }
+        assert_eq!(hist.red().count_u8(3), 8);
+        assert_eq!(hist.green().count_u8(3), 16);
+        assert_eq!(hist.green().count_u8(8), 0);
+        assert_eq!(hist.blue().count_u8(7), 16 * 8);
+    }
+
+    #[test]
+    fn test_signed_histogram() {
+        let img = ImageBuffer2D::solid(4, 3, Luma::new([-128i8]));
+        let hist: Histogram = (&img as &Image2D<Luma<i8>>).into();
+        assert_eq!(hist.bins()[0], 12);
+        assert_eq!(hist.count_i8(-128), 12);
+        assert_eq!(hist.total(), 12);
+
+        let img = ImageBuffer2D::generate(16, 16, |(x, y)| Luma::new([(x + 16 * y) as u8 as i8]));
+        let hist: Histogram = (&img as &Image2D<Luma<i8>>).into();
+        assert!(hist.bins().iter().all(|&b| b == 1));
+        assert_eq!(hist.count_i8(-1), 1);
+        assert_eq!(hist.count_i8(127), 1);
+        // Equalizing a flat histogram spreads the values over the whole range.
+        let equalized = equalize(&img);
+        assert_eq!(equalized.get_pixel(0, 8), &Luma::new([-128]));
+        assert_eq!(equalized.get_pixel(15, 7), &Luma::new([127]));
+    }
+
+    #[test]
+    fn test_u16_histogram() {
+        let img = ImageBuffer2D::generate(4, 4, |(x, _)| Luma::new([x as u16 * 300]));
+        let hist: Histogram = (&img as &Image2D<Luma<u16>>).into();
+        assert_eq!(hist.bins()[0], 4);
+        assert_eq!(hist.bins()[1], 4);
+        assert_eq!(hist.bins()[2], 4);
+        assert_eq!(hist.bins()[3], 4);
+        let equalized = equalize(&img);
+        assert_eq!(equalized.get_pixel(3, 0), &Luma::new([65535]));
+    }
+
+    #[test]
+    fn test_draw() {
+        let img =
+            ImageBuffer2D::generate(4, 4, |(x, _)| Luma::new([if x == 0 { 10u8 } else { 20 }]));
+        let hist: Histogram = (&img as &Image2D<Luma<u8>>).into();
+        let black = Rgb::new([0, 0, 0]);
+        let white = Rgb::new([255, 255, 255]);
+
+        let chart = hist.draw(256, 30);
+        assert_eq!(chart.dimensions(), (256, 30));
+        assert!(chart.col(20).unwrap().all(|p| *p == white));
+        assert_eq!(chart.col(10).unwrap().filter(|p| **p == white).count(), 10);
+        assert_eq!(chart.get_pixel(10, 0), &black);
+        assert!(chart.col(0).unwrap().all(|p| *p == black));
+
+        // Several bins per column
+        let chart = hist.draw(128, 30);
+        assert!(chart.col(10).unwrap().all(|p| *p == white));
+        assert_eq!(chart.col(5).unwrap().filter(|p| **p == white).count(), 10);
+    }
+
+    #[test]
+    fn test_equalize_u16() {
+        let img =
+            ImageBuffer2D::generate(32, 32, |(x, y)| Luma::new([1000 + ((x + y) % 50) as u16]));
+        let equalized = equalize_u16(&img);
+        let min = equalized.iter().map(|p| p.data[0]).min().unwrap();
+        let max = equalized.iter().map(|p| p.data[0]).max().unwrap();
+        assert_eq!((min, max), (0, 65535));
+        // The transfer function is monotonic
+        for (p1, p2) in img.iter().zip(equalized.iter()) {
+            for (q1, q2) in img.iter().zip(equalized.iter()) {
+                if p1.data[0] < q1.data[0] {
+                    assert!(p2.data[0] < q2.data[0]);
+                }
+            }
+        }
+
+        let uniform = ImageBuffer2D::solid(4, 4, Luma::new([1234u16]));
+        assert_eq!(equalize_u16(&uniform), uniform);
+    }
+}
@@ -1,11 +1,12 @@
 //! Contains the definitions of the image kernel type and the convolution operation.
 
-use core::{Image2D, Image2DMut, ImageBuffer2D, Pixel, Primitive, Rect};
+use core::padding::pad_zeros;
+use core::{Image2D, ImageBuffer2D, Luma, Pixel, PixelCast, Primitive, Rect};
 use helper::generic::f64_to_float;
 use math;
 
 use failure::Error;
-use num_traits::{Float, NumCast, Zero};
+use num_traits::{Float, NumCast, Signed, Zero};
 
 use std::ops::Add;
 
@@ -36,49 +37,120 @@ where
         Ok(Kernel { elems, radius })
     }
 
-    /// Convolve an image with the kernel. Uses zero-padding for borders.
+    /// Create a new kernel from the pixels of an image, read in row-major order.
+    ///
+    /// *Error*: if the image is not square, or its side is not odd.
+    pub fn from_image(img: &Image2D<Luma<T>>) -> Result<Kernel<T>, Error> {
+        let (w, h) = img.dimensions();
+        ensure!(w == h, "Kernel image is not square: {}x{}", w, h);
+        ensure!(w % 2 == 1, "Kernel image side is not odd: {}", w);
+        Kernel::new(img.iter().map(|p| p[0]).collect(), w / 2)
+    }
+
+    /// Return the radius of the kernel.
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// Return the elements of the kernel in row-major order.
+    pub fn elems(&self) -> &[T] {
+        &self.elems
+    }
+
+    // Return the kernel rotated by 180 degrees, turning a correlation into a convolution.
+    fn flipped(&self) -> Kernel<T> {
+        Kernel {
+            elems: self.elems.iter().rev().cloned().collect(),
+            radius: self.radius,
+        }
+    }
+
+    /// Correlate an image with the kernel, i.e. compute for each pixel the sum of its neighbors weighted by the
+    /// kernel elements laid over them as is. Uses zero-padding for borders.
+    pub fn correlate<P, S>(&self, img: &Image2D<P>) -> ImageBuffer2D<P>
+    where
+        P: Pixel<Subpixel = S> + Zero + Add,
+        S: Primitive,
+    {
+        let d = 2 * self.radius + 1;
+        let padded = pad_zeros(img, self.radius);
+        let n_channels = <P as Pixel>::N_CHANNELS as usize;
+        let mut pix_accu_t = vec![<T as Zero>::zero(); n_channels];
+        let mut pix_accu_s = vec![<S as Zero>::zero(); n_channels];
+        let (w, h) = img.dimensions();
+        ImageBuffer2D::generate(w, h, |(x, y)| {
+            for a in &mut pix_accu_t {
+                *a = <T as Zero>::zero();
+            }
+            for (p, e) in padded
+                .rect_iter(Rect::new(x, y, d, d))
+                .zip(self.elems.iter())
+            {
+                // Perform the correlation on the kernel floating point type.
+                for (a, c) in pix_accu_t.iter_mut().zip(p.channels()) {
+                    *a += *e * <T as NumCast>::from::<S>(*c).unwrap();
+                }
+            }
+            for (dst, a) in pix_accu_s.iter_mut().zip(pix_accu_t.iter()) {
+                *dst = <S as NumCast>::from::<T>(*a).unwrap_or_else(<S as Zero>::zero);
+            }
+            P::from_slice(&pix_accu_s)
+        })
+    }
+
+    /// Convolve an image with the kernel, i.e. correlate it with the kernel rotated by 180 degrees. Both operations
+    /// give the same result for symmetric kernels such as the gaussian and box kernels. Uses zero-padding for borders.
     pub fn convolve<P, S>(&self, img: &Image2D<P>) -> ImageBuffer2D<P>
     where
         P: Pixel<Subpixel = S> + Zero + Add,
         S: Primitive,
+    {
+        self.flipped().correlate(img)
+    }
+
+    /// Correlate an image with the kernel, casting the values accumulated in the kernel type directly to the `O`
+    /// subpixel type. Unlike with `correlate`, the results are not restricted to the range of the input subpixel type,
+    /// so that e.g. correlating an 8 bit image with a Sobel kernel into `f64` keeps the negative gradients. Values
+    /// that can't be represented by `O` are set to zero. Uses zero-padding for borders.
+    pub fn correlate_raw<P, O>(
+        &self,
+        img: &Image2D<P>,
+    ) -> ImageBuffer2D<<P as PixelCast<O>>::Output>
+    where
+        P: PixelCast<O>,
+        O: Primitive,
     {
         let d = 2 * self.radius + 1;
-        let n_elems = d * d;
-        let n_channels = <P as Pixel>::N_CHANNELS;
-        let mut out = img.to_owned();
-        let mut region_accu = Vec::with_capacity((n_elems * n_channels) as usize);
-        let mut pix_accu_t = vec![<T as Zero>::zero(); n_channels as usize];
-        let mut pix_accu_s = vec![<S as Zero>::zero(); n_channels as usize];
-        for ((y, x), dst_pix) in out.enumerate_pixels_mut() {
-            let rx = (x as u32).saturating_sub(self.radius);
-            let ry = (y as u32).saturating_sub(self.radius);
-            let rect = Rect::new(rx, ry, d, d).crop_to_image(img).unwrap();
-            for (p, e) in img.rect_iter(rect).zip(self.elems.iter()) {
-                // Perform the convolution on the kernel floating point type.
-                region_accu.extend(
-                    p.channels()
-                        .iter()
-                        .map(|c| *e * <T as NumCast>::from::<S>(*c).unwrap()),
-                );
+        let padded = pad_zeros(img, self.radius);
+        let mut accu = vec![<T as Zero>::zero(); P::N_CHANNELS as usize];
+        let (w, h) = img.dimensions();
+        ImageBuffer2D::generate(w, h, |(x, y)| {
+            for a in &mut accu {
+                *a = <T as Zero>::zero();
             }
-            pix_accu_t
-                .as_mut_slice()
-                .iter_mut()
-                .map(|c| *c = <T as Zero>::zero())
-                .count();
-            for convolved_pix in region_accu.as_slice().chunks(n_channels as usize) {
-                for i in 0_usize..n_channels as usize {
-                    pix_accu_t[i] += convolved_pix[i];
+            for (p, e) in padded
+                .rect_iter(Rect::new(x, y, d, d))
+                .zip(self.elems.iter())
+            {
+                for (a, c) in accu.iter_mut().zip(p.channels()) {
+                    *a += *e * <T as NumCast>::from(*c).unwrap_or_else(<T as Zero>::zero);
                 }
             }
-            region_accu.clear();
-            for i in 0_usize..n_channels as usize {
-                pix_accu_s[i] =
-                    <S as NumCast>::from::<T>(pix_accu_t[i]).unwrap_or_else(<S as Zero>::zero);
+            let mut out = <<P as PixelCast<O>>::Output as Zero>::zero();
+            for (dst, a) in out.channels_mut().iter_mut().zip(accu.iter()) {
+                *dst = <O as NumCast>::from(*a).unwrap_or_else(<O as Zero>::zero);
             }
-            *dst_pix = P::from_slice(&pix_accu_s);
-        }
-        out
+            out
+        })
+    }
+
+    /// Convolution counterpart of `correlate_raw`.
+    pub fn convolve_raw<P, O>(&self, img: &Image2D<P>) -> ImageBuffer2D<<P as PixelCast<O>>::Output>
+    where
+        P: PixelCast<O>,
+        O: Primitive,
+    {
+        self.flipped().correlate_raw(img)
     }
 }
 
@@ -113,5 +185,148 @@ where
     }
 }
 
+impl<T> Kernel<T>
+where
+    T: Primitive + Signed,
+{
+    /// Return the 3x3 Sobel kernel computing the horizontal derivative when correlated with an image.
+    pub fn sobel_x_3x3() -> Kernel<T> {
+        let v = [-1, 0, 1, -2, 0, 2, -1, 0, 1]
+            .iter()
+            .map(|e| <T as NumCast>::from::<i32>(*e).unwrap())
+            .collect();
+        Kernel::new(v, 1).unwrap()
+    }
+
+    /// Return the 3x3 Sobel kernel computing the vertical derivative when correlated with an image.
+    pub fn sobel_y_3x3() -> Kernel<T> {
+        let v = [-1, -2, -1, 0, 0, 0, 1, 2, 1]
+            .iter()
+            .map(|e| <T as NumCast>::from::<i32>(*e).unwrap())
+            .collect();
+        Kernel::new(v, 1).unwrap()
+    }
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use core::Image2DMut;
+
+    #[test]
+    fn test_gaussian_separable() {
+        let (sigma, radius) = (1.5, 4);
+        let img = ImageBuffer2D::generate(20, 16, |(x, y)| {
+            Luma::new([((x * 7 + y * 3) % 13) as f64 / 13.])
+        });
+        let full = Kernel::gaussian(sigma, radius).convolve(&img);
+
+        // Same blur computed as two 1D passes
+        let r = radius as i64;
+        let w1d: Vec<f64> = (-r..=r)
+            .map(|i| {
+                (-(i * i) as f64 / (2. * sigma * sigma)).exp()
+                    / ((2. * ::std::f64::consts::PI).sqrt() * sigma)
+            })
+            .collect();
+        let pass = |img: &ImageBuffer2D<Luma<f64>>, horizontal: bool| {
+            let (w, h) = img.dimensions();
+            ImageBuffer2D::generate(w, h, |(x, y)| {
+                let mut acc = 0.;
+                for (i, wi) in (-r..=r).zip(w1d.iter()) {
+                    let (sx, sy) = if horizontal {
+                        ((x as i64) + i, (y as i64))
+                    } else {
+                        ((x as i64), (y as i64) + i)
+                    };
+                    if sx >= 0 && sy >= 0 && sx < (w as i64) && sy < (h as i64) {
+                        acc += wi * img.get_pixel(sx as u32, sy as u32)[0];
+                    }
+                }
+                Luma::new([acc])
+            })
+        };
+        let separable = pass(&pass(&img, true), false);
+
+        let inner = Rect::new(0, 0, 20, 16).inset(radius).unwrap();
+        assert!(full
+            .sub_image(inner)
+            .approx_eq(&separable.sub_image(inner), 1e-6));
+    }
+
+    #[test]
+    fn test_convolve_raw() {
+        // Bright to dark vertical edge
+        let img =
+            ImageBuffer2D::generate(6, 4, |(x, _)| Luma::new([if x < 3 { 255u8 } else { 0 }]));
+        let sobel = Kernel::<f64>::sobel_x_3x3();
+        let raw = sobel.correlate_raw::<_, f64>(&img);
+        for y in 1..3 {
+            assert_eq!(raw.get_pixel(1, y), &Luma::new([0.]));
+            assert_eq!(raw.get_pixel(2, y), &Luma::new([-1020.]));
+            assert_eq!(raw.get_pixel(3, y), &Luma::new([-1020.]));
+            assert_eq!(raw.get_pixel(4, y), &Luma::new([0.]));
+        }
+        // Zero-padding makes the image border look like a dark to bright edge.
+        assert_eq!(raw.get_pixel(0, 1), &Luma::new([1020.]));
+
+        // The same correlation into the input type loses the negative gradients.
+        let clamped = sobel.correlate(&img);
+        assert!(clamped.rect_iter(Rect::new(2, 1, 2, 2)).all(|p| p[0] == 0));
+    }
+
+    #[test]
+    fn test_from_image() {
+        let elems = vec![1., 2., 1., 0., 0., 0., -1., -2., -1.];
+        let img =
+            ImageBuffer2D::from_vec(3, 3, elems.iter().map(|&e| Luma::new([e])).collect()).unwrap();
+        let from_img = Kernel::from_image(&img).unwrap();
+        let kernel = Kernel::new(elems, 1).unwrap();
+        let img = ImageBuffer2D::generate(7, 6, |(x, y)| Luma::new([(x * x + 3 * y) as f64]));
+        assert_eq!(from_img.convolve(&img), kernel.convolve(&img));
+
+        let view = img.sub_image(Rect::new(0, 0, 5, 5));
+        assert_eq!(Kernel::from_image(&view).unwrap().radius, 2);
+        assert!(Kernel::from_image(&ImageBuffer2D::<Luma<f64>>::new(3, 5)).is_err());
+        assert!(Kernel::from_image(&ImageBuffer2D::<Luma<f64>>::new(4, 4)).is_err());
+    }
+
+    #[test]
+    fn test_correlate_convolve() {
+        let elems: Vec<f64> = (1..10).map(|i| i as f64).collect();
+        let kernel = Kernel::new(elems.clone(), 1).unwrap();
+        let mut delta = ImageBuffer2D::<Luma<f64>>::new(5, 5);
+        delta.put_pixel(2, 2, Luma::new([1.]));
+        let around = Rect::new(1, 1, 3, 3);
+
+        // Convolution is the identity for the delta function, correlation flips the kernel.
+        let convolved = kernel.convolve(&delta);
+        assert!(convolved
+            .rect_iter(around)
+            .map(|p| p[0])
+            .eq(elems.iter().cloned()));
+        let correlated = kernel.correlate(&delta);
+        assert!(correlated
+            .rect_iter(around)
+            .map(|p| p[0])
+            .eq(elems.iter().rev().cloned()));
+        assert!(kernel
+            .convolve_raw::<_, f64>(&delta)
+            .rect_iter(around)
+            .map(|p| p[0])
+            .eq(elems.iter().cloned()));
+
+        // Borders are zero-padded, the kernel staying aligned with the image.
+        let mut corner = ImageBuffer2D::<Luma<f64>>::new(5, 5);
+        corner.put_pixel(0, 0, Luma::new([1.]));
+        let convolved = kernel.convolve(&corner);
+        assert!(convolved
+            .rect_iter(Rect::new(0, 0, 2, 2))
+            .map(|p| p[0])
+            .eq(vec![5., 6., 8., 9.]));
+
+        let gaussian = Kernel::gaussian(1., 2);
+        let img = ImageBuffer2D::generate(6, 5, |(x, y)| Luma::new([(x * y) as f64]));
+        assert_eq!(gaussian.convolve(&img), gaussian.correlate(&img));
+    }
+}
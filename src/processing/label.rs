@@ -0,0 +1,278 @@
+//! Contains connected component labeling functions.
+
+use core::{Image2D, Image2DMut, ImageBuffer2D, Luma, Pixel};
+
+use num_traits::cast;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Pixel connectivity used to decide whether two pixels are neighbors.
+pub enum Connectivity {
+    /// Pixels sharing an edge are neighbors.
+    Four,
+    /// Pixels sharing an edge or a corner are neighbors.
+    Eight,
+}
+
+// Union-find structure over provisional labels.
+struct DisjointSets {
+    parents: Vec<u32>,
+}
+
+impl DisjointSets {
+    fn new() -> DisjointSets {
+        // Label 0 is the background.
+        DisjointSets { parents: vec![0] }
+    }
+
+    fn make_set(&mut self) -> u32 {
+        let label = self.parents.len() as u32;
+        self.parents.push(label);
+        label
+    }
+
+    fn find(&mut self, mut label: u32) -> u32 {
+        while self.parents[label as usize] != label {
+            let grandparent = self.parents[self.parents[label as usize] as usize];
+            self.parents[label as usize] = grandparent;
+            label = grandparent;
+        }
+        label
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra < rb {
+            self.parents[rb as usize] = ra;
+        } else if rb < ra {
+            self.parents[ra as usize] = rb;
+        }
+    }
+}
+
+/// Label the connected components of a binary image, in which non-zero pixels are foreground. Return an image where
+/// each foreground pixel is set to the label of its component, numbered from 1 in scanline order, background pixels
+/// being set to 0, along with the number of components.
+pub fn connected_components(
+    img: &Image2D<Luma<u8>>,
+    connectivity: Connectivity,
+) -> (ImageBuffer2D<Luma<u32>>, u32) {
+    let (w, h) = img.dimensions();
+    let mut labels = ImageBuffer2D::<Luma<u32>>::new(w, h);
+    let mut sets = DisjointSets::new();
+
+    // First pass: assign provisional labels and record equivalences.
+    for y in 0..h {
+        for x in 0..w {
+            if img.get_pixel(x, y)[0] == 0 {
+                continue;
+            }
+            let mut neighbors = Vec::with_capacity(4);
+            if x > 0 {
+                neighbors.push((x - 1, y));
+            }
+            if y > 0 {
+                neighbors.push((x, y - 1));
+                if connectivity == Connectivity::Eight {
+                    if x > 0 {
+                        neighbors.push((x - 1, y - 1));
+                    }
+                    if x + 1 < w {
+                        neighbors.push((x + 1, y - 1));
+                    }
+                }
+            }
+            let mut label = 0;
+            for (nx, ny) in neighbors {
+                let n = labels.get_pixel(nx, ny)[0];
+                if n != 0 {
+                    if label == 0 {
+                        label = n;
+                    } else {
+                        sets.union(label, n);
+                    }
+                }
+            }
+            if label == 0 {
+                label = sets.make_set();
+            }
+            labels.put_pixel(x, y, Luma::new([label]));
+        }
+    }
+
+    // Second pass: resolve equivalences and renumber the components.
+    let mut final_labels = vec![0; sets.parents.len()];
+    let mut n_components = 0;
+    for pix in labels.iter_mut() {
+        if pix[0] != 0 {
+            let root = sets.find(pix[0]) as usize;
+            if final_labels[root] == 0 {
+                n_components += 1;
+                final_labels[root] = n_components;
+            }
+            pix[0] = final_labels[root];
+        }
+    }
+
+    (labels, n_components)
+}
+
+/// Replace the connected region of pixels similar to the pixel at `seed` with `new_color`. A pixel is similar to the
+/// seed pixel if the absolute difference of each of their channels is at most `tolerance`.
+///
+/// **Panics** if `seed` is out of the image bounds.
+pub fn flood_fill<P>(
+    img: &mut Image2DMut<P>,
+    seed: (u32, u32),
+    new_color: P,
+    tolerance: P::Subpixel,
+    connectivity: Connectivity,
+) where
+    P: Pixel + PartialEq,
+{
+    let (w, h) = img.dimensions();
+    let target = img.get_pixel(seed.0, seed.1).clone();
+    // Compare on f64, the difference of two channels may not be representable by signed subpixel types.
+    let to_f64 = |v: P::Subpixel| cast::<P::Subpixel, f64>(v).unwrap_or(0.);
+    let tolerance = to_f64(tolerance);
+    let similar = |p: &P| {
+        p.channels()
+            .iter()
+            .zip(target.channels())
+            .all(|(&a, &b)| (to_f64(a) - to_f64(b)).abs() <= tolerance)
+    };
+    let offsets: &[(i64, i64)] = match connectivity {
+        Connectivity::Four => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+        Connectivity::Eight => &[
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ],
+    };
+    // Pixels are marked when pushed, since the new color may itself be similar to the seed.
+    let mut visited = vec![false; w as usize * h as usize];
+    visited[seed.1 as usize * w as usize + seed.0 as usize] = true;
+    let mut stack = vec![seed];
+    while let Some((x, y)) = stack.pop() {
+        img.put_pixel(x, y, new_color.clone());
+        for &(dx, dy) in offsets {
+            let (nx, ny) = (i64::from(x) + dx, i64::from(y) + dy);
+            if nx < 0 || ny < 0 || nx >= i64::from(w) || ny >= i64::from(h) {
+                continue;
+            }
+            let (nx, ny) = (nx as u32, ny as u32);
+            let idx = ny as usize * w as usize + nx as usize;
+            if !visited[idx] && similar(img.get_pixel(nx, ny)) {
+                visited[idx] = true;
+                stack.push((nx, ny));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Rect, Region, Rgb};
+
+    #[test]
+    fn test_connected_components() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(8, 6);
+        let r1 = Rect::new(1, 1, 2, 2);
+        let r2 = Rect::new(5, 3, 2, 2);
+        img.fill_rect(r1, &Luma::new([255]));
+        img.fill_rect(r2, &Luma::new([255]));
+        let (labels, n) = connected_components(&img, Connectivity::Four);
+        assert_eq!(n, 2);
+        for ((x, y), l) in labels.enumerate_pixels() {
+            let expected = if r1.contains(x, y) {
+                1
+            } else if r2.contains(x, y) {
+                2
+            } else {
+                0
+            };
+            assert_eq!(l[0], expected);
+        }
+    }
+
+    #[test]
+    fn test_connectivity() {
+        // Diagonal pixels, and a U shape requiring label merging.
+        let v = [
+            255, 0, 0, 0, 255, 0, 255, //
+            0, 255, 0, 0, 255, 0, 255, //
+            0, 0, 0, 0, 255, 255, 255, //
+        ];
+        let img = ImageBuffer2D::<Luma<u8>>::from_raw_vec(7, 3, &v).unwrap();
+        let (_, n4) = connected_components(&img, Connectivity::Four);
+        let (labels, n8) = connected_components(&img, Connectivity::Eight);
+        assert_eq!(n4, 3);
+        assert_eq!(n8, 2);
+        assert_eq!(labels.get_pixel(0, 0), labels.get_pixel(1, 1));
+        assert_eq!(labels.get_pixel(4, 0)[0], 2);
+        assert_eq!(labels.get_pixel(6, 0)[0], 2);
+    }
+
+    #[test]
+    fn test_connected_components_edge_cases() {
+        let mut img = ImageBuffer2D::<Luma<u8>>::new(5, 5);
+        assert_eq!(connected_components(&img, Connectivity::Eight).1, 0);
+        img.fill(&Luma::new([255]));
+        let (labels, n) = connected_components(&img, Connectivity::Four);
+        assert_eq!(n, 1);
+        assert!(labels.iter().all(|l| l[0] == 1));
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        // Two dark regions separated by a bright wall, touching only diagonally.
+        let v = [
+            0, 0, 255, 0, 0, //
+            0, 0, 255, 0, 0, //
+            0, 0, 0, 255, 0, //
+            255, 255, 255, 255, 0, //
+        ];
+        let img = ImageBuffer2D::<Luma<u8>>::from_raw_vec(5, 4, &v).unwrap();
+        let mut four = img.to_owned();
+        flood_fill(&mut four, (0, 0), Luma::new([100]), 0, Connectivity::Four);
+        for ((x, y), p) in four.enumerate_pixels() {
+            let expected = if x < 3 && y < 3 && img.get_pixel(x, y)[0] == 0 {
+                100
+            } else {
+                img.get_pixel(x, y)[0]
+            };
+            assert_eq!(p[0], expected);
+        }
+        let mut eight = img.to_owned();
+        flood_fill(&mut eight, (1, 1), Luma::new([100]), 0, Connectivity::Eight);
+        assert_eq!(eight.iter().filter(|p| p[0] == 100).count(), 13);
+        assert_eq!(eight.iter().filter(|p| p[0] == 255).count(), 7);
+
+        // Filling with a color similar to the seed still terminates.
+        let mut img = ImageBuffer2D::generate(6, 3, |(x, _)| Rgb::new([x as u8 * 10, 0, 0]));
+        flood_fill(
+            &mut img,
+            (0, 1),
+            Rgb::new([5, 0, 0]),
+            10,
+            Connectivity::Four,
+        );
+        for ((x, _), p) in img.enumerate_pixels() {
+            let expected = if x < 2 { 5 } else { x as u8 * 10 };
+            assert_eq!(p[0], expected);
+        }
+
+        // Signed subpixels whose difference overflows the subpixel type.
+        let mut img = ImageBuffer2D::generate(4, 1, |(x, _)| {
+            Luma::new([[127i8, 120, -128, 127][x as usize]])
+        });
+        flood_fill(&mut img, (0, 0), Luma::new([0]), 10, Connectivity::Four);
+        let values: Vec<i8> = img.iter().map(|p| p[0]).collect();
+        assert_eq!(values, vec![0, 0, -128, 127]);
+    }
+}
@@ -1,4 +1,19 @@
 //! Contains image processing operations.
 
+pub mod color;
+pub mod compare;
+pub mod composite;
+pub mod distance;
+pub mod features;
+#[cfg(feature = "fft")]
+pub mod fft;
+pub mod filter;
+pub mod gradient;
 pub mod histogram;
 pub mod kernel;
+pub mod label;
+pub mod morphology;
+pub mod pyramid;
+pub mod quantize;
+pub mod template_match;
+pub mod transform;
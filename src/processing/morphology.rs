@@ -0,0 +1,322 @@
+//! Contains binary morphology operations. Binary images are represented as 8 bit grayscale images whose pixels are
+//! either 0 (background) or 255 (foreground).
+
+use core::padding::pad_constant;
+use core::{Image2D, ImageBuffer2D, Luma, Rect, RectIter};
+
+use failure::Error;
+
+use std::cmp::max;
+
+// Apply a square window operation of the given radius. `f` receives the pixels of the window and returns whether the
+// output pixel is foreground.
+fn window_op<F>(
+    img: &Image2D<Luma<u8>>,
+    radius: u32,
+    pad_value: u8,
+    f: F,
+) -> ImageBuffer2D<Luma<u8>>
+where
+    F: Fn(RectIter<Luma<u8>>) -> bool,
+{
+    let (w, h) = img.dimensions();
+    let d = 2 * radius + 1;
+    let padded = pad_constant(img, radius, &Luma::new([pad_value]));
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        let window = padded.rect_iter(Rect::new(x, y, d, d));
+        Luma::new([if f(window) { 255 } else { 0 }])
+    })
+}
+
+/// Erode a binary image with a square structuring element of the given radius. A pixel is set to foreground if all
+/// the pixels in its neighborhood are foreground.
+pub fn erode(img: &Image2D<Luma<u8>>, radius: u32) -> ImageBuffer2D<Luma<u8>> {
+    window_op(img, radius, 255, |mut window| window.all(|p| p[0] == 255))
+}
+
+/// Dilate a binary image with a square structuring element of the given radius. A pixel is set to foreground if any
+/// of the pixels in its neighborhood is foreground.
+pub fn dilate(img: &Image2D<Luma<u8>>, radius: u32) -> ImageBuffer2D<Luma<u8>> {
+    window_op(img, radius, 0, |mut window| window.any(|p| p[0] == 255))
+}
+
+/// Structuring element of arbitrary shape, defined by a grid of booleans and an origin in that grid. Only the offsets
+/// of the `true` elements relative to the origin are part of the neighborhood of a pixel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuringElement {
+    elems: Vec<bool>,
+    width: u32,
+    height: u32,
+    origin: (u32, u32),
+}
+
+impl StructuringElement {
+    /// Create a new structuring element from a `width` by `height` grid of booleans in row-major order.
+    ///
+    /// **Error**: if `elems` has an incorrect size or if `origin` is out of the grid.
+    pub fn new(
+        elems: Vec<bool>,
+        width: u32,
+        height: u32,
+        origin: (u32, u32),
+    ) -> Result<StructuringElement, Error> {
+        ensure!(
+            elems.len() == (width * height) as usize,
+            "Vector has an incorrect size: {} (expected {})",
+            elems.len(),
+            width * height
+        );
+        ensure!(
+            origin.0 < width && origin.1 < height,
+            "Origin {:?} out of the {}x{} structuring element",
+            origin,
+            width,
+            height
+        );
+        Ok(StructuringElement {
+            elems,
+            width,
+            height,
+            origin,
+        })
+    }
+
+    // Create a square structuring element centered on its origin, containing the offsets for which `f` is true.
+    fn from_fn<F>(radius: u32, f: F) -> StructuringElement
+    where
+        F: Fn(i64, i64) -> bool,
+    {
+        let d = 2 * radius + 1;
+        let r = i64::from(radius);
+        let elems = (0..d * d)
+            .map(|i| f(i64::from(i % d) - r, i64::from(i / d) - r))
+            .collect();
+        StructuringElement::new(elems, d, d, (radius, radius)).unwrap()
+    }
+
+    /// Return a disk shaped structuring element of the given radius, containing the offsets whose euclidean norm is
+    /// at most `radius`.
+    pub fn disk(radius: u32) -> StructuringElement {
+        let r2 = i64::from(radius) * i64::from(radius);
+        StructuringElement::from_fn(radius, |dx, dy| dx * dx + dy * dy <= r2)
+    }
+
+    /// Return a cross shaped structuring element with arms of length `radius`.
+    pub fn cross(radius: u32) -> StructuringElement {
+        StructuringElement::from_fn(radius, |dx, dy| dx == 0 || dy == 0)
+    }
+
+    /// Return a `w` by `h` rectangular structuring element. The origin is the center of the rectangle, rounded towards
+    /// the top left corner for even dimensions.
+    ///
+    /// **Panics** if `w` or `h` is 0.
+    pub fn rect(w: u32, h: u32) -> StructuringElement {
+        assert!(
+            w != 0 && h != 0,
+            "Structuring element dimensions must be strictly positive."
+        );
+        StructuringElement::new(
+            vec![true; (w * h) as usize],
+            w,
+            h,
+            ((w - 1) / 2, (h - 1) / 2),
+        )
+        .unwrap()
+    }
+
+    /// Return the dimensions of the structuring element grid.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Return the position of the origin in the structuring element grid.
+    pub fn origin(&self) -> (u32, u32) {
+        self.origin
+    }
+
+    // Return the offsets relative to the origin of the elements of the structuring element.
+    fn offsets(&self) -> Vec<(i64, i64)> {
+        let (ox, oy) = (i64::from(self.origin.0), i64::from(self.origin.1));
+        let w = self.width as usize;
+        self.elems
+            .iter()
+            .enumerate()
+            .filter(|&(_, e)| *e)
+            .map(|(i, _)| ((i % w) as i64 - ox, (i / w) as i64 - oy))
+            .collect()
+    }
+
+    // Return the largest distance between the origin and the border of the structuring element.
+    fn radius(&self) -> u32 {
+        let (ox, oy) = self.origin;
+        max(max(ox, self.width - 1 - ox), max(oy, self.height - 1 - oy))
+    }
+}
+
+// Erode (`erosion == true`) or dilate an image with a structuring element. Dilation uses the reflected offsets of the
+// structuring element.
+fn structuring_element_op(
+    img: &Image2D<Luma<u8>>,
+    se: &StructuringElement,
+    erosion: bool,
+) -> ImageBuffer2D<Luma<u8>> {
+    let (w, h) = img.dimensions();
+    let radius = se.radius();
+    let padded = pad_constant(img, radius, &Luma::new([if erosion { 255 } else { 0 }]));
+    let sign = if erosion { 1 } else { -1 };
+    let offsets = se.offsets();
+    let r = i64::from(radius);
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        let (x, y) = (i64::from(x) + r, i64::from(y) + r);
+        let mut neighbors = offsets.iter().map(|&(dx, dy)| {
+            padded.get_pixel((x + sign * dx) as u32, (y + sign * dy) as u32)[0] == 255
+        });
+        let foreground = if erosion {
+            neighbors.all(|fg| fg)
+        } else {
+            neighbors.any(|fg| fg)
+        };
+        Luma::new([if foreground { 255 } else { 0 }])
+    })
+}
+
+/// Erode a binary image with an arbitrary structuring element. A pixel is set to foreground if all the pixels at the
+/// offsets of the structuring element are foreground.
+pub fn erode_with(img: &Image2D<Luma<u8>>, se: &StructuringElement) -> ImageBuffer2D<Luma<u8>> {
+    structuring_element_op(img, se, true)
+}
+
+/// Dilate a binary image with an arbitrary structuring element. A pixel is set to foreground if any of the pixels at
+/// the reflected offsets of the structuring element is foreground, i.e. every foreground pixel of the input is
+/// replaced by a copy of the structuring element.
+pub fn dilate_with(img: &Image2D<Luma<u8>>, se: &StructuringElement) -> ImageBuffer2D<Luma<u8>> {
+    structuring_element_op(img, se, false)
+}
+
+/// Morphological opening, i.e. an erosion followed by a dilation.
+pub fn open(img: &Image2D<Luma<u8>>, radius: u32) -> ImageBuffer2D<Luma<u8>> {
+    dilate(&erode(img, radius), radius)
+}
+
+/// Morphological closing, i.e. a dilation followed by an erosion.
+pub fn close(img: &Image2D<Luma<u8>>, radius: u32) -> ImageBuffer2D<Luma<u8>> {
+    erode(&dilate(img, radius), radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Image2DMut, Region};
+
+    fn square_img(w: u32, h: u32, r: Rect) -> ImageBuffer2D<Luma<u8>> {
+        let mut img = ImageBuffer2D::new(w, h);
+        img.fill_rect(r, &Luma::new([255]));
+        img
+    }
+
+    fn foreground(img: &ImageBuffer2D<Luma<u8>>) -> Vec<(u32, u32)> {
+        img.enumerate_pixels()
+            .filter(|(_, p)| p[0] == 255)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    #[test]
+    fn test_erode() {
+        let img = square_img(7, 7, Rect::new(2, 2, 3, 3));
+        assert_eq!(foreground(&erode(&img, 1)), vec![(3, 3)]);
+        assert!(foreground(&erode(&img, 2)).is_empty());
+
+        // Pixels on the image border are not eroded by the padding.
+        let img = square_img(5, 5, Rect::new(0, 0, 5, 5));
+        assert_eq!(erode(&img, 1), img);
+    }
+
+    #[test]
+    fn test_dilate() {
+        let img = square_img(7, 7, Rect::new(3, 3, 1, 1));
+        let r = Rect::new(2, 2, 3, 3);
+        for ((x, y), p) in dilate(&img, 1).enumerate_pixels() {
+            assert_eq!(p[0] == 255, r.contains(x, y));
+        }
+    }
+
+    #[test]
+    fn test_open_close() {
+        // Opening removes isolated pixels.
+        let mut img = square_img(9, 9, Rect::new(2, 2, 4, 4));
+        img.put_pixel(8, 8, Luma::new([255]));
+        assert_eq!(open(&img, 1), square_img(9, 9, Rect::new(2, 2, 4, 4)));
+
+        // Closing fills small holes.
+        let mut img = square_img(9, 9, Rect::new(2, 2, 5, 5));
+        img.put_pixel(4, 4, Luma::new([0]));
+        assert_eq!(close(&img, 1), square_img(9, 9, Rect::new(2, 2, 5, 5)));
+    }
+
+    #[test]
+    fn test_structuring_elements() {
+        let se = StructuringElement::cross(1);
+        assert_eq!(se.dimensions(), (3, 3));
+        assert_eq!(se.origin(), (1, 1));
+        assert_eq!(se.offsets(), vec![(0, -1), (-1, 0), (0, 0), (1, 0), (0, 1)]);
+        let se = StructuringElement::disk(2);
+        assert_eq!(se.offsets().len(), 13);
+        let se = StructuringElement::rect(4, 1);
+        assert_eq!(se.origin(), (1, 0));
+        assert_eq!(se.offsets(), vec![(-1, 0), (0, 0), (1, 0), (2, 0)]);
+
+        assert!(StructuringElement::new(vec![true; 3], 2, 2, (0, 0)).is_err());
+        assert!(StructuringElement::new(vec![true; 4], 2, 2, (2, 0)).is_err());
+    }
+
+    #[test]
+    fn test_erode_with() {
+        // A horizontal line only shrinks the square horizontally.
+        let img = square_img(12, 12, Rect::new(3, 3, 5, 5));
+        let line = StructuringElement::rect(5, 1);
+        assert_eq!(
+            erode_with(&img, &line),
+            square_img(12, 12, Rect::new(5, 3, 1, 5))
+        );
+        let line = StructuringElement::rect(1, 3);
+        assert_eq!(
+            erode_with(&img, &line),
+            square_img(12, 12, Rect::new(3, 4, 5, 3))
+        );
+
+        // Square structuring elements match the square window operations.
+        let img = square_img(9, 9, Rect::new(1, 2, 6, 5));
+        let square = StructuringElement::rect(3, 3);
+        assert_eq!(erode_with(&img, &square), erode(&img, 1));
+        assert_eq!(dilate_with(&img, &square), dilate(&img, 1));
+    }
+
+    #[test]
+    fn test_dilate_with() {
+        let img = square_img(7, 7, Rect::new(3, 3, 1, 1));
+        let dilated = dilate_with(&img, &StructuringElement::cross(2));
+        assert_eq!(
+            foreground(&dilated),
+            vec![
+                (3, 1),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3),
+                (4, 3),
+                (5, 3),
+                (3, 4),
+                (3, 5)
+            ]
+        );
+
+        // Dilation stamps the reflected structuring element on foreground pixels.
+        let se = StructuringElement::new(vec![true, true], 2, 1, (0, 0)).unwrap();
+        assert_eq!(foreground(&dilate_with(&img, &se)), vec![(3, 3), (4, 3)]);
+        assert_eq!(
+            foreground(&erode_with(&dilate_with(&img, &se), &se)),
+            vec![(3, 3)]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Contains multi-scale image pyramids.
+
+use core::padding::Padding;
+use core::{Image2D, ImageBuffer2D, Pixel, PixelCast};
+use processing::filter::gaussian_blur;
+use processing::transform::{downsample, resize, Interpolation};
+
+/// Build a gaussian pyramid of at most `levels` images, starting with the full resolution image. Each level is
+/// computed by blurring the previous one with a gaussian of standard deviation `sigma` and halving its dimensions.
+/// The pyramid stops early if a dimension would drop below 1.
+pub fn gaussian_pyramid<P>(img: &Image2D<P>, levels: usize, sigma: f64) -> Vec<ImageBuffer2D<P>>
+where
+    P: Pixel,
+{
+    let mut pyramid: Vec<ImageBuffer2D<P>> = Vec::with_capacity(levels);
+    if levels == 0 {
+        return pyramid;
+    }
+    pyramid.push(img.to_owned());
+    while pyramid.len() < levels {
+        let next = {
+            let last = &pyramid[pyramid.len() - 1];
+            let (w, h) = last.dimensions();
+            if w < 2 || h < 2 {
+                break;
+            }
+            downsample(&gaussian_blur(last, sigma, Padding::Replicate), 2)
+        };
+        pyramid.push(next);
+    }
+    pyramid
+}
+
+/// Build a laplacian pyramid of at most `levels` images from the gaussian pyramid of the image. Every level but the
+/// last is the band-pass difference between a level of the gaussian pyramid and the bilinear upsampling of the next
+/// one, the last level is the coarsest level of the gaussian pyramid. Levels are computed on `f64` channels since
+/// the differences may be negative.
+pub fn laplacian_pyramid<P>(
+    img: &Image2D<P>,
+    levels: usize,
+    sigma: f64,
+) -> Vec<ImageBuffer2D<<P as PixelCast<f64>>::Output>>
+where
+    P: PixelCast<f64>,
+{
+    let gaussian: Vec<_> = gaussian_pyramid(img, levels, sigma)
+        .iter()
+        .map(|level| {
+            ImageBuffer2D::generate(level.width(), level.height(), |(x, y)| {
+                level.get_pixel(x, y).cast()
+            })
+        })
+        .collect();
+    let mut pyramid = Vec::with_capacity(gaussian.len());
+    for pair in gaussian.windows(2) {
+        let (fine, coarse) = (&pair[0], &pair[1]);
+        let up = resize(coarse, fine.dimensions(), Interpolation::Bilinear);
+        pyramid.push(ImageBuffer2D::generate(
+            fine.width(),
+            fine.height(),
+            |(x, y)| {
+                let mut p = fine.get_pixel(x, y).clone();
+                for (c, u) in p
+                    .channels_mut()
+                    .iter_mut()
+                    .zip(up.get_pixel(x, y).channels())
+                {
+                    *c -= *u;
+                }
+                p
+            },
+        ));
+    }
+    if let Some(last) = gaussian.last() {
+        pyramid.push(Image2D::to_owned(last));
+    }
+    pyramid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Image2DMut, Luma, Rgb};
+
+    #[test]
+    fn test_gaussian_pyramid() {
+        let img = ImageBuffer2D::generate(64, 64, |(x, y)| Luma::new([(x * 4 + y) as u8]));
+        let pyramid = gaussian_pyramid(&img, 3, 1.);
+        let sizes: Vec<_> = pyramid.iter().map(|l| l.dimensions()).collect();
+        assert_eq!(sizes, vec![(64, 64), (32, 32), (16, 16)]);
+        assert_eq!(pyramid[0], img);
+
+        // The pyramid stops when a dimension would drop below 1.
+        let img = ImageBuffer2D::solid(8, 3, Rgb::new([1., 2., 3f32]));
+        let pyramid = gaussian_pyramid(&img, 10, 1.);
+        let sizes: Vec<_> = pyramid.iter().map(|l| l.dimensions()).collect();
+        assert_eq!(sizes, vec![(8, 3), (4, 1)]);
+        assert!(pyramid[1].approx_eq(&ImageBuffer2D::solid(4, 1, Rgb::new([1., 2., 3.])), 1e-5));
+        assert!(gaussian_pyramid(&img, 0, 1.).is_empty());
+    }
+
+    #[test]
+    fn test_laplacian_pyramid() {
+        let img =
+            ImageBuffer2D::generate(32, 16, |(x, y)| Luma::new([((x * 7 + y * 3) % 23) as u8]));
+        let laplacian = laplacian_pyramid(&img, 3, 1.);
+        assert_eq!(laplacian.len(), 3);
+        assert_eq!(laplacian[2].dimensions(), (8, 4));
+
+        // The image can be reconstructed by upsampling and adding the levels back, coarsest first.
+        let mut reconstructed = laplacian[2].clone();
+        for level in laplacian[..2].iter().rev() {
+            let mut up = resize(&reconstructed, level.dimensions(), Interpolation::Bilinear);
+            for (u, l) in up.iter_mut().zip(level.iter()) {
+                u[0] += l[0];
+            }
+            reconstructed = up;
+        }
+        for ((x, y), p) in img.enumerate_pixels() {
+            assert!((reconstructed.get_pixel(x, y)[0] - f64::from(p[0])).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Contains color quantization functions.
+
+use core::{Image2D, ImageBuffer2D, Luma};
+
+// Return the nearest of `levels` evenly spaced values in the [0, 255] range.
+fn nearest_level(v: f32, levels: u8) -> u8 {
+    let step = 255. / f32::from(levels - 1);
+    ((v / step).round() * step).max(0.).min(255.).round() as u8
+}
+
+/// Reduce the number of gray levels of an image by mapping each pixel to the nearest of `levels` evenly spaced values
+/// between 0 and 255.
+///
+/// # Panics
+///
+/// If `levels` is lower than 2.
+pub fn posterize(img: &Image2D<Luma<u8>>, levels: u8) -> ImageBuffer2D<Luma<u8>> {
+    assert!(levels >= 2, "Cannot posterize to less than 2 levels");
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(w, h, |(x, y)| {
+        Luma::new([nearest_level(f32::from(img.get_pixel(x, y)[0]), levels)])
+    })
+}
+
+/// Same as `posterize`, but diffuse the quantization error to the neighboring pixels with Floyd-Steinberg dithering,
+/// which better preserves the local brightness of the image.
+///
+/// # Panics
+///
+/// If `levels` is lower than 2.
+pub fn posterize_dithered(img: &Image2D<Luma<u8>>, levels: u8) -> ImageBuffer2D<Luma<u8>> {
+    assert!(levels >= 2, "Cannot posterize to less than 2 levels");
+    let (w, h) = img.dimensions();
+    let (w, h) = (w as usize, h as usize);
+    let mut work: Vec<f32> = img.iter().map(|p| f32::from(p[0])).collect();
+    let mut out = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let old = work[y * w + x];
+            let new = nearest_level(old, levels);
+            out.push(Luma::new([new]));
+            let err = old - f32::from(new);
+            if x + 1 < w {
+                work[y * w + x + 1] += err * 7. / 16.;
+            }
+            if y + 1 < h {
+                if x > 0 {
+                    work[(y + 1) * w + x - 1] += err * 3. / 16.;
+                }
+                work[(y + 1) * w + x] += err * 5. / 16.;
+                if x + 1 < w {
+                    work[(y + 1) * w + x + 1] += err / 16.;
+                }
+            }
+        }
+    }
+    ImageBuffer2D::from_vec(w as u32, h as u32, out).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mean(img: &Image2D<Luma<u8>>) -> f64 {
+        img.iter().map(|p| f64::from(p[0])).sum::<f64>() / f64::from(img.width() * img.height())
+    }
+
+    #[test]
+    fn test_posterize() {
+        let ramp = ImageBuffer2D::generate(256, 2, |(x, _)| Luma::new([x as u8]));
+        let two = posterize(&ramp, 2);
+        assert!(two.iter().all(|p| p[0] == 0 || p[0] == 255));
+        assert_eq!(two.get_pixel(127, 0), &Luma::new([0]));
+        assert_eq!(two.get_pixel(128, 0), &Luma::new([255]));
+
+        let four = posterize(&ramp, 4);
+        assert!(four.iter().all(|p| [0, 85, 170, 255].contains(&p[0])));
+        assert!(posterize(&four, 4) == four);
+    }
+
+    #[test]
+    fn test_posterize_dithered() {
+        let img = ImageBuffer2D::solid(32, 32, Luma::new([100u8]));
+        let plain = posterize(&img, 2);
+        let dithered = posterize_dithered(&img, 2);
+        assert!(dithered.iter().all(|p| p[0] == 0 || p[0] == 255));
+        assert!((mean(&dithered) - 100.).abs() < (mean(&plain) - 100.).abs());
+        assert!((mean(&dithered) - 100.).abs() < 2.);
+
+        let ramp = ImageBuffer2D::generate(64, 16, |(x, _)| Luma::new([(x * 4) as u8]));
+        let dithered = posterize_dithered(&ramp, 3);
+        assert!(dithered.iter().all(|p| [0, 128, 255].contains(&p[0])));
+        assert!(
+            (mean(&dithered) - mean(&ramp)).abs()
+                < (mean(&posterize(&ramp, 3)) - mean(&ramp)).abs()
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Contains template matching functions.
+
+use core::{Image2D, Image2DMut, ImageBuffer2D, Luma, RectNeighborhood};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Enumerate the methods used to compare a template with the image regions it is matched against.
+pub enum MatchMethod {
+    /// Sum of the squared differences. The best match is the lowest response.
+    SumSquaredDifferences,
+    /// Zero-mean normalized cross-correlation, in the `[-1, 1]` range. The best match is the highest response.
+    NormalizedCrossCorrelation,
+}
+
+/// Match a template against every position of an image. Return the response map, whose pixel `(x, y)` contains the
+/// result of the comparison of the template with the image region of the same size whose top left corner is
+/// `(x, y)`. The response dimensions are `(img.width() - template.width() + 1, img.height() - template.height() + 1)`,
+/// or empty if the template is larger than the image.
+///
+/// Regions or templates with a uniform value have a normalized cross-correlation of 0.
+pub fn match_template(
+    img: &Image2D<Luma<u8>>,
+    template: &Image2D<Luma<u8>>,
+    method: MatchMethod,
+) -> ImageBuffer2D<Luma<f64>> {
+    let (tw, th) = template.dimensions();
+    let nbh = match RectNeighborhood::new((tw, th), (0, 0)) {
+        Some(nbh) => nbh,
+        None => return ImageBuffer2D::new(0, 0),
+    };
+    let rw = (img.width() + 1).saturating_sub(tw);
+    let rh = (img.height() + 1).saturating_sub(th);
+    let mut response = ImageBuffer2D::new(rw, rh);
+
+    let n = f64::from(tw * th);
+    let tpl_mean = template.iter().map(|p| f64::from(p[0])).sum::<f64>() / n;
+    let tpl_norm = template
+        .iter()
+        .map(|p| (f64::from(p[0]) - tpl_mean).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    for ((x, y), win) in nbh.iter(img) {
+        let val = match method {
+            MatchMethod::SumSquaredDifferences => win
+                .iter()
+                .zip(template.iter())
+                .map(|(p, t)| (f64::from(p[0]) - f64::from(t[0])).powi(2))
+                .sum(),
+            MatchMethod::NormalizedCrossCorrelation => {
+                let mean = win.iter().map(|p| f64::from(p[0])).sum::<f64>() / n;
+                let (mut cross, mut sq) = (0., 0.);
+                for (p, t) in win.iter().zip(template.iter()) {
+                    let d = f64::from(p[0]) - mean;
+                    cross += d * (f64::from(t[0]) - tpl_mean);
+                    sq += d * d;
+                }
+                let denom = sq.sqrt() * tpl_norm;
+                if denom > 0. {
+                    cross / denom
+                } else {
+                    0.
+                }
+            }
+        };
+        *response.get_pixel_mut(x, y) = Luma::new([val]);
+    }
+    response
+}
+
+/// Return the coordinates of the best match in a response map computed by `match_template` with the given method,
+/// or `None` if the response is empty. Ties are resolved in favor of the first position in scanline order.
+pub fn best_match(response: &Image2D<Luma<f64>>, method: MatchMethod) -> Option<(u32, u32)> {
+    let mut best: Option<((u32, u32), f64)> = None;
+    for ((x, y), p) in response.enumerate_pixels() {
+        let better = match best {
+            None => true,
+            Some((_, v)) => match method {
+                MatchMethod::SumSquaredDifferences => p[0] < v,
+                MatchMethod::NormalizedCrossCorrelation => p[0] > v,
+            },
+        };
+        if better {
+            best = Some(((x, y), p[0]));
+        }
+    }
+    best.map(|(coords, _)| coords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::Rect;
+
+    fn test_images() -> (ImageBuffer2D<Luma<u8>>, ImageBuffer2D<Luma<u8>>) {
+        let template =
+            ImageBuffer2D::generate(5, 4, |(x, y)| Luma::new([(x * 40 + y * y * 13) as u8]));
+        let mut img = ImageBuffer2D::generate(30, 20, |(x, y)| {
+            Luma::new([((x * 7 + y * 3) % 23 * 5) as u8])
+        });
+        img.blit_rect(template.rect(), Rect::new(17, 9, 5, 4), &template)
+            .unwrap();
+        (img, template)
+    }
+
+    #[test]
+    fn test_ncc() {
+        let (img, template) = test_images();
+        let method = MatchMethod::NormalizedCrossCorrelation;
+        let response = match_template(&img, &template, method);
+        assert_eq!(response.dimensions(), (26, 17));
+        assert_eq!(best_match(&response, method), Some((17, 9)));
+        assert!((response.get_pixel(17, 9)[0] - 1.).abs() < 1e-9);
+        assert!(response
+            .iter()
+            .all(|p| p[0] >= -1. - 1e-9 && p[0] <= 1. + 1e-9));
+    }
+
+    #[test]
+    fn test_ssd() {
+        let (img, template) = test_images();
+        let method = MatchMethod::SumSquaredDifferences;
+        let response = match_template(&img, &template, method);
+        assert_eq!(best_match(&response, method), Some((17, 9)));
+        assert_eq!(response.get_pixel(17, 9)[0], 0.);
+    }
+
+    #[test]
+    fn test_template_too_large() {
+        let (img, template) = test_images();
+        let method = MatchMethod::SumSquaredDifferences;
+        let response = match_template(&template, &img, method);
+        assert_eq!(response.width() * response.height(), 0);
+        assert_eq!(best_match(&response, method), None);
+    }
+}
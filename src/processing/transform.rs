@@ -0,0 +1,406 @@
+//! Contains geometric transformations of images.
+
+use core::padding::Padding;
+use core::{Image2D, ImageBuffer2D, Luma, Pixel, Rect};
+use helper::generic::from_f64_clamped;
+use processing::gradient::sobel_magnitude;
+
+use num_traits::cast;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Interpolation method used when sampling an image at fractional coordinates.
+pub enum Interpolation {
+    /// Use the value of the nearest pixel.
+    Nearest,
+    /// Blend the values of the 4 surrounding pixels.
+    Bilinear,
+    /// Blend the values of the 16 surrounding pixels with a cubic (Catmull-Rom) kernel.
+    Bicubic,
+}
+
+// Sample an image at fractional coordinates, clamping them to the image borders.
+fn sample<P>(img: &Image2D<P>, x: f64, y: f64, interp: Interpolation) -> P
+where
+    P: Pixel,
+{
+    match interp {
+        Interpolation::Nearest => {
+            let x = x.round().max(0.).min(f64::from(img.width() - 1));
+            let y = y.round().max(0.).min(f64::from(img.height() - 1));
+            img.get_pixel(x as u32, y as u32).clone()
+        }
+        Interpolation::Bilinear => img.sample_bilinear(x as f32, y as f32),
+        Interpolation::Bicubic => img.sample_bicubic(x as f32, y as f32),
+    }
+}
+
+/// Resize an image to the given dimensions using the specified interpolation method. Pixel centers are aligned, i.e.
+/// the output pixel `(x, y)` samples the input image at `((x + 0.5) * w / out_w - 0.5, (y + 0.5) * h / out_h - 0.5)`.
+pub fn resize<P>(img: &Image2D<P>, out_size: (u32, u32), interp: Interpolation) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let (out_w, out_h) = out_size;
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return ImageBuffer2D::new(out_w, out_h);
+    }
+    let (sx, sy) = (
+        f64::from(w) / f64::from(out_w),
+        f64::from(h) / f64::from(out_h),
+    );
+    ImageBuffer2D::generate(out_w, out_h, |(x, y)| {
+        let src_x = (f64::from(x) + 0.5) * sx - 0.5;
+        let src_y = (f64::from(y) + 0.5) * sy - 0.5;
+        sample(img, src_x, src_y, interp)
+    })
+}
+
+/// Downsample an image by an integer factor, averaging each `factor x factor` block of pixels into one output pixel.
+/// The output image is `(w / factor, h / factor)`, remainder rows and columns being discarded. Averages are rounded
+/// for integer subpixel types.
+///
+/// **Panics** if `factor` is 0.
+pub fn downsample<P>(img: &Image2D<P>, factor: u32) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    assert!(
+        factor != 0,
+        "Downsampling factor must be strictly positive."
+    );
+    let (w, h) = img.dimensions();
+    let n = f64::from(factor * factor);
+    ImageBuffer2D::generate(w / factor, h / factor, |(x, y)| {
+        let mut sums = vec![0.; P::N_CHANNELS as usize];
+        for p in img.rect_iter(Rect::new(x * factor, y * factor, factor, factor)) {
+            for (s, c) in sums.iter_mut().zip(p.channels()) {
+                *s += cast::<P::Subpixel, f64>(*c).unwrap_or(0.);
+            }
+        }
+        let mut out = P::zero();
+        for (dst, s) in out.channels_mut().iter_mut().zip(sums) {
+            *dst = from_f64_clamped(s / n);
+        }
+        out
+    })
+}
+
+/// Rotate an image by 90 degrees clockwise.
+pub fn rotate90<P>(img: &Image2D<P>) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let (w, h) = img.dimensions();
+    ImageBuffer2D::generate(h, w, |(x, y)| img.get_pixel(y, h - 1 - x).clone())
+}
+
+/// Rotate an image clockwise by an arbitrary angle in radians around its center. The output image is sized to contain
+/// the whole rotated image, and its pixels that don't map back into the source image are set to `fill`.
+pub fn rotate<P>(
+    img: &Image2D<P>,
+    angle_rad: f64,
+    interp: Interpolation,
+    fill: P,
+) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    // Tolerance absorbing the rounding errors of the trigonometric functions, e.g. cos(pi / 2) != 0.
+    const EPS: f64 = 1e-9;
+    let (w, h) = (f64::from(img.width()), f64::from(img.height()));
+    let (sin, cos) = angle_rad.sin_cos();
+    let out_w = (w * cos.abs() + h * sin.abs() - EPS).ceil().max(0.);
+    let out_h = (w * sin.abs() + h * cos.abs() - EPS).ceil().max(0.);
+    let (cx, cy) = ((w - 1.) / 2., (h - 1.) / 2.);
+    let (out_cx, out_cy) = ((out_w - 1.) / 2., (out_h - 1.) / 2.);
+    ImageBuffer2D::generate(out_w as u32, out_h as u32, |(x, y)| {
+        let (dx, dy) = (f64::from(x) - out_cx, f64::from(y) - out_cy);
+        let (sx, sy) = (cos * dx + sin * dy + cx, -sin * dx + cos * dy + cy);
+        let in_bounds = match interp {
+            Interpolation::Nearest => {
+                let (rx, ry) = ((sx + EPS).round(), (sy + EPS).round());
+                rx >= 0. && ry >= 0. && rx < w && ry < h
+            }
+            Interpolation::Bilinear | Interpolation::Bicubic => {
+                sx >= -EPS && sy >= -EPS && sx <= w - 1. + EPS && sy <= h - 1. + EPS
+            }
+        };
+        if in_bounds {
+            sample(img, sx + EPS, sy + EPS, interp)
+        } else {
+            fill.clone()
+        }
+    })
+}
+
+/// Apply an affine transformation to an image.
+///
+/// `matrix` contains the first 2 rows `[a, b, c, d, e, f]` of the transformation matrix mapping the source coordinates
+/// `(x, y)` to the destination coordinates `(a * x + b * y + c, d * x + e * y + f)`. The output image is computed by
+/// mapping every pixel back to the source image and sampling it with the specified interpolation method. Pixels that
+/// map out of the source image are set to zero, as is the whole output if the matrix is not invertible.
+pub fn warp_affine<P>(
+    img: &Image2D<P>,
+    matrix: [f64; 6],
+    out_size: (u32, u32),
+    interp: Interpolation,
+) -> ImageBuffer2D<P>
+where
+    P: Pixel,
+{
+    let (out_w, out_h) = out_size;
+    let [a, b, c, d, e, f] = matrix;
+    let det = a * e - b * d;
+    let (w, h) = (f64::from(img.width()), f64::from(img.height()));
+    if det == 0. || img.width() == 0 || img.height() == 0 {
+        return ImageBuffer2D::new(out_w, out_h);
+    }
+
+    // Inverse of the linear part, the translation is undone before applying it.
+    let (ia, ib, id, ie) = (e / det, -b / det, -d / det, a / det);
+    ImageBuffer2D::generate(out_w, out_h, |(x, y)| {
+        let (dx, dy) = (f64::from(x) - c, f64::from(y) - f);
+        let (sx, sy) = (ia * dx + ib * dy, id * dx + ie * dy);
+        let in_bounds = match interp {
+            Interpolation::Nearest => {
+                sx.round() >= 0. && sy.round() >= 0. && sx.round() < w && sy.round() < h
+            }
+            Interpolation::Bilinear | Interpolation::Bicubic => {
+                sx >= 0. && sy >= 0. && sx <= w - 1. && sy <= h - 1.
+            }
+        };
+        if in_bounds {
+            sample(img, sx, sy, interp)
+        } else {
+            P::zero()
+        }
+    })
+}
+
+// Find the minimum energy vertical seam of an energy map by dynamic programming. Return the seam as the x coordinate
+// of its pixel on each row.
+fn find_vertical_seam(energy: &Image2D<Luma<f64>>) -> Vec<u32> {
+    let (w, h) = energy.dimensions();
+    let (w, h) = (w as usize, h as usize);
+    // Cumulative energy of the best seam ending at each pixel, row by row.
+    let mut cost = vec![0.; w * h];
+    for x in 0..w {
+        cost[x] = energy.get_pixel(x as u32, 0)[0];
+    }
+    for y in 1..h {
+        for x in 0..w {
+            let prev = &cost[(y - 1) * w..y * w];
+            let best = prev[x.saturating_sub(1)..(x + 2).min(w)]
+                .iter()
+                .cloned()
+                .fold(::std::f64::INFINITY, f64::min);
+            cost[y * w + x] = energy.get_pixel(x as u32, y as u32)[0] + best;
+        }
+    }
+
+    // Backtrack from the cheapest pixel of the last row.
+    let argmin = |row: &[f64], from: usize, to: usize| {
+        (from..to).fold(from, |best, x| if row[x] < row[best] { x } else { best })
+    };
+    let mut seam = vec![0; h];
+    let mut x = argmin(&cost[(h - 1) * w..], 0, w);
+    seam[h - 1] = x as u32;
+    for y in (0..h - 1).rev() {
+        x = argmin(
+            &cost[y * w..(y + 1) * w],
+            x.saturating_sub(1),
+            (x + 2).min(w),
+        );
+        seam[y] = x as u32;
+    }
+    seam
+}
+
+/// Reduce the width of an image to `target_w` with seam carving: the vertical seams of minimal energy, measured by
+/// the Sobel gradient magnitude, are removed one at a time so that the high contrast content of the image is
+/// preserved. If `target_w` is not smaller than the image width, a copy of the image is returned.
+pub fn seam_carve(img: &Image2D<Luma<u8>>, target_w: u32) -> ImageBuffer2D<Luma<u8>> {
+    let mut out = img.to_owned();
+    while out.width() > target_w && out.height() > 0 {
+        let seam = find_vertical_seam(&sobel_magnitude(&out, Padding::Replicate));
+        let (w, h) = out.dimensions();
+        out = ImageBuffer2D::generate(w - 1, h, |(x, y)| {
+            let src_x = if x < seam[y as usize] { x } else { x + 1 };
+            *out.get_pixel(src_x, y)
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{Luma, Rgb};
+
+    #[test]
+    fn test_downsample() {
+        let img = ImageBuffer2D::generate(4, 4, |(x, y)| Luma::new([(x + 4 * y) as u8]));
+        let expected = ImageBuffer2D::generate(2, 2, |(x, y)| {
+            // Quadrant means are 2.5 + 2 * x + 8 * y, rounded half away from zero.
+            Luma::new([(3 + 2 * x + 8 * y) as u8])
+        });
+        assert_eq!(downsample(&img, 2), expected);
+        assert_eq!(downsample(&img, 1), img);
+
+        let img = ImageBuffer2D::generate(5, 3, |(x, y)| Rgb::new([x as f32, y as f32, 1.]));
+        let down = downsample(&img, 2);
+        assert_eq!(down.dimensions(), (2, 1));
+        assert_eq!(down.get_pixel(0, 0), &Rgb::new([0.5, 0.5, 1.]));
+        assert_eq!(down.get_pixel(1, 0), &Rgb::new([2.5, 0.5, 1.]));
+    }
+
+    #[test]
+    fn test_rotate90() {
+        let img = ImageBuffer2D::generate(3, 2, |(x, y)| Luma::new([(x + 10 * y) as u8]));
+        let rotated = rotate90(&img);
+        assert_eq!(rotated.dimensions(), (2, 3));
+        assert_eq!(rotated.get_pixel(0, 0), &Luma::new([10]));
+        assert_eq!(rotated.get_pixel(1, 0), &Luma::new([0]));
+        assert_eq!(rotated.get_pixel(0, 2), &Luma::new([12]));
+    }
+
+    #[test]
+    fn test_rotate() {
+        use std::f64::consts::PI;
+
+        let img = ImageBuffer2D::generate(7, 5, |(x, y)| Luma::new([f64::from(x * x + 3 * y)]));
+        let fill = Luma::new([-1.]);
+        for &interp in &[Interpolation::Bilinear, Interpolation::Bicubic] {
+            let rotated = rotate(&img, 2. * PI, interp, fill);
+            assert!(rotated.approx_eq(&img, 1e-6));
+        }
+
+        let img = ImageBuffer2D::generate(3, 7, |(x, y)| Luma::new([(x + 10 * y) as u8]));
+        for &interp in &[
+            Interpolation::Nearest,
+            Interpolation::Bilinear,
+            Interpolation::Bicubic,
+        ] {
+            assert_eq!(
+                rotate(&img, PI / 2., interp, Luma::new([255])),
+                rotate90(&img)
+            );
+        }
+
+        // The output contains the whole rotated image, corners are filled.
+        let img = ImageBuffer2D::solid(10, 10, Luma::new([1u8]));
+        let rotated = rotate(&img, PI / 4., Interpolation::Nearest, Luma::new([0]));
+        assert_eq!(rotated.dimensions(), (15, 15));
+        assert_eq!(rotated.get_pixel(0, 0), &Luma::new([0]));
+        assert_eq!(rotated.get_pixel(7, 7), &Luma::new([1]));
+        assert_eq!(rotated.get_pixel(7, 0), &Luma::new([1]));
+    }
+
+    #[test]
+    fn test_warp_identity() {
+        let img = ImageBuffer2D::generate(7, 5, |(x, y)| Rgb::new([x as u8, y as u8, 42]));
+        let identity = [1., 0., 0., 0., 1., 0.];
+        for &interp in &[
+            Interpolation::Nearest,
+            Interpolation::Bilinear,
+            Interpolation::Bicubic,
+        ] {
+            assert_eq!(warp_affine(&img, identity, (7, 5), interp), img);
+        }
+    }
+
+    #[test]
+    fn test_warp_rotate90() {
+        let img = ImageBuffer2D::generate(7, 5, |(x, y)| Luma::new([(x + 10 * y) as u8]));
+        let m = [0., -1., 4., 1., 0., 0.];
+        let warped = warp_affine(&img, m, (5, 7), Interpolation::Nearest);
+        assert_eq!(warped, rotate90(&img));
+    }
+
+    #[test]
+    fn test_warp_translate() {
+        let img =
+            ImageBuffer2D::generate(4, 4, |(x, y)| Luma::new([1. + x as f32 + 4. * y as f32]));
+        let m = [1., 0., 1.5, 0., 1., -1.];
+        let warped = warp_affine(&img, m, (4, 4), Interpolation::Bilinear);
+        assert_eq!(warped.get_pixel(0, 0), &Luma::new([0.]));
+        assert_eq!(warped.get_pixel(1, 3), &Luma::new([0.]));
+        assert_eq!(warped.get_pixel(2, 0), &Luma::new([5.5]));
+        assert_eq!(warped.get_pixel(3, 2), &Luma::new([14.5]));
+    }
+
+    #[test]
+    fn test_warp_singular() {
+        let img = ImageBuffer2D::solid(4, 4, Luma::new([1u8]));
+        let warped = warp_affine(
+            &img,
+            [1., 2., 0., 2., 4., 0.],
+            (3, 3),
+            Interpolation::Nearest,
+        );
+        assert_eq!(warped, ImageBuffer2D::new(3, 3));
+    }
+
+    #[test]
+    fn test_resize_nearest() {
+        let img = ImageBuffer2D::generate(2, 2, |(x, y)| Luma::new([(x + 2 * y) as u8]));
+        let up = resize(&img, (4, 4), Interpolation::Nearest);
+        let expected =
+            ImageBuffer2D::generate(4, 4, |(x, y)| Luma::new([(x / 2 + 2 * (y / 2)) as u8]));
+        assert_eq!(up, expected);
+        assert_eq!(resize(&up, (2, 2), Interpolation::Nearest), img);
+    }
+
+    // Sum of the squared second differences along the rows of an image.
+    fn roughness(img: &ImageBuffer2D<Luma<f64>>) -> f64 {
+        let (w, h) = img.dimensions();
+        let mut r = 0.;
+        for y in 0..h {
+            for x in 1..w - 1 {
+                let d2 = img.get_pixel(x - 1, y)[0] - 2. * img.get_pixel(x, y)[0]
+                    + img.get_pixel(x + 1, y)[0];
+                r += d2 * d2;
+            }
+        }
+        r
+    }
+
+    #[test]
+    fn test_resize_bicubic() {
+        let img = ImageBuffer2D::generate(8, 2, |(x, _)| Luma::new([f64::from(x * x)]));
+        let bilinear = resize(&img, (64, 2), Interpolation::Bilinear);
+        let bicubic = resize(&img, (64, 2), Interpolation::Bicubic);
+        assert!(bicubic.iter().all(|p| p[0] >= 0. && p[0] <= 49.));
+        assert!(roughness(&bicubic) < roughness(&bilinear));
+
+        let img = ImageBuffer2D::generate(8, 2, |(x, _)| Luma::new([(x * x * 5) as u8]));
+        let bicubic = resize(&img, (64, 2), Interpolation::Bicubic);
+        assert!(bicubic.iter().all(|p| p[0] <= 245));
+    }
+
+    #[test]
+    fn test_seam_carve() {
+        // Flat background with a bright vertical bar, and a noisy texture on the right.
+        let img = ImageBuffer2D::generate(24, 10, |(x, y)| {
+            Luma::new([match x {
+                9 | 10 => 255,
+                x if x >= 18 => ((x * 37 + y * 91) % 200) as u8,
+                _ => 40,
+            }])
+        });
+        let carved = seam_carve(&img, 17);
+        assert_eq!(carved.dimensions(), (17, 10));
+        // Seams go through the flat regions, so the bar and the texture survive unchanged.
+        for y in 0..10 {
+            let row: Vec<u8> = (0..17).map(|x| carved.get_pixel(x, y)[0]).collect();
+            assert_eq!(row.iter().filter(|&&v| v == 255).count(), 2);
+            let texture: Vec<u8> = (18..24).map(|x| img.get_pixel(x, y)[0]).collect();
+            assert_eq!(&row[11..], &texture[..]);
+        }
+
+        assert_eq!(seam_carve(&img, 24), img);
+        assert_eq!(seam_carve(&img, 30), img);
+        assert_eq!(seam_carve(&img, 0).dimensions(), (0, 10));
+    }
+}
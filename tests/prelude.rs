@@ -0,0 +1,22 @@
+extern crate ndimage;
+extern crate tempfile;
+
+use ndimage::prelude::*;
+
+#[test]
+fn test_prelude() {
+    let mut img = ImageBuffer2D::generate(8, 6, |(x, y)| Rgb::new([x as u8 * 30, y as u8 * 40, 0]));
+    img.fill_rect(Rect::new(2, 2, 3, 2), &Rgb::new([255, 255, 255]));
+    let padded = ndimage::core::padding::pad(&img, 1, Padding::Replicate);
+    assert_eq!(padded.dimensions(), (10, 8));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prelude.png");
+    save(&path, &img).unwrap();
+    let read: DynamicImage = open(&path).unwrap();
+    assert_eq!(read.as_rgb_u8_ref(), Some(&img));
+
+    // Method syntax resolves to the inherent `to_owned`, despite both `Image2D` and `ToOwned` being in scope.
+    let owned = img.to_owned();
+    assert_eq!(owned, img);
+}